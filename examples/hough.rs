@@ -0,0 +1,40 @@
+//! Demonstrates detecting lines in an image using the Hough transform, and
+//! drawing the detected lines over the original image.
+
+extern crate image;
+extern crate imageproc;
+
+use std::env;
+use std::path::Path;
+use image::{open, Rgb};
+use imageproc::edges::canny;
+use imageproc::hough::{detect_lines, draw_polar_lines, LineDetectionOptions};
+use imageproc::map::map_colors;
+
+fn main() {
+
+    let arg = if env::args().count() == 2 {
+            env::args().nth(1).unwrap()
+        } else {
+            panic!("Please enter an input file")
+        };
+    let path = Path::new(&arg);
+
+    // Load a image::DynamicImage and convert it to a image::GrayImage
+    let input = open(path)
+        .expect(&format!("Could not load image at {:?}", path))
+        .to_luma();
+
+    // Detect edges using Canny algorithm
+    let edges = canny(&input, 50.0, 100.0);
+    edges.save(path.with_file_name("edges.png")).unwrap();
+
+    // Detect lines using Hough transform
+    let options = LineDetectionOptions::new(80, 8);
+    let lines = detect_lines(&edges, options);
+
+    // Draw the detected lines in red over a color copy of the input
+    let color_input = map_colors(&input, |p| Rgb([p[0]; 3]));
+    let lines_image = draw_polar_lines(&color_input, &lines, Rgb([255, 0, 0]));
+    lines_image.save(path.with_file_name("lines.png")).unwrap();
+}
@@ -1,10 +1,12 @@
-//! Functions for affine transformations of images.
+//! Functions for affine and projective transformations of images.
 
 use image::{Pixel, GenericImage, ImageBuffer};
 use definitions::{Clamp, HasBlack, Image};
-use math::cast;
-use nalgebra::{Affine2,Point2};
+use math::{cast, round_clamp};
+use nalgebra::{Affine2,DMatrix,DVector,Matrix3,Point2,Vector3};
 use conv::ValueInto;
+use rect::Rect;
+use rayon::prelude::*;
 
 /// How to handle pixels whose pre-image lies between input pixels.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -15,6 +17,12 @@ pub enum Interpolation {
     /// Bilinearly interpolate between the four pixels
     /// closest to the pre-image of the output pixel.
     Bilinear,
+    /// Bicubically interpolate between the sixteen pixels closest
+    /// to the pre-image of the output pixel, using the Catmull-Rom spline.
+    Bicubic,
+    /// Interpolate between the thirty-six pixels closest to the
+    /// pre-image of the output pixel, using a Lanczos kernel with a = 3.
+    Lanczos3,
 }
 
 /// Applies an affine transformation to an image, or None if the provided
@@ -42,6 +50,95 @@ pub fn affine_with_default<P>(image: &Image<P>,
                               -> Option<Image<P>>
     where P: Pixel + 'static,
           <P as Pixel>::Subpixel: ValueInto<f32> + Clamp<f32>
+{
+    let (width, height) = image.dimensions();
+    affine_impl(image, affine, default, interpolation, (0, 0), (width, height))
+}
+
+/// Returns the smallest rectangle containing the image under `affine` of every
+/// pixel in a `width` by `height` image, treating pixels as points at integer
+/// coordinates.
+///
+/// Panics if `width` or `height` is 0.
+pub fn affine_bounding_box(affine: Affine2<f32>, width: u32, height: u32) -> Rect {
+    use std::f32;
+
+    assert!(width > 0 && height > 0, "image dimensions must be strictly positive");
+
+    // Tolerance for rounding errors, e.g. in rotations by multiples of a right angle
+    let tolerance = 1e-3;
+    let (right, bottom) = ((width - 1) as f32, (height - 1) as f32);
+
+    let corners = [(0f32, 0f32), (right, 0f32), (0f32, bottom), (right, bottom)];
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+    for &(x, y) in &corners {
+        let p = affine * Point2::new(x, y);
+        min_x = min_x.min(p[0]);
+        min_y = min_y.min(p[1]);
+        max_x = max_x.max(p[0]);
+        max_y = max_y.max(p[1]);
+    }
+
+    let left = (min_x + tolerance).floor() as i32;
+    let top = (min_y + tolerance).floor() as i32;
+    let right = (max_x - tolerance).ceil() as i32;
+    let bottom = (max_y - tolerance).ceil() as i32;
+
+    Rect::at(left, top).of_size((right - left + 1) as u32, (bottom - top + 1) as u32)
+}
+
+/// Applies an affine transformation to an image, or None if the provided
+/// transformation is not invertible.
+/// The output image is just large enough to contain the transformed input, and its
+/// top left pixel is at the top left of `affine_bounding_box(affine, width, height)`.
+/// Output pixels whose pre-image lies outside the input image are set to default.
+pub fn affine_to_bounding_box<P>(image: &Image<P>,
+                                 affine: Affine2<f32>,
+                                 default: P,
+                                 interpolation: Interpolation)
+                                 -> Option<Image<P>>
+    where P: Pixel + 'static,
+          <P as Pixel>::Subpixel: ValueInto<f32> + Clamp<f32>
+{
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 {
+        return affine_with_default(image, affine, default, interpolation);
+    }
+    let bounds = affine_bounding_box(affine, width, height);
+    affine_to_region(image, affine, default, interpolation, bounds)
+}
+
+/// Applies an affine transformation to an image, or None if the provided
+/// transformation is not invertible.
+/// The output image has the size of `region`, and output pixel (x, y) is the
+/// transformed image at (x + region.left(), y + region.top()). Output pixels
+/// whose pre-image lies outside the input image are set to default.
+pub fn affine_to_region<P>(image: &Image<P>,
+                           affine: Affine2<f32>,
+                           default: P,
+                           interpolation: Interpolation,
+                           region: Rect)
+                           -> Option<Image<P>>
+    where P: Pixel + 'static,
+          <P as Pixel>::Subpixel: ValueInto<f32> + Clamp<f32>
+{
+    affine_impl(image,
+                affine,
+                default,
+                interpolation,
+                (region.left(), region.top()),
+                (region.width(), region.height()))
+}
+
+fn affine_impl<P>(image: &Image<P>,
+                  affine: Affine2<f32>,
+                  default: P,
+                  interpolation: Interpolation,
+                  offset: (i32, i32),
+                  size: (u32, u32))
+                  -> Option<Image<P>>
+    where P: Pixel + 'static,
+          <P as Pixel>::Subpixel: ValueInto<f32> + Clamp<f32>
 {
     let inverse: Affine2<f32>;
     match affine.try_inverse() {
@@ -49,23 +146,72 @@ pub fn affine_with_default<P>(image: &Image<P>,
         Some(inv) => inverse = inv,
     }
 
-    let (width, height) = image.dimensions();
+    let (width, height) = size;
     let mut out = ImageBuffer::new(width, height);
 
     for y in 0..height {
         for x in 0..width {
 
-            let preimage = inverse*Point2::new(x as f32, y as f32);
+            let preimage = inverse*Point2::new((x as i32 + offset.0) as f32,
+                                               (y as i32 + offset.1) as f32);
             let px = preimage[0];
             let py = preimage[1];
 
-            let pix = match interpolation {
-                Interpolation::Nearest => {
-                    nearest(image, px, py, default)
-                }
-                Interpolation::Bilinear => {
-                    interpolate(image, px, py, default)
-                }
+            let pix = sample(image, px, py, default, interpolation);
+            unsafe { out.unsafe_put_pixel(x, y, pix); }
+        }
+    }
+
+    Some(out)
+}
+
+/// Applies a projective transformation to an image, or None if the provided
+/// transformation is not invertible. The transformation maps homogeneous input
+/// coordinates (x, y, 1) to homogeneous output coordinates.
+/// The output image has the same dimensions as the input. Output pixels
+/// whose pre-image lies outside the input image are set to black.
+pub fn warp_perspective<P>(image: &Image<P>,
+                           projection: Matrix3<f32>,
+                           interpolation: Interpolation)
+                           -> Option<Image<P>>
+    where P: Pixel + HasBlack + 'static,
+          <P as Pixel>::Subpixel: ValueInto<f32> + Clamp<f32>
+{
+    warp_perspective_with_default(image, projection, P::black(), interpolation)
+}
+
+/// Applies a projective transformation to an image, or None if the provided
+/// transformation is not invertible. The transformation maps homogeneous input
+/// coordinates (x, y, 1) to homogeneous output coordinates.
+/// The output image has the same dimensions as the input. Output pixels
+/// whose pre-image lies outside the input image, or at infinity, are set to default.
+pub fn warp_perspective_with_default<P>(image: &Image<P>,
+                                        projection: Matrix3<f32>,
+                                        default: P,
+                                        interpolation: Interpolation)
+                                        -> Option<Image<P>>
+    where P: Pixel + 'static,
+          <P as Pixel>::Subpixel: ValueInto<f32> + Clamp<f32>
+{
+    let inverse: Matrix3<f32>;
+    match projection.try_inverse() {
+        None => return None,
+        Some(inv) => inverse = inv,
+    }
+
+    let (width, height) = image.dimensions();
+    let mut out = ImageBuffer::new(width, height);
+
+    for y in 0..height {
+        for x in 0..width {
+
+            let preimage = inverse*Vector3::new(x as f32, y as f32, 1f32);
+            let pix = if preimage[2] == 0f32 {
+                default
+            } else {
+                let px = preimage[0] / preimage[2];
+                let py = preimage[1] / preimage[2];
+                sample(image, px, py, default, interpolation)
             };
             unsafe { out.unsafe_put_pixel(x, y, pix); }
         }
@@ -74,6 +220,52 @@ pub fn affine_with_default<P>(image: &Image<P>,
     Some(out)
 }
 
+/// Computes the projective transformation mapping each point in `from` to the
+/// corresponding point in `to`, normalised so that its bottom right entry is 1.
+/// Returns None if no such transformation exists, e.g. if three of the points
+/// in either set are collinear.
+pub fn homography_from_points(from: [(f32, f32); 4], to: [(f32, f32); 4]) -> Option<Matrix3<f32>> {
+    // Each correspondence gives two linear equations in the eight unknown entries
+    // h11, h12, h13, h21, h22, h23, h31, h32 of the transformation.
+    let mut a = Vec::with_capacity(64);
+    let mut b = Vec::with_capacity(8);
+    for (&(x, y), &(u, v)) in from.iter().zip(to.iter()) {
+        let (x, y, u, v) = (x as f64, y as f64, u as f64, v as f64);
+        a.extend_from_slice(&[x, y, 1.0, 0.0, 0.0, 0.0, -u * x, -u * y]);
+        a.extend_from_slice(&[0.0, 0.0, 0.0, x, y, 1.0, -v * x, -v * y]);
+        b.push(u);
+        b.push(v);
+    }
+
+    let inverse = match DMatrix::from_row_slice(8, 8, &a).try_inverse() {
+        None => return None,
+        Some(inv) => inv,
+    };
+    let h = inverse * DVector::from_column_slice(8, &b);
+
+    let projection = Matrix3::new(
+        h[0] as f32, h[1] as f32, h[2] as f32,
+        h[3] as f32, h[4] as f32, h[5] as f32,
+        h[6] as f32, h[7] as f32, 1f32);
+
+    // The solver does not detect all singular systems, so check that
+    // the result is itself invertible and maps the points as requested.
+    if projection.try_inverse().is_none() {
+        return None;
+    }
+    for (&(x, y), &(u, v)) in from.iter().zip(to.iter()) {
+        let image = projection * Vector3::new(x, y, 1f32);
+        let tolerance = 1e-3 * (1f32 + u.abs().max(v.abs()));
+        if image[2] == 0f32
+            || (image[0] / image[2] - u).abs() > tolerance
+            || (image[1] / image[2] - v).abs() > tolerance {
+            return None;
+        }
+    }
+
+    Some(projection)
+}
+
 /// Rotate an image clockwise about provided center by theta radians.
 /// The output image has the same dimensions as the input. Output pixels
 /// whose pre-image lies outside the input image are black.
@@ -121,6 +313,8 @@ pub fn rotate_with_default<P>(image: &Image<P>,
     match interpolation {
         Interpolation::Nearest => rotate_nearest(image, center, theta, default),
         Interpolation::Bilinear => rotate_bilinear(image, center, theta, default),
+        Interpolation::Bicubic | Interpolation::Lanczos3 =>
+            rotate_interpolated(image, center, theta, default, interpolation),
     }
 }
 
@@ -193,6 +387,41 @@ fn rotate_bilinear<P>(image: &Image<P>,
     out
 }
 
+fn rotate_interpolated<P>(image: &Image<P>,
+                          center: (f32, f32),
+                          theta: f32,
+                          default: P,
+                          interpolation: Interpolation)
+                          -> Image<P>
+    where P: Pixel + 'static,
+          <P as Pixel>::Subpixel: ValueInto<f32> + Clamp<f32>
+{
+    let (width, height) = image.dimensions();
+    let mut out = ImageBuffer::new(width, height);
+
+    let cos_theta = theta.cos();
+    let sin_theta = theta.sin();
+    let center_x = center.0;
+    let center_y = center.1;
+
+    for y in 0..height {
+        let dy = y as f32 - center_y;
+        let mut px = center_x + sin_theta * dy - cos_theta * center_x;
+        let mut py = center_y + cos_theta * dy + sin_theta * center_x;
+
+        for x in 0..width {
+
+            let pix = sample(image, px, py, default, interpolation);
+            unsafe { out.unsafe_put_pixel(x, y, pix); }
+
+            px += cos_theta;
+            py -= sin_theta;
+        }
+    }
+
+    out
+}
+
 /// Translates the input image by t. Note that image coordinates increase from
 /// top left to bottom right. Output pixels whose pre-image are not in the input
 /// image are set to the boundary pixel in the input image nearest to their pre-image.
@@ -224,6 +453,152 @@ pub fn translate<P>(image: &Image<P>, t: (i32, i32)) -> Image<P>
     out
 }
 
+/// Filter used to compute output pixels when resizing an image.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ResizeFilter {
+    /// Choose the input pixel whose centre is nearest to the
+    /// centre of the output pixel.
+    Nearest,
+    /// Interpolate using a triangle kernel.
+    Bilinear,
+    /// Interpolate using the Catmull-Rom cubic kernel.
+    Bicubic,
+    /// Interpolate using a Lanczos kernel with a = 3.
+    Lanczos3,
+    /// Average the input pixels covered by each output pixel, weighted
+    /// by the area of overlap. This is best suited to downsampling.
+    Area,
+}
+
+/// Resizes an image to the given dimensions.
+///
+/// Input and output pixels are treated as unit squares, so that the
+/// centre of output pixel x lies at (x + 0.5) * input_width / width - 0.5 in input
+/// coordinates. When downsampling, the Bilinear, Bicubic and Lanczos3 kernels are
+/// stretched by the scale factor to avoid aliasing. Input pixels outside the image
+/// are replaced by the nearest pixel inside it. Results are clamped to the range of the
+/// subpixel type, and rounded to the nearest integer for integer subpixel types. Rows of the
+/// output are computed in parallel.
+///
+/// Panics if the input image is empty but the requested output is not.
+pub fn resize<P>(image: &Image<P>, width: u32, height: u32, filter: ResizeFilter) -> Image<P>
+    where P: Pixel + Send + Sync + 'static,
+          <P as Pixel>::Subpixel: ValueInto<f32> + Clamp<f32> + Send + Sync
+{
+    let mut out = ImageBuffer::new(width, height);
+    if width == 0 || height == 0 {
+        return out;
+    }
+
+    let (in_width, in_height) = image.dimensions();
+    assert!(in_width > 0 && in_height > 0, "cannot resize an empty image to a non-empty size");
+
+    let num_channels = P::channel_count() as usize;
+    let x_weights = resampling_weights(in_width, width, filter);
+    let y_weights = resampling_weights(in_height, height, filter);
+
+    // Resample each input row horizontally
+    let row_len = width as usize * num_channels;
+    let mut horizontal = vec![0f32; row_len * in_height as usize];
+    horizontal.par_chunks_mut(row_len).enumerate().for_each(|(y, row)| {
+        for (x, &(start, ref weights)) in x_weights.iter().enumerate() {
+            for (i, w) in weights.iter().enumerate() {
+                let pix = unsafe { image.unsafe_get_pixel((start + i) as u32, y as u32) };
+                for c in 0..num_channels {
+                    row[x * num_channels + c] += w * cast::<_, f32>(pix.channels()[c]);
+                }
+            }
+        }
+    });
+
+    // Then resample the result vertically
+    out.par_chunks_mut(row_len).enumerate().for_each(|(y, row)| {
+        let (start, ref weights) = y_weights[y];
+        let mut acc = vec![0f32; row_len];
+        for (i, w) in weights.iter().enumerate() {
+            let input_row = &horizontal[(start + i) * row_len..(start + i + 1) * row_len];
+            for (a, v) in acc.iter_mut().zip(input_row.iter()) {
+                *a += w * v;
+            }
+        }
+        for (o, a) in row.iter_mut().zip(acc.iter()) {
+            *o = round_clamp(*a);
+        }
+    });
+
+    out
+}
+
+/// For each output coordinate, the first input coordinate it depends on
+/// and the weights of that coordinate and its successors. Weights sum to one.
+fn resampling_weights(in_len: u32, out_len: u32, filter: ResizeFilter) -> Vec<(usize, Vec<f32>)> {
+    let scale = in_len as f32 / out_len as f32;
+    let last = in_len as i64 - 1;
+
+    let (radius, kernel): (f32, fn(f32) -> f32) = match filter {
+        ResizeFilter::Nearest => {
+            return (0..out_len).map(|i| {
+                let nearest = ((i as f32 + 0.5) * scale).floor() as i64;
+                (nearest.min(last) as usize, vec![1f32])
+            }).collect();
+        }
+        ResizeFilter::Area => {
+            return (0..out_len).map(|i| {
+                let lo = i as f32 * scale;
+                let hi = (i + 1) as f32 * scale;
+                let start = (lo.floor() as i64).min(last);
+                let end = ((hi.ceil() as i64) - 1).min(last).max(start);
+                let mut weights: Vec<f32> = (start..end + 1).map(|j| {
+                    (hi.min((j + 1) as f32) - lo.max(j as f32)).max(0f32)
+                }).collect();
+                normalize_weights(&mut weights);
+                (start as usize, weights)
+            }).collect();
+        }
+        ResizeFilter::Bilinear => (1f32, triangle),
+        ResizeFilter::Bicubic => (2f32, catmull_rom),
+        ResizeFilter::Lanczos3 => (3f32, lanczos3),
+    };
+
+    let filter_scale = scale.max(1f32);
+    (0..out_len).map(|i| {
+        let centre = (i as f32 + 0.5) * scale - 0.5;
+        let lo = (centre - radius * filter_scale).ceil() as i64;
+        let hi = (centre + radius * filter_scale).floor() as i64;
+        let start = lo.max(0).min(last);
+        let end = hi.min(last).max(start);
+
+        let mut weights = vec![0f32; (end - start + 1) as usize];
+        for j in lo..hi + 1 {
+            let index = j.max(start).min(end) - start;
+            weights[index as usize] += kernel((j as f32 - centre) / filter_scale);
+        }
+        normalize_weights(&mut weights);
+        (start as usize, weights)
+    }).collect()
+}
+
+/// Scales weights to sum to one, or replaces them with a single
+/// unit weight if they sum to zero.
+fn normalize_weights(weights: &mut [f32]) {
+    let sum: f32 = weights.iter().sum();
+    if sum == 0f32 {
+        for w in weights.iter_mut() {
+            *w = 0f32;
+        }
+        weights[0] = 1f32;
+    } else {
+        for w in weights.iter_mut() {
+            *w /= sum;
+        }
+    }
+}
+
+/// The triangle kernel, as used for linear interpolation.
+fn triangle(x: f32) -> f32 {
+    (1f32 - x.abs()).max(0f32)
+}
+
 fn blend<P>(top_left: P,
             top_right: P,
             bottom_left: P,
@@ -273,6 +648,101 @@ fn interpolate<P>(image: &Image<P>, x: f32, y: f32, default: P) -> P
     }
 }
 
+fn sample<P>(image: &Image<P>, x: f32, y: f32, default: P, interpolation: Interpolation) -> P
+    where P: Pixel + 'static,
+          <P as Pixel>::Subpixel: ValueInto<f32> + Clamp<f32>
+{
+    match interpolation {
+        Interpolation::Nearest => nearest(image, x, y, default),
+        Interpolation::Bilinear => interpolate(image, x, y, default),
+        Interpolation::Bicubic => interpolate_separable(image, x, y, default, 2, catmull_rom),
+        Interpolation::Lanczos3 => interpolate_separable(image, x, y, default, 3, lanczos3),
+    }
+}
+
+/// The Catmull-Rom cubic convolution kernel.
+fn catmull_rom(x: f32) -> f32 {
+    let x = x.abs();
+    if x < 1f32 {
+        1.5 * x * x * x - 2.5 * x * x + 1.0
+    } else if x < 2f32 {
+        -0.5 * x * x * x + 2.5 * x * x - 4.0 * x + 2.0
+    } else {
+        0f32
+    }
+}
+
+/// The Lanczos kernel with a = 3.
+fn lanczos3(x: f32) -> f32 {
+    use std::f32;
+
+    let x = x.abs();
+    if x == 0f32 {
+        1f32
+    } else if x.fract() == 0f32 {
+        // Avoid rounding errors from sin at multiples of pi
+        0f32
+    } else if x < 3f32 {
+        let px = f32::consts::PI * x;
+        3f32 * px.sin() * (px / 3f32).sin() / (px * px)
+    } else {
+        0f32
+    }
+}
+
+/// Interpolates using a separable kernel supported on (-radius, radius), with weights
+/// normalised to sum to one. As for bilinear interpolation, returns default if any of the
+/// four pixels nearest to (x, y) is outside the image. Other pixels outside the image
+/// are replaced by the nearest pixel inside it.
+fn interpolate_separable<P, K>(image: &Image<P>, x: f32, y: f32, default: P, radius: i32, kernel: K) -> P
+    where P: Pixel + 'static,
+          <P as Pixel>::Subpixel: ValueInto<f32> + Clamp<f32>,
+          K: Fn(f32) -> f32
+{
+    use std::cmp;
+
+    let left = x.floor();
+    let top = y.floor();
+
+    let (width, height) = image.dimensions();
+    if left < 0f32 || left + 1f32 >= width as f32 || top < 0f32 || top + 1f32 >= height as f32 {
+        return default;
+    }
+
+    let (left, top) = (left as i32, top as i32);
+    let taps = (2 * radius) as usize;
+    let mut x_weights = [0f32; 6];
+    let mut y_weights = [0f32; 6];
+    for i in 0..taps {
+        let offset = i as i32 - radius + 1;
+        x_weights[i] = kernel(x - (left + offset) as f32);
+        y_weights[i] = kernel(y - (top + offset) as f32);
+    }
+    let x_sum: f32 = x_weights[..taps].iter().sum();
+    let y_sum: f32 = y_weights[..taps].iter().sum();
+
+    let num_channels = P::channel_count() as usize;
+    let mut acc = [0f32; 4];
+    for (j, y_weight) in y_weights[..taps].iter().enumerate() {
+        let py = cmp::max(0, cmp::min(top + j as i32 - radius + 1, height as i32 - 1)) as u32;
+        for (i, x_weight) in x_weights[..taps].iter().enumerate() {
+            let px = cmp::max(0, cmp::min(left + i as i32 - radius + 1, width as i32 - 1)) as u32;
+            let weight = x_weight * y_weight;
+            let pix = unsafe { image.unsafe_get_pixel(px, py) };
+            for c in 0..num_channels {
+                acc[c] += weight * cast::<_, f32>(pix.channels()[c]);
+            }
+        }
+    }
+
+    let norm = x_sum * y_sum;
+    let mut out = unsafe { image.unsafe_get_pixel(left as u32, top as u32) };
+    for c in 0..num_channels {
+        out.channels_mut()[c] = round_clamp(acc[c] / norm);
+    }
+    out
+}
+
 fn nearest<P: Pixel + 'static>(image: &Image<P>, x: f32, y: f32, default: P) -> P
 {
     let rx = x.round();
@@ -290,10 +760,14 @@ fn nearest<P: Pixel + 'static>(image: &Image<P>, x: f32, y: f32, default: P) ->
 #[cfg(test)]
 mod test {
 
-    use super::{affine, rotate_bilinear, rotate_nearest, translate, Interpolation};
+    use super::{affine, affine_bounding_box, affine_to_bounding_box, affine_to_region,
+                affine_with_default, homography_from_points, rotate_about_center, rotate_bilinear, rotate_nearest,
+                rotate_with_default, resize, translate, ResizeFilter, warp_perspective, warp_perspective_with_default,
+                Interpolation};
     use utils::gray_bench_image;
-    use image::{GrayImage, ImageBuffer, Luma};
-    use nalgebra::{Affine2,Matrix3};
+    use image::{GrayImage, ImageBuffer, Luma, Rgb, RgbImage};
+    use nalgebra::{Affine2,Matrix3,Vector3};
+    use rect::Rect;
     use test;
 
     #[test]
@@ -482,4 +956,450 @@ mod test {
             test::black_box(transformed);
         });
     }
+
+    #[test]
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    fn test_warp_perspective_matches_affine() {
+        let image = gray_bench_image(10, 8);
+        let matrix = Matrix3::new(
+            0.9, 0.2, 1.5,
+            -0.1, 1.1, -0.5,
+            0.0, 0.0, 1.0,
+        );
+
+        for &interpolation in &[Interpolation::Nearest, Interpolation::Bilinear] {
+            let expected = affine(&image, Affine2::from_matrix_unchecked(matrix), interpolation).unwrap();
+            let warped = warp_perspective(&image, matrix, interpolation).unwrap();
+            assert_pixels_eq!(warped, expected);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    fn test_warp_perspective_with_default() {
+        let image: GrayImage = ImageBuffer::from_raw(3, 3, vec![
+            00, 01, 02,
+            10, 11, 12,
+            20, 21, 22,]).unwrap();
+
+        // Maps (x, y) to (2x / (x + 1), 2y / (x + 1)), so output pixel (1, 1)
+        // has pre-image (1, 1) and output pixel (0, 1) has pre-image (0, 0.5)
+        let projection = Matrix3::new(
+            2.0, 0.0, 0.0,
+            0.0, 2.0, 0.0,
+            1.0, 0.0, 1.0,
+        );
+
+        let warped = warp_perspective_with_default(
+            &image, projection, Luma([99u8]), Interpolation::Bilinear).unwrap();
+
+        assert_eq!(warped.get_pixel(0, 0)[0], 0);
+        assert_eq!(warped.get_pixel(0, 1)[0], 5);
+        assert_eq!(warped.get_pixel(1, 1)[0], 11);
+        // Pre-image (-2, 2) lies outside the input
+        assert_eq!(warped.get_pixel(2, 2)[0], 99);
+    }
+
+    #[test]
+    fn test_warp_perspective_not_invertible() {
+        let image = gray_bench_image(5, 5);
+        let projection = Matrix3::new(
+            1.0, 2.0, 0.0,
+            2.0, 4.0, 0.0,
+            0.0, 0.0, 1.0,
+        );
+        assert!(warp_perspective(&image, projection, Interpolation::Nearest).is_none());
+    }
+
+    #[test]
+    fn test_homography_from_points() {
+        let from = [(0.0, 0.0), (100.0, 0.0), (100.0, 50.0), (0.0, 50.0)];
+        let to = [(10.0, 5.0), (90.0, 20.0), (120.0, 80.0), (-5.0, 60.0)];
+
+        let h = homography_from_points(from, to).unwrap();
+        assert_eq!(h[(2, 2)], 1.0);
+        for (&(x, y), &(u, v)) in from.iter().zip(to.iter()) {
+            let p = h * Vector3::new(x, y, 1.0);
+            assert!((p[0] / p[2] - u).abs() < 1e-3);
+            assert!((p[1] / p[2] - v).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_homography_from_points_rectifies_quadrilateral() {
+        let rectangle = [(0.0, 0.0), (7.0, 0.0), (7.0, 7.0), (0.0, 7.0)];
+        let quad = [(1.0, 1.0), (6.0, 2.0), (5.0, 6.0), (2.0, 5.0)];
+        let image = GrayImage::from_fn(8, 8, |x, y| Luma([(x * 10 + y) as u8]));
+
+        // Output pixels at the rectangle's corners sample the quadrilateral's corners
+        let h = homography_from_points(quad, rectangle).unwrap();
+        let warped = warp_perspective(&image, h, Interpolation::Nearest).unwrap();
+        assert_eq!(warped.get_pixel(0, 0)[0], 11);
+        assert_eq!(warped.get_pixel(7, 0)[0], 62);
+        assert_eq!(warped.get_pixel(7, 7)[0], 56);
+        assert_eq!(warped.get_pixel(0, 7)[0], 25);
+    }
+
+    #[test]
+    fn test_homography_from_collinear_points() {
+        let from = [(0.0, 0.0), (1.0, 1.0), (2.0, 2.0), (0.0, 5.0)];
+        let to = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+        assert!(homography_from_points(from, to).is_none());
+    }
+
+    #[bench]
+    fn bench_warp_perspective_bilinear(b: &mut test::Bencher) {
+        let image = GrayImage::from_pixel(200, 200, Luma([15u8]));
+
+        let projection = Matrix3::new(
+            1.0, 0.1, 1.0,
+            0.0, 1.0, 1.0,
+            0.001, 0.0, 1.0,
+        );
+
+        b.iter(|| {
+            let transformed = warp_perspective(&image, projection, Interpolation::Bilinear);
+            test::black_box(transformed);
+        });
+    }
+
+    #[test]
+    fn test_affine_identity_higher_order_interpolation() {
+        let image = gray_bench_image(9, 7);
+        let identity = Affine2::from_matrix_unchecked(Matrix3::new(
+            1.0, 0.0, 0.0,
+            0.0, 1.0, 0.0,
+            0.0, 0.0, 1.0,
+        ));
+        let default = Luma([99u8]);
+
+        for &interpolation in &[Interpolation::Bicubic, Interpolation::Lanczos3] {
+            let transformed = affine(&image, identity, interpolation).unwrap();
+            // As with bilinear interpolation, the last row and column lack a neighbour
+            for y in 0..6 {
+                for x in 0..8 {
+                    assert_eq!(transformed.get_pixel(x, y), image.get_pixel(x, y));
+                }
+            }
+
+            let rotated = rotate_with_default(&image, (0.0, 0.0), 0.0, default, interpolation);
+            assert_eq!(rotated.get_pixel(3, 3), image.get_pixel(3, 3));
+            assert_eq!(*rotated.get_pixel(8, 6), default);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    fn test_higher_order_interpolation_clamps() {
+        let image: GrayImage = ImageBuffer::from_raw(5, 3, vec![
+            0, 0, 255, 255, 255,
+            0, 0, 255, 255, 255,
+            0, 0, 255, 255, 255]).unwrap();
+
+        // Output pixel x has pre-image x + 0.5
+        let shift = Affine2::from_matrix_unchecked(Matrix3::new(
+            1.0, 0.0, -0.5,
+            0.0, 1.0, 0.0,
+            0.0, 0.0, 1.0,
+        ));
+
+        // The kernels undershoot to the left of the step and overshoot to its right.
+        // The final output pixel's pre-image has no right neighbour so is set to black.
+        let expected = [
+            (Interpolation::Bicubic, vec![0, 128, 255, 255, 0]),
+            (Interpolation::Lanczos3, vec![0, 127, 255, 249, 0])
+        ];
+        for &(interpolation, ref expected_row) in &expected {
+            let shifted = affine(&image, shift, interpolation).unwrap();
+            let row: Vec<u8> = (0..5).map(|x| shifted.get_pixel(x, 0)[0]).collect();
+            assert_eq!(&row, expected_row, "{:?}", interpolation);
+        }
+    }
+
+    #[test]
+    fn test_higher_order_interpolation_of_float_image() {
+        let image = ImageBuffer::from_fn(5, 3, |x, _| Luma([if x < 2 { 0.25f32 } else { 255.25f32 }]));
+
+        // Output pixel x has pre-image x + 0.5
+        let shift = Affine2::from_matrix_unchecked(Matrix3::new(
+            1.0, 0.0, -0.5,
+            0.0, 1.0, 0.0,
+            0.0, 0.0, 1.0,
+        ));
+
+        // Float results are neither rounded nor clamped to the range of the input.
+        for &interpolation in &[Interpolation::Bicubic, Interpolation::Lanczos3] {
+            let shifted = affine_with_default(&image, shift, Luma([0f32]), interpolation).unwrap();
+            let middle = shifted.get_pixel(1, 0)[0];
+            assert!((middle - 127.75).abs() < 1e-3, "{:?}: {}", interpolation, middle);
+            let undershoot = shifted.get_pixel(0, 0)[0];
+            assert!(undershoot < 0.25, "{:?}: {}", interpolation, undershoot);
+        }
+    }
+
+    #[test]
+    fn test_rotate_about_center_lanczos3_constant_image() {
+        let image = GrayImage::from_pixel(20, 20, Luma([120u8]));
+        for &interpolation in &[Interpolation::Bicubic, Interpolation::Lanczos3] {
+            let rotated = rotate_about_center(&image, 0.7, interpolation);
+            for y in 7..13 {
+                for x in 7..13 {
+                    let p = rotated.get_pixel(x, y)[0];
+                    assert_eq!(p, 120, "pixel ({}, {}) has value {}", x, y, p);
+                }
+            }
+        }
+    }
+
+    #[bench]
+    fn bench_affine_bicubic(b: &mut test::Bencher) {
+        let image = GrayImage::from_pixel(200, 200, Luma([15u8]));
+
+        let aff = Affine2::from_matrix_unchecked(Matrix3::new(
+            1.0, 0.0, 1.0,
+            0.0, 1.0, 1.0,
+            0.0, 0.0, 1.0,
+        ));
+
+        b.iter(|| {
+            let transformed = affine(&image, aff, Interpolation::Bicubic);
+            test::black_box(transformed);
+        });
+    }
+
+    #[bench]
+    fn bench_rotate_lanczos3(b: &mut test::Bencher) {
+        let image = GrayImage::from_pixel(200, 200, Luma([15u8]));
+        b.iter(|| {
+            let rotated = rotate_about_center(&image, 1f32, Interpolation::Lanczos3);
+            test::black_box(rotated);
+        });
+    }
+
+    fn quarter_turn() -> Affine2<f32> {
+        // Maps (x, y) to (-y, x), i.e. a clockwise quarter turn about the origin
+        Affine2::from_matrix_unchecked(Matrix3::new(
+            0.0, -1.0, 0.0,
+            1.0, 0.0, 0.0,
+            0.0, 0.0, 1.0,
+        ))
+    }
+
+    #[test]
+    fn test_affine_bounding_box() {
+        let identity = Affine2::from_matrix_unchecked(Matrix3::new(
+            1.0, 0.0, 0.0,
+            0.0, 1.0, 0.0,
+            0.0, 0.0, 1.0,
+        ));
+        assert_eq!(affine_bounding_box(identity, 4, 3), Rect::at(0, 0).of_size(4, 3));
+        assert_eq!(affine_bounding_box(quarter_turn(), 4, 3), Rect::at(-2, 0).of_size(3, 4));
+
+        let scale = Affine2::from_matrix_unchecked(Matrix3::new(
+            2.0, 0.0, 0.5,
+            0.0, 1.5, 0.0,
+            0.0, 0.0, 1.0,
+        ));
+        assert_eq!(affine_bounding_box(scale, 3, 3), Rect::at(0, 0).of_size(6, 4));
+    }
+
+    #[test]
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    fn test_affine_to_bounding_box_does_not_crop() {
+        let image: GrayImage = ImageBuffer::from_raw(4, 3, vec![
+            00, 01, 02, 03,
+            10, 11, 12, 13,
+            20, 21, 22, 23]).unwrap();
+
+        let expected: GrayImage = ImageBuffer::from_raw(3, 4, vec![
+            20, 10, 00,
+            21, 11, 01,
+            22, 12, 02,
+            23, 13, 03]).unwrap();
+
+        let rotated = affine_to_bounding_box(
+            &image, quarter_turn(), Luma([99u8]), Interpolation::Nearest).unwrap();
+        assert_pixels_eq!(rotated, expected);
+
+        // The same transformation with the default canvas keeps only the first input row
+        let cropped = affine_with_default(
+            &image, quarter_turn(), Luma([99u8]), Interpolation::Nearest).unwrap();
+        assert_eq!(cropped.pixels().filter(|p| p[0] != 99).count(), 3);
+    }
+
+    #[test]
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    fn test_affine_to_region() {
+        let image: GrayImage = ImageBuffer::from_raw(3, 3, vec![
+            00, 01, 02,
+            10, 11, 12,
+            20, 21, 22]).unwrap();
+
+        let translation = Affine2::from_matrix_unchecked(Matrix3::new(
+            1.0, 0.0, 5.0,
+            0.0, 1.0, -2.0,
+            0.0, 0.0, 1.0,
+        ));
+
+        let expected: GrayImage = ImageBuffer::from_raw(3, 2, vec![
+            11, 12, 99,
+            21, 22, 99]).unwrap();
+
+        let region = Rect::at(6, -1).of_size(3, 2);
+        let out = affine_to_region(
+            &image, translation, Luma([99u8]), Interpolation::Nearest, region).unwrap();
+        assert_pixels_eq!(out, expected);
+    }
+
+    #[test]
+    fn test_affine_to_region_not_invertible() {
+        let image = gray_bench_image(3, 3);
+        let singular = Affine2::from_matrix_unchecked(Matrix3::new(
+            1.0, 2.0, 0.0,
+            2.0, 4.0, 0.0,
+            0.0, 0.0, 1.0,
+        ));
+        let region = Rect::at(0, 0).of_size(3, 3);
+        assert!(affine_to_region(&image, singular, Luma([0u8]), Interpolation::Nearest, region).is_none());
+    }
+
+    const RESIZE_FILTERS: [ResizeFilter; 5] = [
+        ResizeFilter::Nearest,
+        ResizeFilter::Bilinear,
+        ResizeFilter::Bicubic,
+        ResizeFilter::Lanczos3,
+        ResizeFilter::Area,
+    ];
+
+    #[test]
+    fn test_resize_to_same_size_is_identity() {
+        let image = gray_bench_image(11, 7);
+        for &filter in &RESIZE_FILTERS {
+            assert_pixels_eq!(resize(&image, 11, 7, filter), image);
+        }
+    }
+
+    #[test]
+    fn test_resize_dimensions() {
+        let image = gray_bench_image(10, 6);
+        for &filter in &RESIZE_FILTERS {
+            assert_eq!(resize(&image, 23, 3, filter).dimensions(), (23, 3));
+            assert_eq!(resize(&image, 1, 1, filter).dimensions(), (1, 1));
+            assert_eq!(resize(&image, 0, 5, filter).dimensions(), (0, 5));
+        }
+    }
+
+    #[test]
+    fn test_resize_constant_image() {
+        let image = RgbImage::from_pixel(13, 9, Rgb([5u8, 120, 250]));
+        for &filter in &RESIZE_FILTERS {
+            for &(w, h) in &[(5, 4), (30, 17)] {
+                let resized = resize(&image, w, h, filter);
+                assert!(resized.pixels().all(|p| *p == Rgb([5u8, 120, 250])),
+                    "{:?} to ({}, {})", filter, w, h);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    fn test_resize_nearest_upsample() {
+        let image: GrayImage = ImageBuffer::from_raw(2, 2, vec![
+            1, 2,
+            3, 4]).unwrap();
+
+        let expected: GrayImage = ImageBuffer::from_raw(4, 4, vec![
+            1, 1, 2, 2,
+            1, 1, 2, 2,
+            3, 3, 4, 4,
+            3, 3, 4, 4]).unwrap();
+
+        assert_pixels_eq!(resize(&image, 4, 4, ResizeFilter::Nearest), expected);
+    }
+
+    #[test]
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    fn test_resize_area_downsample() {
+        let image: GrayImage = ImageBuffer::from_raw(4, 2, vec![
+            1, 3, 10, 20,
+            5, 7, 30, 40]).unwrap();
+
+        let expected: GrayImage = ImageBuffer::from_raw(2, 1, vec![
+            4, 25]).unwrap();
+
+        assert_pixels_eq!(resize(&image, 2, 1, ResizeFilter::Area), expected);
+
+        // Output pixels covering fractions of input pixels
+        let row: GrayImage = ImageBuffer::from_raw(3, 1, vec![0, 90, 180]).unwrap();
+        let expected_row: GrayImage = ImageBuffer::from_raw(2, 1, vec![30, 150]).unwrap();
+        assert_pixels_eq!(resize(&row, 2, 1, ResizeFilter::Area), expected_row);
+    }
+
+    #[test]
+    fn test_resize_bilinear_upsample() {
+        let image: GrayImage = ImageBuffer::from_raw(2, 1, vec![0, 200]).unwrap();
+        let resized = resize(&image, 4, 1, ResizeFilter::Bilinear);
+        let row: Vec<u8> = resized.pixels().map(|p| p[0]).collect();
+        assert_eq!(row, vec![0, 50, 150, 200]);
+    }
+
+    #[test]
+    fn test_resize_clamps_overshoot() {
+        let image = GrayImage::from_fn(8, 2, |x, _| Luma([if x < 4 { 0u8 } else { 255u8 }]));
+        for &filter in &[ResizeFilter::Bicubic, ResizeFilter::Lanczos3] {
+            let resized = resize(&image, 32, 2, filter);
+            for x in 0..14 {
+                assert!(resized.get_pixel(x, 0)[0] < 20, "{:?} at {}", filter, x);
+            }
+            for x in 18..32 {
+                assert!(resized.get_pixel(x, 0)[0] > 235, "{:?} at {}", filter, x);
+            }
+        }
+    }
+
+    #[test]
+    fn test_resize_u16() {
+        let image = ImageBuffer::from_pixel(6, 6, Luma([40000u16]));
+        let resized = resize(&image, 3, 3, ResizeFilter::Lanczos3);
+        assert!(resized.pixels().all(|p| p[0] == 40000));
+    }
+
+    #[test]
+    fn test_resize_f32() {
+        let image = ImageBuffer::from_pixel(13, 9, Luma([1000.05f32]));
+        for &filter in &RESIZE_FILTERS {
+            for &(w, h) in &[(5, 4), (30, 17)] {
+                let resized = resize(&image, w, h, filter);
+                assert!(resized.pixels().all(|p| (p[0] - 1000.05).abs() < 1e-3),
+                    "{:?} to ({}, {})", filter, w, h);
+            }
+        }
+    }
+
+    #[bench]
+    fn bench_resize_bilinear_downsample(b: &mut test::Bencher) {
+        let image = gray_bench_image(500, 500);
+        b.iter(|| {
+            let resized = resize(&image, 200, 200, ResizeFilter::Bilinear);
+            test::black_box(resized);
+        });
+    }
+
+    #[bench]
+    fn bench_resize_lanczos3_upsample(b: &mut test::Bencher) {
+        let image = gray_bench_image(200, 200);
+        b.iter(|| {
+            let resized = resize(&image, 500, 500, ResizeFilter::Lanczos3);
+            test::black_box(resized);
+        });
+    }
+
+    #[bench]
+    fn bench_resize_area_downsample(b: &mut test::Bencher) {
+        let image = gray_bench_image(500, 500);
+        b.iter(|| {
+            let resized = resize(&image, 200, 200, ResizeFilter::Area);
+            test::black_box(resized);
+        });
+    }
 }
@@ -0,0 +1,384 @@
+//! Functions for detecting blobs using scale-normalised Laplacian-of-Gaussian
+//! and Difference-of-Gaussians scale spaces.
+
+use image::{
+    GenericImage,
+    GrayImage,
+    ImageBuffer,
+    Luma
+};
+
+use definitions::{
+    Image,
+    Position,
+    Score
+};
+
+use filter::normalized_gaussian_blur_f32;
+
+/// A blob detected in scale space.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Blob {
+    /// x-coordinate of the blob centre.
+    pub x: u32,
+    /// y-coordinate of the blob centre.
+    pub y: u32,
+    /// Standard deviation of the Gaussian at which the blob was detected.
+    /// The radius of a disc-shaped blob is approximately `sigma * sqrt(2)`.
+    pub sigma: f32,
+    /// Scale-normalised Laplacian response at the blob. This is negative
+    /// for bright blobs on a dark background and positive for dark blobs
+    /// on a bright background.
+    pub response: f32
+}
+
+impl Blob {
+    /// A blob at location (x, y) with scale `sigma` and response `response`.
+    pub fn new(x: u32, y: u32, sigma: f32, response: f32) -> Blob {
+        Blob {x: x, y: y, sigma: sigma, response: response}
+    }
+}
+
+impl Position for Blob {
+    /// x-coordinate of the blob centre.
+    fn x(&self) -> u32 {
+        self.x
+    }
+
+    /// y-coordinate of the blob centre.
+    fn y(&self) -> u32 {
+        self.y
+    }
+}
+
+impl Score for Blob {
+    /// Magnitude of the blob's response.
+    fn score(&self) -> f32 {
+        self.response.abs()
+    }
+}
+
+/// Options for blob detection.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct BlobDetectionOptions {
+    /// Smallest scale to search.
+    pub min_sigma: f32,
+    /// Largest scale to search.
+    pub max_sigma: f32,
+    /// Number of scales to search, spaced geometrically between
+    /// `min_sigma` and `max_sigma`. Must be at least 3.
+    pub num_scales: u32,
+    /// Blobs with a response of magnitude at most this value are discarded.
+    /// Intensities are scaled to lie between 0 and 1 before computing responses.
+    pub contrast_threshold: f32,
+    /// Blobs are discarded if the ratio of the principal curvatures of the
+    /// response at their location is greater than this value, as such points
+    /// lie on edges or ridges rather than at blob centres.
+    pub edge_threshold: f32
+}
+
+impl BlobDetectionOptions {
+    /// Options searching 10 scales between `min_sigma` and `max_sigma`, with a
+    /// contrast threshold of 0.03 and an edge threshold of 10.
+    pub fn new(min_sigma: f32, max_sigma: f32) -> BlobDetectionOptions {
+        BlobDetectionOptions {
+            min_sigma: min_sigma,
+            max_sigma: max_sigma,
+            num_scales: 10,
+            contrast_threshold: 0.03,
+            edge_threshold: 10.0
+        }
+    }
+
+    /// The scales to search.
+    fn sigmas(&self) -> Vec<f32> {
+        assert!(self.num_scales >= 3, "at least three scales are required");
+        assert!(self.min_sigma > 0.0 && self.max_sigma > self.min_sigma,
+            "scales must satisfy 0 < min_sigma < max_sigma");
+
+        let k = self.scale_ratio();
+        (0..self.num_scales).map(|i| self.min_sigma * k.powi(i as i32)).collect()
+    }
+
+    /// Ratio between consecutive scales.
+    fn scale_ratio(&self) -> f32 {
+        (self.max_sigma / self.min_sigma).powf(1.0 / (self.num_scales - 1) as f32)
+    }
+}
+
+/// Detects blobs as extrema of the scale-normalised Laplacian of Gaussian,
+/// `sigma^2 * (Lxx + Lyy)`, across position and scale.
+///
+/// A point is reported if its response is strictly greater or strictly less
+/// than those of its 26 neighbours in scale space, and it passes the contrast
+/// and edge tests described on `BlobDetectionOptions`. Extrema at the smallest
+/// and largest scales and on the image border are not reported. Blobs are returned
+/// in descending order of score.
+pub fn blobs_log(image: &GrayImage, options: BlobDetectionOptions) -> Vec<Blob> {
+    let sigmas = options.sigmas();
+    let normalized = normalize_intensities(image);
+
+    let responses: Vec<_> = sigmas.iter().map(|&sigma| {
+        let blurred = normalized_gaussian_blur_f32(&normalized, sigma);
+        let (width, height) = blurred.dimensions();
+        ImageBuffer::from_fn(width, height, |x, y| {
+            let (dxx, dyy, _) = hessian(&blurred, x, y);
+            Luma([sigma * sigma * (dxx + dyy)])
+        })
+    }).collect();
+
+    scale_space_extrema(&responses, &sigmas, options)
+}
+
+/// Detects blobs as extrema of the Difference of Gaussians between consecutive
+/// scales, a fast approximation to `blobs_log`. The difference between the images
+/// blurred at scales `sigma * k` and `sigma` is divided by `k - 1` so that responses
+/// are comparable to those returned by `blobs_log`, and is assigned scale `sigma`.
+///
+/// See `blobs_log` for the conditions under which a blob is reported.
+pub fn blobs_dog(image: &GrayImage, options: BlobDetectionOptions) -> Vec<Blob> {
+    let sigmas = options.sigmas();
+    let k = options.scale_ratio();
+    let normalized = normalize_intensities(image);
+
+    let mut blurred: Vec<_> = sigmas.iter()
+        .map(|&sigma| normalized_gaussian_blur_f32(&normalized, sigma))
+        .collect();
+    blurred.push(normalized_gaussian_blur_f32(&normalized, sigmas[sigmas.len() - 1] * k));
+
+    let responses: Vec<_> = blurred.windows(2).map(|pair| {
+        let (width, height) = pair[0].dimensions();
+        ImageBuffer::from_fn(width, height, |x, y| {
+            let difference = pair[1].get_pixel(x, y)[0] - pair[0].get_pixel(x, y)[0];
+            Luma([difference / (k - 1.0)])
+        })
+    }).collect();
+
+    scale_space_extrema(&responses, &sigmas, options)
+}
+
+/// Converts intensities to floats between 0 and 1.
+fn normalize_intensities(image: &GrayImage) -> Image<Luma<f32>> {
+    let (width, height) = image.dimensions();
+    ImageBuffer::from_fn(width, height, |x, y| {
+        Luma([image.get_pixel(x, y)[0] as f32 / 255.0])
+    })
+}
+
+/// Finds extrema of `responses`, whose ith entry was computed at scale `sigmas[i]`.
+fn scale_space_extrema(responses: &[Image<Luma<f32>>],
+                       sigmas: &[f32],
+                       options: BlobDetectionOptions) -> Vec<Blob> {
+    let (width, height) = responses[0].dimensions();
+    let r = options.edge_threshold;
+    let max_curvature_ratio = (r + 1.0) * (r + 1.0) / r;
+    let mut blobs = vec![];
+
+    if width < 3 || height < 3 {
+        return blobs;
+    }
+
+    for s in 1..responses.len() - 1 {
+        let layer = &responses[s];
+        for y in 1..height - 1 {
+            for x in 1..width - 1 {
+                let value = layer.get_pixel(x, y)[0];
+                if value.abs() <= options.contrast_threshold {
+                    continue;
+                }
+                if !is_extremum(&responses[s - 1..s + 2], x, y, value) {
+                    continue;
+                }
+                let (dxx, dyy, dxy) = hessian(layer, x, y);
+                let trace = dxx + dyy;
+                let det = dxx * dyy - dxy * dxy;
+                if det <= 0.0 || trace * trace / det >= max_curvature_ratio {
+                    continue;
+                }
+                blobs.push(Blob::new(x, y, sigmas[s], value));
+            }
+        }
+    }
+
+    blobs.sort_by(|a, b| b.score().partial_cmp(&a.score()).unwrap());
+    blobs
+}
+
+/// Returns true if `value`, the response at (x, y) in the middle of the three
+/// given layers, is strictly greater or strictly less than all of its neighbours.
+fn is_extremum(layers: &[Image<Luma<f32>>], x: u32, y: u32, value: f32) -> bool {
+    let mut is_max = true;
+    let mut is_min = true;
+
+    for (s, layer) in layers.iter().enumerate() {
+        for ny in y - 1..y + 2 {
+            for nx in x - 1..x + 2 {
+                if s == 1 && nx == x && ny == y {
+                    continue;
+                }
+                let neighbour = unsafe { layer.unsafe_get_pixel(nx, ny)[0] };
+                is_max &= value > neighbour;
+                is_min &= value < neighbour;
+            }
+        }
+        if !is_max && !is_min {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Returns the second derivatives (dxx, dyy, dxy) of an image at (x, y),
+/// estimated by central differences. Coordinates outside the image are
+/// clamped to the nearest pixel in the image.
+fn hessian(image: &Image<Luma<f32>>, x: u32, y: u32) -> (f32, f32, f32) {
+    let (width, height) = image.dimensions();
+    let left = if x > 0 { x - 1 } else { x };
+    let right = if x + 1 < width { x + 1 } else { x };
+    let top = if y > 0 { y - 1 } else { y };
+    let bottom = if y + 1 < height { y + 1 } else { y };
+
+    let at = |px, py| unsafe { image.unsafe_get_pixel(px, py)[0] };
+    let centre = at(x, y);
+
+    let dxx = at(right, y) - 2.0 * centre + at(left, y);
+    let dyy = at(x, bottom) - 2.0 * centre + at(x, top);
+    let dxy = (at(right, bottom) - at(right, top) - at(left, bottom) + at(left, top)) / 4.0;
+
+    (dxx, dyy, dxy)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        blobs_dog,
+        blobs_log,
+        Blob,
+        BlobDetectionOptions
+    };
+    use definitions::{
+        Position,
+        Score
+    };
+    use image::{
+        GrayImage,
+        Luma
+    };
+    use utils::gray_bench_image;
+    use test::{
+        Bencher,
+        black_box
+    };
+
+    fn disc_image(width: u32, height: u32, cx: u32, cy: u32, radius: f32,
+                  foreground: u8, background: u8) -> GrayImage {
+        GrayImage::from_fn(width, height, |x, y| {
+            let dx = x as f32 - cx as f32;
+            let dy = y as f32 - cy as f32;
+            if dx * dx + dy * dy <= radius * radius {
+                Luma([foreground])
+            } else {
+                Luma([background])
+            }
+        })
+    }
+
+    fn assert_single_disc_detected(blobs: &[Blob], cx: u32, cy: u32, radius: f32) {
+        assert!(!blobs.is_empty());
+        let strongest = blobs[0];
+        assert!((strongest.x as i32 - cx as i32).abs() <= 1, "{:?}", strongest);
+        assert!((strongest.y as i32 - cy as i32).abs() <= 1, "{:?}", strongest);
+        let estimated_radius = strongest.sigma * 2f32.sqrt();
+        assert!((estimated_radius - radius).abs() <= 0.25 * radius,
+            "estimated radius {} for disc of radius {}", estimated_radius, radius);
+    }
+
+    #[test]
+    fn test_blobs_log_bright_disc() {
+        let image = disc_image(60, 60, 30, 30, 8.0, 255, 0);
+        let blobs = blobs_log(&image, BlobDetectionOptions::new(2.0, 12.0));
+        assert_single_disc_detected(&blobs, 30, 30, 8.0);
+        assert!(blobs[0].response < 0.0);
+    }
+
+    #[test]
+    fn test_blobs_log_dark_disc() {
+        let image = disc_image(60, 60, 25, 33, 5.0, 0, 255);
+        let blobs = blobs_log(&image, BlobDetectionOptions::new(2.0, 12.0));
+        assert_single_disc_detected(&blobs, 25, 33, 5.0);
+        assert!(blobs[0].response > 0.0);
+    }
+
+    #[test]
+    fn test_blobs_dog_bright_disc() {
+        let image = disc_image(60, 60, 30, 30, 8.0, 255, 0);
+        let blobs = blobs_dog(&image, BlobDetectionOptions::new(2.0, 12.0));
+        assert_single_disc_detected(&blobs, 30, 30, 8.0);
+        assert!(blobs[0].response < 0.0);
+    }
+
+    #[test]
+    fn test_blobs_of_constant_image() {
+        let image = GrayImage::from_pixel(30, 30, Luma([123u8]));
+        let options = BlobDetectionOptions::new(1.0, 5.0);
+        assert!(blobs_log(&image, options).is_empty());
+        assert!(blobs_dog(&image, options).is_empty());
+    }
+
+    #[test]
+    fn test_blobs_contrast_threshold() {
+        let image = disc_image(60, 60, 30, 30, 6.0, 50, 30);
+        let mut options = BlobDetectionOptions::new(2.0, 10.0);
+        assert!(!blobs_log(&image, options).is_empty());
+        options.contrast_threshold = 0.1;
+        assert!(blobs_log(&image, options).is_empty());
+    }
+
+    #[test]
+    fn test_blobs_edge_rejection() {
+        // A long thin ellipse has a strongly anisotropic response at its centre
+        let image = GrayImage::from_fn(80, 40, |x, y| {
+            let dx = (x as f32 - 40.0) / 25.0;
+            let dy = (y as f32 - 20.0) / 3.0;
+            Luma([if dx * dx + dy * dy <= 1.0 { 255u8 } else { 0u8 }])
+        });
+        let mut options = BlobDetectionOptions::new(1.5, 6.0);
+
+        let near_centre = |blobs: &[Blob]| {
+            blobs.iter().filter(|b| b.x > 30 && b.x < 50 && b.y > 15 && b.y < 25).count()
+        };
+
+        options.edge_threshold = 1e6;
+        assert!(near_centre(&blobs_log(&image, options)) > 0);
+        options.edge_threshold = 10.0;
+        assert_eq!(near_centre(&blobs_log(&image, options)), 0);
+    }
+
+    #[test]
+    fn test_blob_position_and_score() {
+        let blob = Blob::new(3, 4, 2.0, -0.5);
+        assert_eq!((blob.x(), blob.y()), (3, 4));
+        assert_eq!(blob.score(), 0.5);
+    }
+
+    #[bench]
+    fn bench_blobs_log(b: &mut Bencher) {
+        let image = gray_bench_image(100, 100);
+        let options = BlobDetectionOptions::new(1.0, 8.0);
+        b.iter(|| {
+            let blobs = blobs_log(&image, options);
+            black_box(blobs);
+        });
+    }
+
+    #[bench]
+    fn bench_blobs_dog(b: &mut Bencher) {
+        let image = gray_bench_image(100, 100);
+        let options = BlobDetectionOptions::new(1.0, 8.0);
+        b.iter(|| {
+            let blobs = blobs_dog(&image, options);
+            black_box(blobs);
+        });
+    }
+}
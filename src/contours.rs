@@ -0,0 +1,420 @@
+//! Functions for finding the borders of connected regions in binary images,
+//! and for simplifying the resulting curves.
+
+use image::GrayImage;
+use drawing::Point;
+
+/// Whether a border separates a region from the background surrounding it,
+/// or from a hole inside it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BorderType {
+    /// The outer border of a region.
+    Outer,
+    /// The border of a hole in a region.
+    Hole,
+}
+
+/// A border found by `find_contours`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contour {
+    /// The pixels on the border, in the order they were traced.
+    pub points: Vec<Point<i32>>,
+    /// Whether this is an outer or hole border.
+    pub border_type: BorderType,
+    /// Index of the immediately enclosing border in the output of `find_contours`,
+    /// or `None` if this border is not enclosed by any other.
+    ///
+    /// The parent of a hole border is the outer border of the region containing the
+    /// hole, and the parent of an outer border is the hole border it lies within.
+    pub parent: Option<usize>,
+}
+
+/// Offsets to the 8-connected neighbours of a pixel, in clockwise order
+/// (with y increasing downwards), starting from the pixel to the east.
+static NEIGHBOURS: [(i32, i32); 8] =
+    [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+
+/// Finds all borders of 8-connected foreground regions in a binary image, using the
+/// border following algorithm of Suzuki and Abe, "Topological Structural Analysis of
+/// Digitized Binary Images by Border Following". All non-zero pixels are treated as
+/// foreground.
+///
+/// Outer borders are traced anticlockwise and hole borders clockwise (with y increasing
+/// downwards). Borders are returned in the order in which their first pixels are
+/// reached in a raster scan of the image, so every border appears after its parent.
+pub fn find_contours(image: &GrayImage) -> Vec<Contour> {
+    let (width, height) = (image.width() as i32 + 2, image.height() as i32 + 2);
+
+    // Pad the image with a frame of background pixels, so that tracing never
+    // has to check bounds. Foreground pixels start with label 1, and pixels on
+    // traced borders are relabelled with the (signed) number of their border.
+    // Border number 1 is reserved for the frame.
+    let mut labels = vec![0i32; (width * height) as usize];
+    for (x, y, p) in image.enumerate_pixels() {
+        if p[0] != 0 {
+            labels[((y as i32 + 1) * width + x as i32 + 1) as usize] = 1;
+        }
+    }
+    let at = |x: i32, y: i32| (y * width + x) as usize;
+
+    let mut contours: Vec<Contour> = Vec::new();
+
+    for y in 1..height - 1 {
+        // Number of the last border encountered on this row.
+        let mut last_border = 1;
+        for x in 1..width - 1 {
+            let current = labels[at(x, y)];
+
+            let start = if current == 1 && labels[at(x - 1, y)] == 0 {
+                Some((BorderType::Outer, (x - 1, y)))
+            } else if current >= 1 && labels[at(x + 1, y)] == 0 {
+                if current > 1 {
+                    last_border = current;
+                }
+                Some((BorderType::Hole, (x + 1, y)))
+            } else {
+                None
+            };
+
+            if let Some((border_type, adjacent)) = start {
+                let parent = if last_border == 1 {
+                    None
+                } else {
+                    let index = (last_border - 2) as usize;
+                    if contours[index].border_type == border_type {
+                        contours[index].parent
+                    } else {
+                        Some(index)
+                    }
+                };
+
+                let number = contours.len() as i32 + 2;
+                let points = follow_border(&mut labels, width, (x, y), adjacent, number);
+                contours.push(Contour {
+                    points: points,
+                    border_type: border_type,
+                    parent: parent,
+                });
+            }
+
+            let current = labels[at(x, y)];
+            if current != 0 && current != 1 {
+                last_border = current.abs();
+            }
+        }
+    }
+
+    contours
+}
+
+/// Traces the border starting at `start`, whose background neighbour `adjacent`
+/// was found in the raster scan, labelling the traced pixels with `number`.
+/// Returns the traced pixels in image coordinates.
+fn follow_border(
+    labels: &mut [i32],
+    width: i32,
+    start: (i32, i32),
+    adjacent: (i32, i32),
+    number: i32,
+) -> Vec<Point<i32>> {
+    let at = |p: (i32, i32)| (p.1 * width + p.0) as usize;
+    let direction = |from: (i32, i32), to: (i32, i32)| {
+        let offset = (to.0 - from.0, to.1 - from.1);
+        NEIGHBOURS.iter().position(|n| *n == offset).unwrap()
+    };
+    let step = |p: (i32, i32), d: usize| (p.0 + NEIGHBOURS[d].0, p.1 + NEIGHBOURS[d].1);
+
+    // Search clockwise from the background neighbour for a foreground pixel.
+    let adjacent_dir = direction(start, adjacent);
+    let first = (1..9)
+        .map(|i| step(start, (adjacent_dir + i) % 8))
+        .find(|&p| labels[at(p)] != 0);
+
+    let first = match first {
+        Some(p) => p,
+        None => {
+            // Isolated pixel.
+            labels[at(start)] = -number;
+            return vec![Point::new(start.0 - 1, start.1 - 1)];
+        }
+    };
+
+    let mut points = Vec::new();
+    let mut previous = first;
+    let mut current = start;
+
+    loop {
+        points.push(Point::new(current.0 - 1, current.1 - 1));
+
+        // Search anticlockwise from the previous pixel for the next foreground pixel,
+        // noting whether the background pixel to the east was examined on the way.
+        let previous_dir = direction(current, previous);
+        let mut east_is_background = false;
+        let mut next = current;
+        for i in 1..9 {
+            let d = (previous_dir + 8 - i) % 8;
+            let candidate = step(current, d);
+            if labels[at(candidate)] != 0 {
+                next = candidate;
+                break;
+            }
+            if d == 0 {
+                east_is_background = true;
+            }
+        }
+
+        if east_is_background {
+            labels[at(current)] = -number;
+        } else if labels[at(current)] == 1 {
+            labels[at(current)] = number;
+        }
+
+        if next == start && current == first {
+            break;
+        }
+        previous = current;
+        current = next;
+    }
+
+    points
+}
+
+/// Simplifies a curve using the Ramer-Douglas-Peucker algorithm, keeping the
+/// subset of its points needed so that no discarded point is further than `epsilon`
+/// from the simplified curve.
+///
+/// If `closed` is true then `curve` is treated as a closed polygon with an implicit
+/// edge from its last point to its first, such as the points of a `Contour`.
+pub fn approximate_polygon_dp(curve: &[Point<i32>], epsilon: f64, closed: bool) -> Vec<Point<i32>> {
+    if curve.len() < 3 {
+        return curve.to_vec();
+    }
+
+    if !closed {
+        let mut keep = vec![false; curve.len()];
+        keep[0] = true;
+        keep[curve.len() - 1] = true;
+        mark_simplified(curve, 0, curve.len() - 1, epsilon, &mut keep);
+        return kept_points(curve, &keep);
+    }
+
+    // Split the polygon at its first point and the point furthest from it,
+    // and simplify the two resulting paths.
+    let first = curve[0];
+    let furthest = (1..curve.len())
+        .max_by_key(|&i| squared_distance(first, curve[i]))
+        .unwrap();
+
+    if squared_distance(first, curve[furthest]) as f64 <= epsilon * epsilon {
+        return vec![first];
+    }
+
+    let mut path = curve.to_vec();
+    path.push(first);
+    let mut keep = vec![false; path.len()];
+    keep[0] = true;
+    keep[furthest] = true;
+    mark_simplified(&path, 0, furthest, epsilon, &mut keep);
+    mark_simplified(&path, furthest, path.len() - 1, epsilon, &mut keep);
+    kept_points(curve, &keep[..curve.len()])
+}
+
+/// Marks in `keep` the points strictly between `start` and `end` that are
+/// retained by the Ramer-Douglas-Peucker algorithm.
+fn mark_simplified(path: &[Point<i32>], start: usize, end: usize, epsilon: f64, keep: &mut [bool]) {
+    let mut stack = vec![(start, end)];
+    while let Some((start, end)) = stack.pop() {
+        if end <= start + 1 {
+            continue;
+        }
+        let mut max_distance = 0f64;
+        let mut max_index = start;
+        for i in start + 1..end {
+            let distance = distance_to_segment(path[i], path[start], path[end]);
+            if distance > max_distance {
+                max_distance = distance;
+                max_index = i;
+            }
+        }
+        if max_distance > epsilon {
+            keep[max_index] = true;
+            stack.push((start, max_index));
+            stack.push((max_index, end));
+        }
+    }
+}
+
+fn kept_points(curve: &[Point<i32>], keep: &[bool]) -> Vec<Point<i32>> {
+    curve.iter().zip(keep).filter(|&(_, k)| *k).map(|(p, _)| *p).collect()
+}
+
+fn squared_distance(p: Point<i32>, q: Point<i32>) -> i64 {
+    let (dx, dy) = ((p.x - q.x) as i64, (p.y - q.y) as i64);
+    dx * dx + dy * dy
+}
+
+/// Distance from `p` to the line segment from `a` to `b`.
+fn distance_to_segment(p: Point<i32>, a: Point<i32>, b: Point<i32>) -> f64 {
+    let (dx, dy) = ((b.x - a.x) as f64, (b.y - a.y) as f64);
+    let (px, py) = ((p.x - a.x) as f64, (p.y - a.y) as f64);
+    let length_sq = dx * dx + dy * dy;
+    if length_sq == 0.0 {
+        return (px * px + py * py).sqrt();
+    }
+    let t = ((px * dx + py * dy) / length_sq).max(0.0).min(1.0);
+    let (ex, ey) = (px - t * dx, py - t * dy);
+    (ex * ex + ey * ey).sqrt()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{approximate_polygon_dp, find_contours, BorderType};
+    use drawing::Point;
+    use image::{GrayImage, ImageBuffer, Luma};
+    use test::{Bencher, black_box};
+
+    fn points(coords: &[(i32, i32)]) -> Vec<Point<i32>> {
+        coords.iter().map(|&(x, y)| Point::new(x, y)).collect()
+    }
+
+    #[test]
+    fn test_find_contours_empty() {
+        let image = GrayImage::new(5, 5);
+        assert!(find_contours(&image).is_empty());
+    }
+
+    #[test]
+    fn test_find_contours_single_pixel() {
+        let mut image = GrayImage::new(5, 5);
+        image.put_pixel(2, 3, Luma([255u8]));
+
+        let contours = find_contours(&image);
+        assert_eq!(contours.len(), 1);
+        assert_eq!(contours[0].points, points(&[(2, 3)]));
+        assert_eq!(contours[0].border_type, BorderType::Outer);
+        assert_eq!(contours[0].parent, None);
+    }
+
+    #[test]
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    fn test_find_contours_square() {
+        let image: GrayImage = ImageBuffer::from_raw(5, 5, vec![
+            0, 0, 0, 0, 0,
+            0, 1, 1, 1, 0,
+            0, 1, 1, 1, 0,
+            0, 1, 1, 1, 0,
+            0, 0, 0, 0, 0]).unwrap();
+
+        let contours = find_contours(&image);
+        assert_eq!(contours.len(), 1);
+        assert_eq!(contours[0].points,
+            points(&[(1, 1), (1, 2), (1, 3), (2, 3), (3, 3), (3, 2), (3, 1), (2, 1)]));
+        assert_eq!(contours[0].border_type, BorderType::Outer);
+        assert_eq!(contours[0].parent, None);
+    }
+
+    #[test]
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    fn test_find_contours_touching_image_border() {
+        let image: GrayImage = ImageBuffer::from_raw(3, 2, vec![
+            1, 1, 1,
+            1, 1, 1]).unwrap();
+
+        let contours = find_contours(&image);
+        assert_eq!(contours.len(), 1);
+        assert_eq!(contours[0].points,
+            points(&[(0, 0), (0, 1), (1, 1), (2, 1), (2, 0), (1, 0)]));
+    }
+
+    #[test]
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    fn test_find_contours_hierarchy() {
+        let image: GrayImage = ImageBuffer::from_raw(9, 9, vec![
+            1, 1, 1, 1, 1, 1, 1, 0, 1,
+            1, 0, 0, 0, 0, 0, 1, 0, 0,
+            1, 0, 1, 1, 1, 0, 1, 0, 0,
+            1, 0, 1, 0, 1, 0, 1, 0, 0,
+            1, 0, 1, 1, 1, 0, 1, 0, 0,
+            1, 0, 0, 0, 0, 0, 1, 0, 0,
+            1, 1, 1, 1, 1, 1, 1, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 1, 1, 0, 0, 0, 0, 0]).unwrap();
+
+        let contours = find_contours(&image);
+        let summary: Vec<_> = contours
+            .iter()
+            .map(|c| (c.points[0], c.border_type, c.parent))
+            .collect();
+
+        assert_eq!(summary, vec![
+            (Point::new(0, 0), BorderType::Outer, None),
+            (Point::new(8, 0), BorderType::Outer, None),
+            (Point::new(0, 1), BorderType::Hole, Some(0)),
+            (Point::new(2, 2), BorderType::Outer, Some(2)),
+            (Point::new(2, 3), BorderType::Hole, Some(3)),
+            (Point::new(2, 8), BorderType::Outer, None)]);
+
+        assert_eq!(contours[4].points,
+            points(&[(2, 3), (3, 2), (4, 3), (3, 4)]));
+    }
+
+    #[test]
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    fn test_find_contours_diagonal_connectivity() {
+        let image: GrayImage = ImageBuffer::from_raw(4, 4, vec![
+            1, 0, 0, 0,
+            0, 1, 0, 0,
+            0, 0, 1, 0,
+            0, 0, 0, 0]).unwrap();
+
+        let contours = find_contours(&image);
+        assert_eq!(contours.len(), 1);
+        assert_eq!(contours[0].points, points(&[(0, 0), (1, 1), (2, 2), (1, 1)]));
+    }
+
+    #[test]
+    fn test_approximate_polygon_dp_open() {
+        let curve = points(&[(0, 0), (1, 0), (2, 1), (3, 0), (4, 0), (5, 4), (6, 4)]);
+        assert_eq!(approximate_polygon_dp(&curve, 1.5, false),
+            points(&[(0, 0), (4, 0), (6, 4)]));
+        assert_eq!(approximate_polygon_dp(&curve, 0.4, false), curve);
+        assert_eq!(approximate_polygon_dp(&curve, 10.0, false),
+            points(&[(0, 0), (6, 4)]));
+    }
+
+    #[test]
+    fn test_approximate_polygon_dp_closed() {
+        let mut image = GrayImage::new(12, 10);
+        for y in 2..8 {
+            for x in 3..10 {
+                image.put_pixel(x, y, Luma([1u8]));
+            }
+        }
+        let contour = &find_contours(&image)[0];
+
+        assert_eq!(approximate_polygon_dp(&contour.points, 0.5, true),
+            points(&[(3, 2), (3, 7), (9, 7), (9, 2)]));
+    }
+
+    #[test]
+    fn test_approximate_polygon_dp_short_curves() {
+        assert!(approximate_polygon_dp(&[], 1.0, true).is_empty());
+        let curve = points(&[(0, 0), (3, 3)]);
+        assert_eq!(approximate_polygon_dp(&curve, 1.0, false), curve);
+        let tiny = points(&[(0, 0), (1, 0), (1, 1)]);
+        assert_eq!(approximate_polygon_dp(&tiny, 2.0, true), points(&[(0, 0)]));
+    }
+
+    #[bench]
+    fn bench_find_contours(b: &mut Bencher) {
+        let image = GrayImage::from_fn(200, 200, |x, y| {
+            let (dx, dy) = ((x % 40) as i32 - 20, (y % 40) as i32 - 20);
+            let r = dx * dx + dy * dy;
+            Luma([if r < 300 && r > 50 { 255u8 } else { 0u8 }])
+        });
+        b.iter(|| {
+            let contours = find_contours(&image);
+            black_box(contours);
+        });
+    }
+}
@@ -13,7 +13,7 @@ use rayon::prelude::*;
 /// as the threshold then it will have a value of 255 in the output image, otherwise 0.
 pub fn adaptive_threshold(image: &GrayImage, block_radius: u32) -> GrayImage {
      assert!(block_radius > 0);
-     let integral = integral_image(image);
+     let integral = integral_image::<_, u32>(image);
      let mut out = ImageBuffer::from_pixel(image.width(), image.height(), Luma::black());
      for y in 0..image.height() {
          for x in 0..image.width() {
@@ -26,7 +26,7 @@ pub fn adaptive_threshold(image: &GrayImage, block_radius: u32) -> GrayImage {
 
              // Number of pixels in the block, adjusted for edge cases.
              let w = (y_high - y_low + 1) * (x_high - x_low + 1);
-             let mean = sum_image_pixels(&integral, x_low, y_low, x_high, y_high) / w;
+             let mean = sum_image_pixels(&integral, x_low, y_low, x_high, y_high)[0] / w;
 
              if current_pixel[0] as u32 >= mean as u32 {
                  out.put_pixel(x, y, Luma::white());
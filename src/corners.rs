@@ -2,14 +2,33 @@
 
 use image::{
     GrayImage,
-    GenericImage
+    GenericImage,
+    ImageBuffer,
+    Luma
 };
 
 use definitions::{
+    Image,
     Position,
     Score
 };
 
+use drawing::Point;
+
+use filter::gaussian_blur_f32;
+
+use gradients::{
+    horizontal_sobel,
+    vertical_sobel
+};
+
+use pyramid::scale_pyramid;
+
+use suppress::local_maxima;
+
+use std::cmp;
+use std::f32;
+
 /// A location and score for a detected corner.
 /// The scores need not be comparable between different
 /// corner detectors.
@@ -48,6 +67,61 @@ impl Score for Corner {
     }
 }
 
+impl From<Corner> for Point<i32> {
+    fn from(corner: Corner) -> Point<i32> {
+        Point::new(corner.x as i32, corner.y as i32)
+    }
+}
+
+/// A corner whose location is known to sub-pixel accuracy.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct SubPixelCorner {
+    /// x-coordinate of the corner.
+    pub x: f32,
+    /// y-coordinate of the corner.
+    pub y: f32,
+    /// Score of the detected corner.
+    pub score: f32
+}
+
+impl SubPixelCorner {
+    /// A corner at location (x, y) with score `score`.
+    pub fn new(x: f32, y: f32, score: f32) -> SubPixelCorner {
+        SubPixelCorner {x: x, y: y, score: score}
+    }
+}
+
+impl From<Corner> for SubPixelCorner {
+    fn from(corner: Corner) -> SubPixelCorner {
+        SubPixelCorner::new(corner.x as f32, corner.y as f32, corner.score)
+    }
+}
+
+impl Position for SubPixelCorner {
+    /// x-coordinate of the pixel containing the corner.
+    fn x(&self) -> u32 {
+        self.x.round().max(0.0) as u32
+    }
+
+    /// y-coordinate of the pixel containing the corner.
+    fn y(&self) -> u32 {
+        self.y.round().max(0.0) as u32
+    }
+}
+
+impl Score for SubPixelCorner {
+    fn score(&self) -> f32 {
+        self.score
+    }
+}
+
+impl From<SubPixelCorner> for Point<i32> {
+    /// The pixel containing the corner.
+    fn from(corner: SubPixelCorner) -> Point<i32> {
+        Point::new(corner.x.round() as i32, corner.y.round() as i32)
+    }
+}
+
 /// Variants of the [FAST](https://en.wikipedia.org/wiki/Features_from_accelerated_segment_test)
 /// corner detector. These classify a point based on its intensity relative to the 16 pixels
 /// in the Bresenham circle of radius 3 around it. A point P with intensity I is detected as a
@@ -97,6 +171,281 @@ pub fn corners_fast9(image: &GrayImage, threshold: u8) -> Vec<Corner> {
     corners
 }
 
+/// Options for the Harris and Shi-Tomasi corner detectors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CornerDetectionOptions {
+    /// Standard deviation of the Gaussian used to weight the gradients
+    /// around each pixel when computing its structure tensor.
+    pub sigma: f32,
+    /// Pixels whose corner response is at most this value are not corners.
+    pub threshold: f32,
+    /// Each returned corner has the greatest response of all corners within the
+    /// (2 * `min_distance` + 1) square block centred on it. If this is 0 then all
+    /// pixels whose response exceeds the threshold are returned.
+    pub min_distance: u32
+}
+
+impl CornerDetectionOptions {
+    /// Options with the given threshold and minimum distance, and a Gaussian
+    /// weighting with standard deviation 1.
+    pub fn new(threshold: f32, min_distance: u32) -> CornerDetectionOptions {
+        CornerDetectionOptions {
+            sigma: 1.0,
+            threshold: threshold,
+            min_distance: min_distance
+        }
+    }
+}
+
+/// Computes the [Harris corner response](https://en.wikipedia.org/wiki/Harris_Corner_Detector)
+/// det(M) - k * trace(M)^2 at each pixel, where M is the Gaussian-weighted structure tensor
+/// of the image gradients. The sensitivity parameter k is typically chosen between 0.04 and 0.06.
+///
+/// Gradients are computed using the Sobel filters from the `gradients` module, and
+/// are normalised to have units of intensity per pixel.
+pub fn harris_response(image: &GrayImage, k: f32, sigma: f32) -> Image<Luma<f32>> {
+    corner_response(image, sigma, |a, b, c| (a * c - b * b) - k * (a + c) * (a + c))
+}
+
+/// Computes the Shi-Tomasi corner response at each pixel. This is the smaller eigenvalue of
+/// the Gaussian-weighted structure tensor of the image gradients.
+///
+/// Gradients are computed using the Sobel filters from the `gradients` module, and
+/// are normalised to have units of intensity per pixel.
+pub fn shi_tomasi_response(image: &GrayImage, sigma: f32) -> Image<Luma<f32>> {
+    corner_response(image, sigma, |a, b, c| {
+        let half_trace = (a + c) / 2.0;
+        let half_diff = (a - c) / 2.0;
+        half_trace - (half_diff * half_diff + b * b).sqrt()
+    })
+}
+
+/// Finds corners using the Harris corner detector. See the comment on `harris_response`.
+/// The returned corners have score equal to their Harris response, and are sorted
+/// in order of decreasing score.
+pub fn corners_harris(image: &GrayImage, k: f32, options: CornerDetectionOptions) -> Vec<Corner> {
+    let response = harris_response(image, k, options.sigma);
+    corners_from_response(&response, options)
+}
+
+/// Finds corners using the Shi-Tomasi ("good features to track") detector. See the
+/// comment on `shi_tomasi_response`. The returned corners have score equal to their
+/// Shi-Tomasi response, and are sorted in order of decreasing score.
+pub fn corners_shi_tomasi(image: &GrayImage, options: CornerDetectionOptions) -> Vec<Corner> {
+    let response = shi_tomasi_response(image, options.sigma);
+    corners_from_response(&response, options)
+}
+
+/// Options for refining corner locations to sub-pixel accuracy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SubPixelOptions {
+    /// Gradients are sampled in the (2 * `window_radius` + 1) square block
+    /// centred on the current estimate of each corner's location.
+    pub window_radius: u32,
+    /// The maximum number of refinement steps to perform for each corner.
+    pub max_iterations: u32,
+    /// Refinement stops when an iteration moves a corner by less than this distance.
+    pub epsilon: f32
+}
+
+impl SubPixelOptions {
+    /// Options with the given window radius, at most 20 iterations and an epsilon of 0.01.
+    pub fn new(window_radius: u32) -> SubPixelOptions {
+        SubPixelOptions {
+            window_radius: window_radius,
+            max_iterations: 20,
+            epsilon: 0.01
+        }
+    }
+}
+
+/// Refines the locations of corners to sub-pixel accuracy.
+///
+/// For a corner at q, the image gradient at each point p near q is orthogonal to
+/// p - q: either p lies in a flat region and the gradient is zero, or p lies on an
+/// edge through q and the gradient is orthogonal to the edge. Each refinement step
+/// finds the q minimising the Gaussian-weighted sum of squared dot products of
+/// gradients with p - q over a window centred on the current estimate.
+///
+/// If a corner's refined location is more than `window_radius` pixels from
+/// its initial location along either axis then its initial location is returned.
+/// Corner scores are not modified.
+pub fn refine_corners(image: &GrayImage, corners: &[Corner], options: SubPixelOptions)
+        -> Vec<SubPixelCorner> {
+    corners.iter().map(|c| refine_corner(image, *c, options)).collect()
+}
+
+fn refine_corner(image: &GrayImage, corner: Corner, options: SubPixelOptions) -> SubPixelCorner {
+    let initial = SubPixelCorner::from(corner);
+    let (width, height) = image.dimensions();
+    if width < 3 || height < 3 {
+        return initial;
+    }
+
+    let radius = options.window_radius as i32;
+    let sigma = cmp::max(1, radius) as f32;
+    let (mut qx, mut qy) = (initial.x, initial.y);
+
+    for _ in 0..options.max_iterations {
+        // Centre pixel of the window, and its bounds. Gradients are not defined on the image border.
+        let cx = cmp::max(1, cmp::min(width as i32 - 2, qx.round() as i32));
+        let cy = cmp::max(1, cmp::min(height as i32 - 2, qy.round() as i32));
+        let (x0, x1) = (cmp::max(1, cx - radius), cmp::min(width as i32 - 2, cx + radius));
+        let (y0, y1) = (cmp::max(1, cy - radius), cmp::min(height as i32 - 2, cy + radius));
+
+        let (mut a, mut b, mut c) = (0f32, 0f32, 0f32);
+        let (mut bx, mut by) = (0f32, 0f32);
+        for py in y0..y1 + 1 {
+            for px in x0..x1 + 1 {
+                let intensity = |x: i32, y: i32| image.get_pixel(x as u32, y as u32)[0] as f32;
+                let gx = (intensity(px + 1, py) - intensity(px - 1, py)) / 2.0;
+                let gy = (intensity(px, py + 1) - intensity(px, py - 1)) / 2.0;
+                let (dx, dy) = (px as f32 - qx, py as f32 - qy);
+                let weight = (-(dx * dx + dy * dy) / (2.0 * sigma * sigma)).exp();
+
+                let (gxx, gxy, gyy) = (weight * gx * gx, weight * gx * gy, weight * gy * gy);
+                a += gxx;
+                b += gxy;
+                c += gyy;
+                bx += gxx * px as f32 + gxy * py as f32;
+                by += gxy * px as f32 + gyy * py as f32;
+            }
+        }
+
+        let det = a * c - b * b;
+        // The window is flat or contains a single straight edge
+        if det.abs() <= f32::EPSILON * (a * c).abs() {
+            break;
+        }
+        let nx = (c * bx - b * by) / det;
+        let ny = (a * by - b * bx) / det;
+        let shift = ((nx - qx).powi(2) + (ny - qy).powi(2)).sqrt();
+        qx = nx;
+        qy = ny;
+        if shift < options.epsilon {
+            break;
+        }
+    }
+
+    let max_shift = options.window_radius as f32;
+    if !qx.is_finite() || !qy.is_finite()
+        || (qx - initial.x).abs() > max_shift || (qy - initial.y).abs() > max_shift {
+        return initial;
+    }
+
+    SubPixelCorner::new(qx, qy, corner.score)
+}
+
+/// Computes the structure tensor [[a, b], [b, c]] at each pixel and applies f to its entries.
+fn corner_response<F>(image: &GrayImage, sigma: f32, f: F) -> Image<Luma<f32>>
+    where F: Fn(f32, f32, f32) -> f32
+{
+    let (width, height) = image.dimensions();
+    let gx = horizontal_sobel(image);
+    let gy = vertical_sobel(image);
+
+    let mut xx = ImageBuffer::new(width, height);
+    let mut xy = ImageBuffer::new(width, height);
+    let mut yy = ImageBuffer::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            // The Sobel filters have a gain of 8
+            let dx = gx.get_pixel(x, y)[0] as f32 / 8.0;
+            let dy = gy.get_pixel(x, y)[0] as f32 / 8.0;
+            xx.put_pixel(x, y, Luma([dx * dx]));
+            xy.put_pixel(x, y, Luma([dx * dy]));
+            yy.put_pixel(x, y, Luma([dy * dy]));
+        }
+    }
+
+    let xx: Image<Luma<f32>> = gaussian_blur_f32(&xx, sigma);
+    let xy: Image<Luma<f32>> = gaussian_blur_f32(&xy, sigma);
+    let yy: Image<Luma<f32>> = gaussian_blur_f32(&yy, sigma);
+
+    ImageBuffer::from_fn(width, height, |x, y| {
+        Luma([f(xx.get_pixel(x, y)[0], xy.get_pixel(x, y)[0], yy.get_pixel(x, y)[0])])
+    })
+}
+
+/// Returns all pixels whose response exceeds the threshold, after suppressing
+/// those within `min_distance` of a stronger corner, sorted by decreasing response.
+fn corners_from_response(response: &Image<Luma<f32>>, options: CornerDetectionOptions)
+        -> Vec<Corner> {
+
+    let mut corners: Vec<Corner> = response
+        .enumerate_pixels()
+        .filter(|&(_, _, p)| p[0] > options.threshold)
+        .map(|(x, y, p)| Corner::new(x, y, p[0]))
+        .collect();
+
+    if options.min_distance > 0 {
+        corners = local_maxima(&corners, options.min_distance);
+    }
+
+    corners.sort_by(|c, d| d.score.partial_cmp(&c.score).unwrap());
+    corners
+}
+
+/// A corner detected in one level of an image pyramid.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct ScaledCorner {
+    /// x-coordinate of the corner in the original image.
+    pub x: f32,
+    /// y-coordinate of the corner in the original image.
+    pub y: f32,
+    /// Score of the detected corner, computed in the level at which it was detected.
+    pub score: f32,
+    /// The pyramid level in which the corner was detected. Level 0 is the original image.
+    pub level: u32,
+    /// The ratio between the size of the original image and the size
+    /// of the level in which the corner was detected.
+    pub scale: f32
+}
+
+/// Finds corners using FAST-9 features at multiple scales.
+///
+/// Builds a pyramid with `num_levels` levels, the first being the input image and each
+/// subsequent level being the previous level downscaled by `scale_factor`. Pyramid
+/// construction stops early if a level would be too small to contain any FAST corners.
+/// Corners are detected at each level and suppressed if they do not have the greatest
+/// score in the 3x3 block centred on them within that level. See comment on Fast enum.
+pub fn corners_fast9_pyramid(image: &GrayImage, threshold: u8, num_levels: u32, scale_factor: f32)
+        -> Vec<ScaledCorner> {
+    let mut corners = vec![];
+
+    for (level, (scale, level_image)) in fast_pyramid(image, num_levels, scale_factor).iter().enumerate() {
+        let level_corners = local_maxima(&corners_fast9(level_image, threshold), 1);
+        for c in level_corners {
+            corners.push(ScaledCorner {
+                x: c.x as f32 * scale,
+                y: c.y as f32 * scale,
+                score: c.score,
+                level: level as u32,
+                scale: *scale
+            });
+        }
+    }
+
+    corners
+}
+
+/// Returns up to `num_levels` levels of `pyramid::scale_pyramid`, each paired with its
+/// scale relative to the original image. Stops early if a level would be too small to
+/// contain any FAST corners.
+pub(crate) fn fast_pyramid(image: &GrayImage, num_levels: u32, scale_factor: f32)
+        -> Vec<(f32, GrayImage)> {
+    let mut scale = 1f32;
+    scale_pyramid(image, num_levels, scale_factor)
+        .into_iter()
+        .take_while(|level| level.width() >= 7 && level.height() >= 7)
+        .map(|level| {
+            let scaled = (scale, level);
+            scale *= scale_factor;
+            scaled
+        })
+        .collect()
+}
+
 /// The score of a corner detected using the FAST
 /// detector is the largest threshold for which this
 /// pixel is still a corner. We input the threshold at which
@@ -293,16 +642,32 @@ fn search_span<F>(circle: &[i16; 16], length: u8, f: F) -> bool
 mod test {
 
     use super::{
+        corners_fast9_pyramid,
+        corners_harris,
+        corners_shi_tomasi,
         fast_corner_score,
+        harris_response,
         is_corner_fast9,
         is_corner_fast12,
-        Fast
+        refine_corners,
+        shi_tomasi_response,
+        Corner,
+        CornerDetectionOptions,
+        Fast,
+        SubPixelCorner,
+        SubPixelOptions
     };
     use image::{
         GrayImage,
-        ImageBuffer
+        ImageBuffer,
+        Luma
+    };
+    use definitions::Position;
+    use utils::gray_bench_image;
+    use test::{
+        Bencher,
+        black_box
     };
-    use test::Bencher;
 
     #[test]
     fn test_is_corner_fast12_12_contiguous_darker_pixels() {
@@ -480,4 +845,194 @@ mod test {
         let score = fast_corner_score(&image, 9, 3, 3, Fast::Nine);
         assert_eq!(score, 9);
     }
+
+    /// A black image with a white square whose top left corner
+    /// is at (left, top), and which has the given side length.
+    fn square_image(width: u32, height: u32, left: u32, top: u32, side: u32) -> GrayImage {
+        GrayImage::from_fn(width, height, |x, y| {
+            if x >= left && x < left + side && y >= top && y < top + side {
+                Luma([255u8])
+            } else {
+                Luma([0u8])
+            }
+        })
+    }
+
+    fn assert_corners_near_square(corners: &[(u32, u32)], left: u32, top: u32, side: u32) {
+        let expected = [
+            (left, top),
+            (left + side - 1, top),
+            (left, top + side - 1),
+            (left + side - 1, top + side - 1)
+        ];
+        assert_eq!(corners.len(), 4, "corners: {:?}", corners);
+        for &(ex, ey) in &expected {
+            assert!(corners.iter().any(|&(x, y)| {
+                (x as i32 - ex as i32).abs() <= 1 && (y as i32 - ey as i32).abs() <= 1
+            }), "no corner found near {:?} in {:?}", (ex, ey), corners);
+        }
+    }
+
+    #[test]
+    fn test_harris_response_signs() {
+        let image = square_image(20, 20, 5, 5, 10);
+        let response = harris_response(&image, 0.05, 1.0);
+
+        // Corners have positive response, edges negative response,
+        // and flat regions zero response.
+        assert!(response.get_pixel(5, 5)[0] > 0.0);
+        assert!(response.get_pixel(10, 5)[0] < 0.0);
+        assert_eq!(response.get_pixel(10, 10)[0], 0.0);
+        assert_eq!(response.get_pixel(0, 19)[0], 0.0);
+    }
+
+    #[test]
+    fn test_shi_tomasi_response() {
+        let image = square_image(20, 20, 5, 5, 10);
+        let response = shi_tomasi_response(&image, 1.0);
+
+        assert!(response.get_pixel(5, 5)[0] > 100.0);
+        // The smaller eigenvalue is close to zero along straight edges
+        assert!(response.get_pixel(10, 5)[0].abs() < 1.0);
+        assert_eq!(response.get_pixel(10, 10)[0], 0.0);
+    }
+
+    #[test]
+    fn test_corners_harris_finds_square_corners() {
+        let image = square_image(30, 25, 8, 6, 12);
+        let corners = corners_harris(&image, 0.05, CornerDetectionOptions::new(100.0, 3));
+        let locations: Vec<(u32, u32)> = corners.iter().map(|c| (c.x, c.y)).collect();
+        assert_corners_near_square(&locations, 8, 6, 12);
+    }
+
+    #[test]
+    fn test_corners_shi_tomasi_finds_square_corners() {
+        let image = square_image(30, 25, 8, 6, 12);
+        let corners = corners_shi_tomasi(&image, CornerDetectionOptions::new(100.0, 3));
+        let locations: Vec<(u32, u32)> = corners.iter().map(|c| (c.x, c.y)).collect();
+        assert_corners_near_square(&locations, 8, 6, 12);
+    }
+
+    #[test]
+    fn test_corners_shi_tomasi_min_distance() {
+        let image = gray_bench_image(40, 40);
+        let min_distance = 4;
+        let corners = corners_shi_tomasi(&image, CornerDetectionOptions::new(1.0, min_distance));
+
+        assert!(corners.len() > 0);
+        for (i, c) in corners.iter().enumerate() {
+            if i > 0 {
+                assert!(corners[i - 1].score >= c.score);
+            }
+            for d in &corners[i + 1..] {
+                let dx = (c.x as i32 - d.x as i32).abs() as u32;
+                let dy = (c.y as i32 - d.y as i32).abs() as u32;
+                assert!(dx > min_distance || dy > min_distance);
+            }
+        }
+    }
+
+    #[bench]
+    fn bench_corners_harris(b: &mut Bencher) {
+        let image = gray_bench_image(200, 200);
+        b.iter(|| {
+            let corners = corners_harris(&image, 0.05, CornerDetectionOptions::new(1000.0, 5));
+            black_box(corners);
+        });
+    }
+
+    #[bench]
+    fn bench_corners_shi_tomasi(b: &mut Bencher) {
+        let image = gray_bench_image(200, 200);
+        b.iter(|| {
+            let corners = corners_shi_tomasi(&image, CornerDetectionOptions::new(100.0, 5));
+            black_box(corners);
+        });
+    }
+
+    /// A checkerboard pattern with a single X-junction at (cx, cy). Pixel (x, y)
+    /// covers the square [x - 0.5, x + 0.5] * [y - 0.5, y + 0.5], and has intensity
+    /// proportional to the fraction of its area that is white.
+    fn x_junction_image(width: u32, height: u32, cx: f32, cy: f32) -> GrayImage {
+        let samples = 16;
+        GrayImage::from_fn(width, height, |x, y| {
+            let mut white = 0;
+            for sy in 0..samples {
+                for sx in 0..samples {
+                    let u = x as f32 - 0.5 + (sx as f32 + 0.5) / samples as f32;
+                    let v = y as f32 - 0.5 + (sy as f32 + 0.5) / samples as f32;
+                    if (u > cx) != (v > cy) {
+                        white += 1;
+                    }
+                }
+            }
+            Luma([(255 * white / (samples * samples)) as u8])
+        })
+    }
+
+    #[test]
+    fn test_refine_corners_x_junction() {
+        let (cx, cy) = (10.3f32, 12.6f32);
+        let image = x_junction_image(24, 24, cx, cy);
+
+        let corners = vec![Corner::new(10, 13, 5.0), Corner::new(11, 12, 3.0)];
+        let refined = refine_corners(&image, &corners, SubPixelOptions::new(4));
+
+        assert_eq!(refined.len(), 2);
+        for (r, c) in refined.iter().zip(corners.iter()) {
+            assert!((r.x - cx).abs() < 0.1, "refined {:?}", r);
+            assert!((r.y - cy).abs() < 0.1, "refined {:?}", r);
+            assert_eq!(r.score, c.score);
+        }
+    }
+
+    #[test]
+    fn test_refine_corners_flat_region_keeps_initial_location() {
+        let image = GrayImage::from_pixel(10, 10, Luma([50u8]));
+        let corners = vec![Corner::new(4, 5, 1.0)];
+        let refined = refine_corners(&image, &corners, SubPixelOptions::new(2));
+
+        assert_eq!(refined, vec![SubPixelCorner::new(4.0, 5.0, 1.0)]);
+    }
+
+    #[test]
+    fn test_sub_pixel_corner_position() {
+        let corner = SubPixelCorner::new(3.4, 7.6, 1.0);
+        assert_eq!((corner.x(), corner.y()), (3, 8));
+    }
+
+    #[bench]
+    fn bench_refine_corners(b: &mut Bencher) {
+        let image = gray_bench_image(200, 200);
+        let corners = corners_shi_tomasi(&image, CornerDetectionOptions::new(100.0, 5));
+        b.iter(|| {
+            let refined = refine_corners(&image, &corners, SubPixelOptions::new(5));
+            black_box(refined);
+        });
+    }
+
+    #[test]
+    fn test_corners_fast9_pyramid() {
+        let image = square_image(64, 64, 16, 16, 32);
+        let corners = corners_fast9_pyramid(&image, 50, 3, 2.0);
+
+        // The square's corners are detected at every level
+        for level in 0..3 {
+            let level_corners: Vec<_> = corners.iter().filter(|c| c.level == level).collect();
+            assert!(level_corners.len() >= 4, "level {}: {:?}", level, level_corners);
+            for c in level_corners {
+                assert_eq!(c.scale, 2f32.powi(level as i32));
+                let near = |a: f32, b: f32| (a - b).abs() <= 2.0 * c.scale;
+                assert!((near(c.x, 16.0) || near(c.x, 47.0)) && (near(c.y, 16.0) || near(c.y, 47.0)),
+                    "corner {:?} is not near a corner of the square", c);
+            }
+        }
+    }
+
+    #[test]
+    fn test_corners_fast9_pyramid_stops_at_small_levels() {
+        let image = square_image(20, 20, 5, 5, 10);
+        let corners = corners_fast9_pyramid(&image, 50, 10, 2.0);
+        assert!(corners.iter().all(|c| c.level < 2));
+    }
 }
@@ -105,6 +105,8 @@ implement_clamp!(u32, u8, u8::MIN, u8::MAX, u8::MIN as u32, u8::MAX as u32);
 implement_clamp!(i32, u16, u16::MIN, u16::MAX, u16::MIN as i32, u16::MAX as i32);
 implement_clamp!(i32, i16, i16::MIN, i16::MAX, i16::MIN as i32, i16::MAX as i32);
 implement_clamp!(u16, u8, u8::MIN, u8::MAX, u8::MIN as u16, u8::MAX as u16);
+implement_clamp!(f64, f32, f32::MIN, f32::MAX, f32::MIN as f64, f32::MAX as f64);
+implement_clamp!(f32, f32, f32::MIN, f32::MAX, f32::MIN, f32::MAX);
 
 #[cfg(test)]
 mod test {
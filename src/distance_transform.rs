@@ -0,0 +1,380 @@
+//! Functions for computing distance transforms - the distance of each pixel in an
+//! image from the nearest pixel of interest.
+//!
+//! All functions in this module treat their input as a binary image: pixels with non-zero
+//! intensity (e.g. the white pixels in the output of `contrast::threshold`) are foreground,
+//! and all other pixels are background.
+
+use image::{
+    GrayImage,
+    ImageBuffer,
+    Luma
+};
+
+use definitions::{
+    Image
+};
+
+use std::cmp;
+use std::{f32, f64, u8, u32};
+
+/// A norm on two dimensional vectors, used to determine
+/// the distance between pixels.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Norm {
+    /// Defines d((x1, y1), (x2, y2)) to be abs(x1 - x2) + abs(y1 - y2).
+    /// Also known as the Manhattan or city block norm.
+    L1,
+    /// Defines d((x1, y1), (x2, y2)) to be max(abs(x1 - x2), abs(y1 - y2)).
+    /// Also known as the chessboard norm.
+    LInf
+}
+
+/// Returns an image showing the distance of each pixel from the nearest foreground pixel
+/// under the given norm. Distances greater than 255 are clamped to 255, and if there are
+/// no foreground pixels in the image then all pixels have value 255.
+pub fn distance_transform(image: &GrayImage, norm: Norm) -> GrayImage {
+    let mut out = image.clone();
+    distance_transform_mut(&mut out, norm);
+    out
+}
+
+/// Updates an image in place to show the distance of each pixel from the nearest
+/// foreground pixel under the given norm. See `distance_transform`.
+pub fn distance_transform_mut(image: &mut GrayImage, norm: Norm) {
+    let distances = chamfer_distances(image, norm, DistanceFrom::Foreground);
+    for (p, d) in image.iter_mut().zip(distances.iter()) {
+        *p = cmp::min(*d, u8::MAX as u32) as u8;
+    }
+}
+
+/// Which pixels to measure distances from.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub(crate) enum DistanceFrom {
+    /// Distance to the nearest non-zero pixel.
+    Foreground,
+    /// Distance to the nearest zero pixel.
+    Background
+}
+
+/// Returns, for each pixel in row-major order, its distance under the given norm to the
+/// nearest pixel of the requested kind, or `u32::MAX` if there is no such pixel.
+/// Computed using two passes of a 3x3 chamfer mask, which is exact for the L1 and LInf norms.
+pub(crate) fn chamfer_distances(image: &GrayImage, norm: Norm, from: DistanceFrom) -> Vec<u32> {
+    let (width, height) = image.dimensions();
+    let (w, h) = (width as usize, height as usize);
+    let to_foreground = from == DistanceFrom::Foreground;
+
+    let mut distances: Vec<u32> = image
+        .iter()
+        .map(|p| if (*p > 0) == to_foreground { 0 } else { u32::MAX })
+        .collect();
+
+    let diagonals = norm == Norm::LInf;
+
+    // Forward pass: propagate distances from the top left.
+    for y in 0..h {
+        for x in 0..w {
+            let mut d = distances[y * w + x];
+            if x > 0 {
+                d = cmp::min(d, distances[y * w + x - 1].saturating_add(1));
+            }
+            if y > 0 {
+                d = cmp::min(d, distances[(y - 1) * w + x].saturating_add(1));
+                if diagonals && x > 0 {
+                    d = cmp::min(d, distances[(y - 1) * w + x - 1].saturating_add(1));
+                }
+                if diagonals && x + 1 < w {
+                    d = cmp::min(d, distances[(y - 1) * w + x + 1].saturating_add(1));
+                }
+            }
+            distances[y * w + x] = d;
+        }
+    }
+
+    // Backward pass: propagate distances from the bottom right.
+    for y in (0..h).rev() {
+        for x in (0..w).rev() {
+            let mut d = distances[y * w + x];
+            if x + 1 < w {
+                d = cmp::min(d, distances[y * w + x + 1].saturating_add(1));
+            }
+            if y + 1 < h {
+                d = cmp::min(d, distances[(y + 1) * w + x].saturating_add(1));
+                if diagonals && x + 1 < w {
+                    d = cmp::min(d, distances[(y + 1) * w + x + 1].saturating_add(1));
+                }
+                if diagonals && x > 0 {
+                    d = cmp::min(d, distances[(y + 1) * w + x - 1].saturating_add(1));
+                }
+            }
+            distances[y * w + x] = d;
+        }
+    }
+
+    distances
+}
+
+/// Stand-in for an infinite squared distance. Using a finite value avoids
+/// computing `inf - inf` when intersecting parabolas.
+const FAR: f64 = 1e20;
+
+/// Returns an image showing the exact Euclidean distance of each pixel from the
+/// nearest foreground pixel. If there are no foreground pixels in the image then
+/// all pixels have value `f32::INFINITY`.
+///
+/// Uses the linear time algorithm from [Distance Transforms of Sampled Functions]
+/// (Felzenszwalb and Huttenlocher), applied first to columns and then to rows.
+///
+/// [Distance Transforms of Sampled Functions]: http://cs.brown.edu/~pff/papers/dt-final.pdf
+pub fn euclidean_distance_transform(image: &GrayImage) -> Image<Luma<f32>> {
+    let (width, height) = image.dimensions();
+    let (w, h) = (width as usize, height as usize);
+    let mut squared: Vec<f64> = image
+        .iter()
+        .map(|p| if *p > 0 { 0f64 } else { FAR })
+        .collect();
+
+    let n = cmp::max(w, h);
+    let mut f = vec![0f64; n];
+    let mut d = vec![0f64; n];
+    let mut v = vec![0usize; n];
+    let mut z = vec![0f64; n + 1];
+
+    for x in 0..w {
+        for y in 0..h {
+            f[y] = squared[y * w + x];
+        }
+        lower_envelope(&f[..h], &mut d[..h], &mut v, &mut z);
+        for y in 0..h {
+            squared[y * w + x] = d[y];
+        }
+    }
+
+    for y in 0..h {
+        f[..w].copy_from_slice(&squared[y * w..(y + 1) * w]);
+        lower_envelope(&f[..w], &mut d[..w], &mut v, &mut z);
+        squared[y * w..(y + 1) * w].copy_from_slice(&d[..w]);
+    }
+
+    let distances = squared
+        .iter()
+        .map(|s| if *s >= FAR { f32::INFINITY } else { s.sqrt() as f32 })
+        .collect();
+
+    ImageBuffer::from_raw(width, height, distances).unwrap()
+}
+
+/// One dimensional squared Euclidean distance transform of a sampled function `f`,
+/// computed as the lower envelope of the parabolas rooted at each sample.
+/// Writes results to `d`. `v` and `z` are scratch buffers with lengths at
+/// least `f.len()` and `f.len() + 1` respectively.
+fn lower_envelope(f: &[f64], d: &mut [f64], v: &mut [usize], z: &mut [f64]) {
+    let n = f.len();
+    if n == 0 {
+        return;
+    }
+
+    // Index of the rightmost parabola in the lower envelope.
+    let mut k = 0;
+    v[0] = 0;
+    z[0] = f64::NEG_INFINITY;
+    z[1] = f64::INFINITY;
+
+    for q in 1..n {
+        let mut s = intersection(f, v[k], q);
+        // z[0] is -infinity, so this loop always terminates with k >= 0.
+        while s <= z[k] {
+            k -= 1;
+            s = intersection(f, v[k], q);
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = f64::INFINITY;
+    }
+
+    k = 0;
+    for q in 0..n {
+        while z[k + 1] < q as f64 {
+            k += 1;
+        }
+        let p = v[k];
+        let dq = q as f64 - p as f64;
+        d[q] = dq * dq + f[p];
+    }
+}
+
+/// Horizontal position of the intersection of the parabolas rooted at p and q, where p < q.
+fn intersection(f: &[f64], p: usize, q: usize) -> f64 {
+    ((f[q] + (q * q) as f64) - (f[p] + (p * p) as f64)) / (2 * (q - p)) as f64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::{
+        GrayImage,
+        ImageBuffer,
+        Luma
+    };
+    use definitions::{
+        Image
+    };
+    use quickcheck::{
+        quickcheck,
+        TestResult
+    };
+    use utils::{
+        GrayTestImage,
+        pixel_diff_summary
+    };
+    use std::{cmp, f32};
+    use test::{
+        Bencher,
+        black_box
+    };
+
+    #[test]
+    fn test_distance_transform_l1() {
+        let image: GrayImage = ImageBuffer::from_raw(5, 4, vec![
+            0,   0,   0,   0,   0,
+            0,   0,   0,   0,   0,
+            0,   0, 255,   0,   0,
+            0,   0,   0,   0,   0]).unwrap();
+
+        let expected: GrayImage = ImageBuffer::from_raw(5, 4, vec![
+            4,   3,   2,   3,   4,
+            3,   2,   1,   2,   3,
+            2,   1,   0,   1,   2,
+            3,   2,   1,   2,   3]).unwrap();
+
+        assert_pixels_eq!(distance_transform(&image, Norm::L1), expected);
+    }
+
+    #[test]
+    fn test_distance_transform_linf() {
+        let image: GrayImage = ImageBuffer::from_raw(5, 4, vec![
+            0,   0,   0,   0,   0,
+            0,   0,   0,   0,   0,
+            0,   0, 255,   0,   0,
+            0,   0,   0,   0,   0]).unwrap();
+
+        let expected: GrayImage = ImageBuffer::from_raw(5, 4, vec![
+            2,   2,   2,   2,   2,
+            2,   1,   1,   1,   2,
+            2,   1,   0,   1,   2,
+            2,   1,   1,   1,   2]).unwrap();
+
+        assert_pixels_eq!(distance_transform(&image, Norm::LInf), expected);
+    }
+
+    #[test]
+    fn test_distance_transform_without_foreground() {
+        let image = GrayImage::new(3, 2);
+        let expected = GrayImage::from_pixel(3, 2, Luma([255u8]));
+        assert_pixels_eq!(distance_transform(&image, Norm::L1), expected);
+    }
+
+    #[test]
+    fn test_euclidean_distance_transform() {
+        let image: GrayImage = ImageBuffer::from_raw(4, 3, vec![
+            255, 0, 0,   0,
+              0, 0, 0,   0,
+              0, 0, 0, 255]).unwrap();
+
+        let s2 = 2f64.sqrt() as f32;
+        let expected: Image<Luma<f32>> = ImageBuffer::from_raw(4, 3, vec![
+            0.0, 1.0, 2.0, 2.0,
+            1.0,  s2,  s2, 1.0,
+            2.0, 2.0, 1.0, 0.0]).unwrap();
+
+        assert_pixels_eq!(euclidean_distance_transform(&image), expected);
+    }
+
+    #[test]
+    fn test_euclidean_distance_transform_without_foreground() {
+        let image = GrayImage::new(3, 2);
+        let expected = ImageBuffer::from_pixel(3, 2, Luma([f32::INFINITY]));
+        assert_pixels_eq!(euclidean_distance_transform(&image), expected);
+    }
+
+    /// Computes distances by searching over all foreground pixels.
+    fn distance_transform_reference<F>(image: &GrayImage, dist: F) -> Image<Luma<f32>>
+        where F: Fn(i32, i32) -> f32
+    {
+        let foreground: Vec<(i32, i32)> = image
+            .enumerate_pixels()
+            .filter(|&(_, _, p)| p[0] > 0)
+            .map(|(x, y, _)| (x as i32, y as i32))
+            .collect();
+
+        ImageBuffer::from_fn(image.width(), image.height(), |x, y| {
+            let d = foreground
+                .iter()
+                .map(|&(fx, fy)| dist(x as i32 - fx, y as i32 - fy))
+                .fold(f32::INFINITY, f32::min);
+            Luma([d])
+        })
+    }
+
+    fn clamp_to_u8(image: &Image<Luma<f32>>) -> GrayImage {
+        ImageBuffer::from_fn(image.width(), image.height(), |x, y| {
+            Luma([image.get_pixel(x, y)[0].min(255f32) as u8])
+        })
+    }
+
+    #[test]
+    fn test_distance_transform_matches_reference_implementation() {
+        fn prop(image: GrayTestImage) -> TestResult {
+            let l1 = distance_transform_reference(&image.0, |dx, dy| (dx.abs() + dy.abs()) as f32);
+            let linf = distance_transform_reference(&image.0, |dx, dy| cmp::max(dx.abs(), dy.abs()) as f32);
+
+            if let Some(err) = pixel_diff_summary(&distance_transform(&image.0, Norm::L1), &clamp_to_u8(&l1)) {
+                return TestResult::error(err);
+            }
+            if let Some(err) = pixel_diff_summary(&distance_transform(&image.0, Norm::LInf), &clamp_to_u8(&linf)) {
+                return TestResult::error(err);
+            }
+            TestResult::passed()
+        }
+        quickcheck(prop as fn(GrayTestImage) -> TestResult);
+    }
+
+    #[test]
+    fn test_euclidean_distance_transform_matches_reference_implementation() {
+        fn prop(image: GrayTestImage) -> TestResult {
+            let expected = distance_transform_reference(&image.0, |dx, dy| ((dx * dx + dy * dy) as f64).sqrt() as f32);
+            let actual = euclidean_distance_transform(&image.0);
+            match pixel_diff_summary(&actual, &expected) {
+                None => TestResult::passed(),
+                Some(err) => TestResult::error(err)
+            }
+        }
+        quickcheck(prop as fn(GrayTestImage) -> TestResult);
+    }
+
+    fn distance_transform_bench_image(width: u32, height: u32) -> GrayImage {
+        ImageBuffer::from_fn(width, height, |x, y| {
+            if (x * 7 + y * 13) % 97 == 0 { Luma([255u8]) } else { Luma([0u8]) }
+        })
+    }
+
+    #[bench]
+    fn bench_distance_transform_l1(b: &mut Bencher) {
+        let image = distance_transform_bench_image(200, 200);
+        b.iter(|| {
+            let distances = distance_transform(&image, Norm::L1);
+            black_box(distances);
+        });
+    }
+
+    #[bench]
+    fn bench_euclidean_distance_transform(b: &mut Bencher) {
+        let image = distance_transform_bench_image(200, 200);
+        b.iter(|| {
+            let distances = euclidean_distance_transform(&image);
+            black_box(distances);
+        });
+    }
+}
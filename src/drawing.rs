@@ -556,8 +556,10 @@ fn draw_if_in_bounds<I>(image: &mut I, x: i32, y: i32, color: I::Pixel)
 /// A 2D point.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Point<T: Copy + PartialEq + Eq> {
-    x: T,
-    y: T,
+    /// x-coordinate.
+    pub x: T,
+    /// y-coordinate.
+    pub y: T,
 }
 
 impl<T: Copy + PartialEq + Eq> Point<T> {
@@ -669,6 +671,46 @@ pub fn draw_convex_polygon_mut<I>(image: &mut I, poly: &[Point<i32>], color: I::
     }
 }
 
+/// Draws as much of the outline of a polygon as lies within image bounds. The provided
+/// list of points should be an open path, i.e. the first and last points must not be equal.
+/// An implicit edge is added from the last to the first point in the slice.
+///
+/// Unlike `draw_convex_polygon` the polygon is not filled, so it need not be convex.
+pub fn draw_hollow_polygon<I>(image: &I, poly: &[Point<i32>], color: I::Pixel) -> Image<I::Pixel>
+    where I : GenericImage, I::Pixel: 'static
+{
+    let mut out = ImageBuffer::new(image.width(), image.height());
+    out.copy_from(image, 0, 0);
+    draw_hollow_polygon_mut(&mut out, poly, color);
+    out
+}
+
+/// Draws as much of the outline of a polygon as lies within image bounds. The provided
+/// list of points should be an open path, i.e. the first and last points must not be equal.
+/// An implicit edge is added from the last to the first point in the slice.
+///
+/// Unlike `draw_convex_polygon_mut` the polygon is not filled, so it need not be convex.
+pub fn draw_hollow_polygon_mut<I>(image: &mut I, poly: &[Point<i32>], color: I::Pixel)
+    where I : GenericImage, I::Pixel: 'static
+{
+    if poly.len() == 0 {
+        return;
+    }
+    if poly.len() > 1 && poly[0] == poly[poly.len() - 1] {
+        panic!("First point {:?} == last point {:?}", poly[0], poly[poly.len() - 1]);
+    }
+    if poly.len() == 1 {
+        draw_if_in_bounds(image, poly[0].x, poly[0].y, color);
+        return;
+    }
+
+    for i in 0..poly.len() {
+        let p0 = poly[i];
+        let p1 = poly[(i + 1) % poly.len()];
+        draw_line_segment_mut(image, (p0.x as f32, p0.y as f32), (p1.x as f32, p1.y as f32), color);
+    }
+}
+
 /// Draws as much of a cubic bezier curve as lies within image bounds.
 pub fn draw_cubic_bezier_curve<I>(image: &I, start: (f32, f32), end: (f32, f32), control_a: (f32, f32), control_b: (f32, f32), color: I::Pixel) -> Image<I::Pixel>
     where I : GenericImage, I::Pixel: 'static
@@ -1162,6 +1204,26 @@ mod test {
         assert_pixels_eq!(actual, expected);
     }
 
+    #[test]
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    fn test_draw_hollow_polygon() {
+        let image: GrayImage = ImageBuffer::from_pixel(5, 5, Luma([1u8]));
+
+        let expected: GrayImage = ImageBuffer::from_raw(5, 5, vec![
+            4, 4, 4, 4, 4,
+            4, 1, 1, 4, 1,
+            4, 1, 4, 1, 1,
+            4, 1, 1, 4, 1,
+            4, 4, 4, 4, 4]).unwrap();
+
+        // A non-convex polygon with a notch in its right side
+        let poly = vec![
+            Point::new(0, 0), Point::new(4, 0), Point::new(2, 2), Point::new(4, 4), Point::new(0, 4)];
+
+        let actual = draw_hollow_polygon(&image, &poly, Luma([4u8]));
+        assert_pixels_eq!(actual, expected);
+    }
+
     #[test]
     #[cfg_attr(rustfmt, rustfmt_skip)]
     fn test_draw_filled_rect() {
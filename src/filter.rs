@@ -1,10 +1,7 @@
 //! Functions for filtering images.
 
 use image::{
-    GrayImage,
     GenericImage,
-    ImageBuffer,
-    Luma,
     Pixel,
     Primitive
 };
@@ -15,6 +12,7 @@ use integralimage::{
 };
 
 use map::{
+    map_subpixels,
     WithChannel,
     ChannelMap
 };
@@ -29,54 +27,133 @@ use num::{
 };
 
 use conv::ValueInto;
-use math::cast;
+use math::{cast, round_clamp};
 use std::cmp;
 use std::f32;
 
-/// Convolves an 8bpp grayscale image with a kernel of width (2 * `x_radius` + 1)
+/// How to treat locations outside an image when filtering it.
+///
+/// The example for each variant shows how the row `a b c d` is padded
+/// by three pixels on each side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorderMode<P> {
+    /// Locations outside the image take the given value: `x x x | a b c d | x x x`.
+    Constant(P),
+    /// The edge pixels are repeated indefinitely: `a a a | a b c d | d d d`.
+    Replicate,
+    /// The image is reflected about its boundary, so that edge pixels
+    /// are repeated: `c b a | a b c d | d c b`.
+    Reflect,
+    /// The image is reflected about its edge pixels, which are not
+    /// repeated: `d c b | a b c d | c b a`.
+    Reflect101,
+    /// The image is tiled: `b c d | a b c d | a b c`.
+    Wrap,
+}
+
+impl<P> BorderMode<P> {
+    /// Maps a coordinate along an axis of length `len` to the coordinate within
+    /// the image whose value it should take, or `None` if it should take the
+    /// value of a `Constant` border.
+    pub(crate) fn map_coordinate(&self, i: i64, len: u32) -> Option<u32> {
+        let n = len as i64;
+        if i >= 0 && i < n {
+            return Some(i as u32);
+        }
+        if n == 0 {
+            return None;
+        }
+        let modulo = |i: i64, m: i64| ((i % m) + m) % m;
+        let mapped = match *self {
+            BorderMode::Constant(_) => return None,
+            BorderMode::Replicate => cmp::max(0, cmp::min(i, n - 1)),
+            BorderMode::Reflect => {
+                let r = modulo(i, 2 * n);
+                if r < n { r } else { 2 * n - 1 - r }
+            },
+            BorderMode::Reflect101 => {
+                if n == 1 {
+                    return Some(0);
+                }
+                let r = modulo(i, 2 * n - 2);
+                if r < n { r } else { 2 * n - 2 - r }
+            },
+            BorderMode::Wrap => modulo(i, n),
+        };
+        Some(mapped as u32)
+    }
+}
+
+/// Returns the pixel at location (x, y), or the value determined by `border`
+/// if this location is outside the image. The image must be non-empty.
+pub(crate) fn pixel_with_border<P>(image: &Image<P>, x: i64, y: i64, border: BorderMode<P>) -> P
+    where P: Pixel + 'static
+{
+    let (width, height) = image.dimensions();
+    match (border.map_coordinate(x, width), border.map_coordinate(y, height)) {
+        (Some(x_p), Some(y_p)) => unsafe { image.unsafe_get_pixel(x_p, y_p) },
+        _ => match border {
+            BorderMode::Constant(value) => value,
+            _ => panic!("Cannot pad an empty image")
+        }
+    }
+}
+
+/// Convolves an image with a kernel of width (2 * `x_radius` + 1)
 /// and height (2 * `y_radius` + 1) whose entries are equal and
 /// sum to one. i.e. each output pixel is the unweighted mean of
 /// a rectangular region surrounding its corresponding input pixel.
-/// We handle locations where the kernel would extend past the image's
-/// boundary by treating the image as if its boundary pixels were
-/// repeated indefinitely.
+/// Each channel is filtered independently. Locations where the
+/// kernel extends past the image's boundary are handled as specified
+/// by `border`.
+///
+/// Intermediate sums are computed at type f64, which is exact for all
+/// integer subpixel types supported by this library. Results are truncated
+/// rather than rounded when converting back to integer subpixel types.
 // TODO: for small kernels we probably want to do the convolution
 // TODO: directly instead of using an integral image.
-// TODO: more formats!
-pub fn box_filter(image: &GrayImage, x_radius: u32, y_radius: u32)
-        -> Image<Luma<u8>> {
-
+pub fn box_filter<P>(image: &Image<P>, x_radius: u32, y_radius: u32, border: BorderMode<P>)
+        -> Image<P>
+    where P: Pixel + 'static,
+          <P as Pixel>::Subpixel: ValueInto<f64> + Clamp<f64>
+{
     let (width, height) = image.dimensions();
-    let mut out = ImageBuffer::new(width, height);
+    let mut out = Image::<P>::new(width, height);
+    if width == 0 || height == 0 {
+        return out;
+    }
 
-    let kernel_width = 2 * x_radius + 1;
-    let kernel_height = 2 * y_radius + 1;
+    let num_channels = P::channel_count() as usize;
+    let kernel_width = (2 * x_radius + 1) as f64;
+    let kernel_height = (2 * y_radius + 1) as f64;
 
-    let mut row_buffer = vec![0; (width + 2 * x_radius) as usize];
+    // TODO: This way we pay rounding errors for each of the
+    // TODO: x and y convolutions. Is there a better way?
+    let mut row_buffer = vec![0f64; (width + 2 * x_radius) as usize * num_channels];
     for y in 0..height {
-        row_running_sum(image, y, &mut row_buffer, x_radius);
-        let val = row_buffer[(2 * x_radius) as usize]/kernel_width;
-        unsafe { out.unsafe_put_pixel(0, y, Luma([val as u8])); }
-        for x in 1..width {
-            // TODO: This way we pay rounding errors for each of the
-            // TODO: x and y convolutions. Is there a better way?
-            let u = (x + 2 * x_radius) as usize;
-            let l = (x - 1) as usize;
-            let val = (row_buffer[u] - row_buffer[l])/kernel_width;
-            unsafe { out.unsafe_put_pixel(x, y, Luma([val as u8])); }
+        row_running_sum(image, y, &mut row_buffer, x_radius, border);
+        for x in 0..width {
+            let u = (x + 2 * x_radius) as usize * num_channels;
+            let out_channels = out.get_pixel_mut(x, y).channels_mut();
+            for (c, out_channel) in out_channels.iter_mut().enumerate() {
+                let lower = if x == 0 { 0f64 } else { row_buffer[(x - 1) as usize * num_channels + c] };
+                *out_channel = <P as Pixel>::Subpixel::clamp((row_buffer[u + c] - lower) / kernel_width);
+            }
         }
     }
 
-    let mut col_buffer = vec![0; (height + 2 * y_radius) as usize];
+    // The horizontal pass maps a constant row to itself, so we
+    // can use the same border for the vertical pass.
+    let mut col_buffer = vec![0f64; (height + 2 * y_radius) as usize * num_channels];
     for x in 0..width {
-        column_running_sum(&out, x, &mut col_buffer, y_radius);
-        let val = col_buffer[(2 * y_radius) as usize]/kernel_height;
-        unsafe { out.unsafe_put_pixel(x, 0, Luma([val as u8])); }
-        for y in 1..height {
-            let u = (y + 2 * y_radius) as usize;
-            let l = (y - 1) as usize;
-            let val = (col_buffer[u] - col_buffer[l])/kernel_height;
-            unsafe { out.unsafe_put_pixel(x, y, Luma([val as u8])); }
+        column_running_sum(&out, x, &mut col_buffer, y_radius, border);
+        for y in 0..height {
+            let u = (y + 2 * y_radius) as usize * num_channels;
+            let out_channels = out.get_pixel_mut(x, y).channels_mut();
+            for (c, out_channel) in out_channels.iter_mut().enumerate() {
+                let lower = if y == 0 { 0f64 } else { col_buffer[(y - 1) as usize * num_channels + c] };
+                *out_channel = <P as Pixel>::Subpixel::clamp((col_buffer[u + c] - lower) / kernel_height);
+            }
         }
     }
 
@@ -105,8 +182,9 @@ impl<'a, K: Num + Copy + 'a> Kernel<'a, K> {
     }
 
     /// Returns 2d correlation of an image. Intermediate calculations are performed
-    /// at type K, and the results converted to pixel Q via f. Pads by continuity.
-    pub fn filter<P, F, Q>(&self, image: &Image<P>, mut f: F) -> Image<Q>
+    /// at type K, and the results converted to pixel Q via f. Locations where the
+    /// kernel extends past the image's boundary are handled as specified by `border`.
+    pub fn filter<P, F, Q>(&self, image: &Image<P>, border: BorderMode<P>, mut f: F) -> Image<Q>
         where P: Pixel + 'static,
               <P as Pixel>::Subpixel: ValueInto<K>,
               Q: Pixel + 'static,
@@ -117,24 +195,28 @@ impl<'a, K: Num + Copy + 'a> Kernel<'a, K> {
         let num_channels = P::channel_count() as usize;
         let zero = K::zero();
         let mut acc = vec![zero; num_channels];
-        let (k_width, k_height) = (self.width, self.height);
+        let (k_width, k_height) = (self.width as i64, self.height as i64);
+        let (half_width, half_height) = (k_width / 2, k_height / 2);
+
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                let inside = x >= half_width && x - half_width + k_width <= width as i64
+                    && y >= half_height && y - half_height + k_height <= height as i64;
 
-        for y in 0..height {
-            for x in 0..width {
                 for k_y in 0..k_height {
-                    let y_p = cmp::min(height + height - 1,
-                                       cmp::max(height, (height + y + k_y - k_height / 2))) - height;
+                    let y_p = y + k_y - half_height;
                     for k_x in 0..k_width {
-                        let x_p = cmp::min(width + width - 1,
-                                           cmp::max(width, (width + x + k_x - k_width / 2))) - width;
-                        let (p, k) = unsafe {
-                            (image.unsafe_get_pixel(x_p, y_p),
-                             *self.data.get_unchecked((k_y * k_width + k_x) as usize))
+                        let x_p = x + k_x - half_width;
+                        let p = if inside {
+                            unsafe { image.unsafe_get_pixel(x_p as u32, y_p as u32) }
+                        } else {
+                            pixel_with_border(image, x_p, y_p, border)
                         };
+                        let k = unsafe { *self.data.get_unchecked((k_y * k_width + k_x) as usize) };
                         accumulate(&mut acc, &p, k);
                     }
                 }
-                let out_channels = out.get_pixel_mut(x, y).channels_mut();
+                let out_channels = out.get_pixel_mut(x as u32, y as u32).channels_mut();
                 for (a, c) in acc.iter_mut().zip(out_channels.iter_mut()) {
                     f(c, *a);
                     *a = zero;
@@ -172,29 +254,149 @@ pub fn gaussian_blur_f32<P>(image: &Image<P>, sigma: f32) -> Image<P>
           <P as Pixel>::Subpixel: ValueInto<f32> + Clamp<f32>
 {
     let kernel = gaussian_kernel_f32(sigma);
-    separable_filter_equal(image, &kernel)
+    separable_filter_equal(image, &kernel, BorderMode::Replicate)
+}
+
+/// As `gaussian_blur_f32`, but with the kernel normalised to sum to one so
+/// that constant regions are unchanged by blurring. The image is blurred at
+/// type f32, and the results rounded rather than truncated when converting
+/// back to an integer subpixel type.
+pub(crate) fn normalized_gaussian_blur_f32<P>(image: &Image<P>, sigma: f32) -> Image<P>
+    where P: WithChannel<f32> + 'static,
+          <P as Pixel>::Subpixel: ValueInto<f32> + Clamp<f32>
+{
+    let mut kernel = gaussian_kernel_f32(sigma);
+    let sum: f32 = kernel.iter().sum();
+    for k in kernel.iter_mut() {
+        *k /= sum;
+    }
+
+    let float: Image<ChannelMap<P, f32>> = map_subpixels(image, |c| cast(c));
+    let blurred = separable_filter_equal(&float, &kernel, BorderMode::Replicate);
+
+    let mut out = Image::<P>::new(image.width(), image.height());
+    for (o, b) in out.iter_mut().zip(blurred.iter()) {
+        *o = round_clamp(*b);
+    }
+    out
+}
+
+/// Applies a bilateral filter to an image, smoothing it while preserving edges.
+///
+/// Each output pixel is a weighted mean of the input pixels within distance
+/// 2 * `sigma_spatial` of it. Weights are the product of a Gaussian of standard
+/// deviation `sigma_spatial` in the distance between pixel locations, and a Gaussian of
+/// standard deviation `sigma_range` in the Euclidean distance between pixel values.
+/// Locations outside the image are handled as specified by `border`.
+pub fn bilateral_filter<P>(image: &Image<P>, sigma_spatial: f32, sigma_range: f32,
+                           border: BorderMode<P>) -> Image<P>
+    where P: Pixel<Subpixel = u8> + 'static
+{
+    assert!(sigma_spatial > 0.0, "sigma_spatial must be positive");
+    assert!(sigma_range > 0.0, "sigma_range must be positive");
+
+    let (width, height) = image.dimensions();
+    let mut out = Image::<P>::new(width, height);
+    let num_channels = P::channel_count() as usize;
+
+    let radius = (2.0 * sigma_spatial).ceil() as i32;
+    let window_width = (2 * radius + 1) as usize;
+    let mut spatial_weights = vec![0f32; window_width * window_width];
+    for dy in -radius..radius + 1 {
+        for dx in -radius..radius + 1 {
+            let d2 = (dx * dx + dy * dy) as f32;
+            let idx = (dy + radius) as usize * window_width + (dx + radius) as usize;
+            spatial_weights[idx] = (-d2 / (2.0 * sigma_spatial * sigma_spatial)).exp();
+        }
+    }
+    let range_denom = 2.0 * sigma_range * sigma_range;
+
+    let mut centre = vec![0f32; num_channels];
+    let mut neighbour = vec![0f32; num_channels];
+    let mut acc = vec![0f32; num_channels];
+
+    for y in 0..height as i32 {
+        for x in 0..width as i32 {
+            let p = unsafe { image.unsafe_get_pixel(x as u32, y as u32) };
+            for (c, v) in centre.iter_mut().zip(p.channels().iter()) {
+                *c = *v as f32;
+            }
+
+            let inside = x >= radius && x + radius < width as i32
+                && y >= radius && y + radius < height as i32;
+
+            let mut weight_sum = 0f32;
+            for dy in -radius..radius + 1 {
+                for dx in -radius..radius + 1 {
+                    let q = if inside {
+                        unsafe { image.unsafe_get_pixel((x + dx) as u32, (y + dy) as u32) }
+                    } else {
+                        pixel_with_border(image, (x + dx) as i64, (y + dy) as i64, border)
+                    };
+                    let mut d2 = 0f32;
+                    for i in 0..num_channels {
+                        neighbour[i] = q.channels()[i] as f32;
+                        d2 += (neighbour[i] - centre[i]).powi(2);
+                    }
+                    let idx = (dy + radius) as usize * window_width + (dx + radius) as usize;
+                    let weight = spatial_weights[idx] * (-d2 / range_denom).exp();
+                    for i in 0..num_channels {
+                        acc[i] += weight * neighbour[i];
+                    }
+                    weight_sum += weight;
+                }
+            }
+
+            // weight_sum is at least one, as the centre pixel has weight one.
+            let out_channels = out.get_pixel_mut(x as u32, y as u32).channels_mut();
+            for (a, c) in acc.iter_mut().zip(out_channels.iter_mut()) {
+                *c = (*a / weight_sum).round() as u8;
+                *a = 0.0;
+            }
+        }
+    }
+
+    out
 }
 
 /// Returns 2d correlation of view with the outer product of the 1d
-/// kernels `h_kernel` and `v_kernel`.
-pub fn separable_filter<P, K>(image: &Image<P>, h_kernel: &[K], v_kernel: &[K])
-        -> Image<P>
+/// kernels `h_kernel` and `v_kernel`. Locations where the kernel extends
+/// past the image's boundary are handled as specified by `border`.
+pub fn separable_filter<P, K>(image: &Image<P>, h_kernel: &[K], v_kernel: &[K],
+                              border: BorderMode<P>) -> Image<P>
     where P: Pixel + 'static,
           <P as Pixel>::Subpixel: ValueInto<K> + Clamp<K>,
           K: Num + Copy
 {
-    let h = horizontal_filter(image, h_kernel);
-    vertical_filter(&h, v_kernel)
+    let h = horizontal_filter(image, h_kernel, border);
+    // Rows outside the horizontally filtered image are the horizontal
+    // filtering of constant rows outside the input.
+    let v_border = match border {
+        BorderMode::Constant(value) => {
+            let mut acc = vec![K::zero(); P::channel_count() as usize];
+            for k in h_kernel {
+                accumulate(&mut acc, &value, *k);
+            }
+            let mut filtered = value;
+            for (a, c) in acc.iter().zip(filtered.channels_mut().iter_mut()) {
+                *c = <P as Pixel>::Subpixel::clamp(*a);
+            }
+            BorderMode::Constant(filtered)
+        },
+        _ => border
+    };
+    vertical_filter(&h, v_kernel, v_border)
 }
 
 /// Returns 2d correlation of an image with the outer product of the 1d
-/// kernel filter with itself.
-pub fn separable_filter_equal<P, K>(image: &Image<P>, kernel: &[K])
+/// kernel filter with itself. Locations where the kernel extends past
+/// the image's boundary are handled as specified by `border`.
+pub fn separable_filter_equal<P, K>(image: &Image<P>, kernel: &[K], border: BorderMode<P>)
         -> Image<P>
     where P: Pixel + 'static,
           <P as Pixel>::Subpixel: ValueInto<K> + Clamp<K>,
           K: Num + Copy {
-    separable_filter(image, kernel, kernel)
+    separable_filter(image, kernel, kernel, border)
 }
 
 /// Returns 2d correlation of an image with a 3x3 row-major kernel. Intermediate calculations are
@@ -205,13 +407,13 @@ pub fn filter3x3<P, K, S>(image: &Image<P>, kernel: &[K]) -> Image<ChannelMap<P,
           P: WithChannel<S> + 'static,
           K: Num + Copy {
     let kernel = Kernel::new(kernel, 3, 3);
-    kernel.filter(image, |channel, acc| *channel = S::clamp(acc))
+    kernel.filter(image, BorderMode::Replicate, |channel, acc| *channel = S::clamp(acc))
 }
 
-///	Returns horizontal correlations between an image and a 1d kernel.
-/// Pads by continuity. Intermediate calculations are performed at
-/// type K.
-pub fn horizontal_filter<P, K>(image: &Image<P>, kernel: &[K]) -> Image<P>
+/// Returns horizontal correlations between an image and a 1d kernel.
+/// Intermediate calculations are performed at type K. Locations where the
+/// kernel extends past the image's boundary are handled as specified by `border`.
+pub fn horizontal_filter<P, K>(image: &Image<P>, kernel: &[K], border: BorderMode<P>) -> Image<P>
     where P: Pixel + 'static,
           <P as Pixel>::Subpixel: ValueInto<K> + Clamp<K>,
           K: Num + Copy
@@ -224,41 +426,33 @@ pub fn horizontal_filter<P, K>(image: &Image<P>, kernel: &[K]) -> Image<P>
     let zero = K::zero();
     let mut acc = vec![zero; P::channel_count() as usize];
     let k_width = kernel.len() as i32;
+    let half_k = k_width / 2;
 
     // Typically the image side will be much larger than the kernel length.
     // In that case we can remove a lot of bounds checks for most pixels.
-    if k_width >= width as i32 {
-        for y in 0..height {
-            for x in 0..width {
+    let (left_end, right_start) = if k_width >= width as i32 {
+        (width as i32, width as i32)
+    } else {
+        (half_k, width as i32 - half_k)
+    };
+
+    for y in 0..height {
+        for x in 0..width as i32 {
+            if x < left_end || x >= right_start {
+                // Left or right margin - need to handle the border
                 for (i, k) in kernel.iter().enumerate() {
-                    let x_unchecked = (x as i32) + i as i32 - k_width / 2;
-                    let x_p = cmp::max(0, cmp::min(x_unchecked, width as i32 - 1)) as u32;
-                    let p = unsafe { image.unsafe_get_pixel(x_p, y) };
+                    let x_unchecked = x + i as i32 - half_k;
+                    let p = pixel_with_border(image, x_unchecked as i64, y as i64, border);
                     accumulate(&mut acc, &p, *k);
                 }
-
-                let out_channels = out.get_pixel_mut(x, y).channels_mut();
-                for (a, c) in acc.iter_mut().zip(out_channels.iter_mut()) {
-                    *c = <P as Pixel>::Subpixel::clamp(*a);
-                    *a = zero;
+            } else {
+                // Neither margin - don't need bounds check on either side
+                for (i, k) in kernel.iter().enumerate() {
+                    let x_p = (x + i as i32 - half_k) as u32;
+                    let p = unsafe { image.unsafe_get_pixel(x_p, y) };
+                    accumulate(&mut acc, &p, *k);
                 }
             }
-        }
-
-        return out;
-    }
-
-    let half_k = k_width / 2;
-
-    for y in 0..height {
-        // Left margin - need to check lower bound only
-        for x in 0..half_k {
-            for (i, k) in kernel.iter().enumerate() {
-                let x_unchecked = (x as i32) + i as i32 - k_width / 2;
-                let x_p = cmp::max(0, x_unchecked) as u32;
-                let p = unsafe { image.unsafe_get_pixel(x_p, y) };
-                accumulate(&mut acc, &p, *k);
-            }
 
             let out_channels = out.get_pixel_mut(x as u32, y).channels_mut();
             for (a, c) in acc.iter_mut().zip(out_channels.iter_mut()) {
@@ -266,46 +460,15 @@ pub fn horizontal_filter<P, K>(image: &Image<P>, kernel: &[K]) -> Image<P>
                 *a = zero;
             }
         }
-
-        // Neither margin - don't need bounds check on either side
-        for x in half_k..(width as i32 - half_k) {
-            for (i, k) in kernel.iter().enumerate() {
-                let x_unchecked = (x as i32) + i as i32 - k_width / 2;
-                let x_p = x_unchecked as u32;
-                let p = unsafe { image.unsafe_get_pixel(x_p, y) };
-                accumulate(&mut acc, &p, *k);
-            }
-
-            let out_channels = out.get_pixel_mut(x as u32, y).channels_mut();
-            for (a, c) in acc.iter_mut().zip(out_channels.iter_mut()) {
-                *c = <P as Pixel>::Subpixel::clamp(*a);
-                *a = zero;
-            }
-        }
-
-        // Right margin - need to check upper bound only
-        for x in (width as i32 - half_k)..(width as i32) {
-            for (i, k) in kernel.iter().enumerate() {
-                let x_unchecked = (x as i32) + i as i32 - k_width / 2;
-                let x_p = cmp::min(x_unchecked, width as i32 - 1) as u32;
-                let p = unsafe { image.unsafe_get_pixel(x_p, y) };
-                accumulate(&mut acc, &p, *k);
-            }
-
-            let out_channels = out.get_pixel_mut(x as u32, y).channels_mut();
-            for (a, c) in acc.iter_mut().zip(out_channels.iter_mut()) {
-               *c = <P as Pixel>::Subpixel::clamp(*a);
-               *a = zero;
-            }
-        }
     }
 
     out
 }
 
-///	Returns horizontal correlations between an image and a 1d kernel.
-/// Pads by continuity.
-pub fn vertical_filter<P, K>(image: &Image<P>, kernel: &[K])
+/// Returns vertical correlations between an image and a 1d kernel.
+/// Intermediate calculations are performed at type K. Locations where the
+/// kernel extends past the image's boundary are handled as specified by `border`.
+pub fn vertical_filter<P, K>(image: &Image<P>, kernel: &[K], border: BorderMode<P>)
         -> Image<P>
     where P: Pixel + 'static,
           <P as Pixel>::Subpixel: ValueInto<K> + Clamp<K>,
@@ -318,87 +481,126 @@ pub fn vertical_filter<P, K>(image: &Image<P>, kernel: &[K])
     let zero = K::zero();
     let mut acc = vec![zero; P::channel_count() as usize];
     let k_height = kernel.len() as i32;
+    let half_k = k_height / 2;
 
     // Typically the image side will be much larger than the kernel length.
     // In that case we can remove a lot of bounds checks for most pixels.
-    if k_height >= height as i32 {
-        for y in 0..height {
-            for x in 0..width {
+    let (top_end, bottom_start) = if k_height >= height as i32 {
+        (height as i32, height as i32)
+    } else {
+        (half_k, height as i32 - half_k)
+    };
+
+    for y in 0..height as i32 {
+        for x in 0..width {
+            if y < top_end || y >= bottom_start {
+                // Top or bottom margin - need to handle the border
                 for (i, k) in kernel.iter().enumerate() {
-                    let y_unchecked = (y as i32) + i as i32 - k_height / 2;
-                    let y_p = cmp::max(0, cmp::min(y_unchecked, height as i32 - 1)) as u32;
+                    let y_unchecked = y + i as i32 - half_k;
+                    let p = pixel_with_border(image, x as i64, y_unchecked as i64, border);
+                    accumulate(&mut acc, &p, *k);
+                }
+            } else {
+                // Neither margin - don't need bounds check on either side
+                for (i, k) in kernel.iter().enumerate() {
+                    let y_p = (y + i as i32 - half_k) as u32;
                     let p = unsafe { image.unsafe_get_pixel(x, y_p) };
                     accumulate(&mut acc, &p, *k);
                 }
+            }
 
-                let out_channels = out.get_pixel_mut(x, y).channels_mut();
-                for (a, c) in acc.iter_mut().zip(out_channels.iter_mut()) {
-                    *c = <P as Pixel>::Subpixel::clamp(*a);
-                    *a = zero;
-                }
+            let out_channels = out.get_pixel_mut(x, y as u32).channels_mut();
+            for (a, c) in acc.iter_mut().zip(out_channels.iter_mut()) {
+                *c = <P as Pixel>::Subpixel::clamp(*a);
+                *a = zero;
             }
         }
+    }
+
+    out
+}
 
+/// Applies a median filter of width (2 * `x_radius` + 1) and height
+/// (2 * `y_radius` + 1) to an image. Each channel is filtered independently,
+/// so for multi-channel images the output pixels need not occur in the input.
+/// Locations where the window extends past the image's boundary are handled
+/// as specified by `border`.
+///
+/// Uses a sliding histogram (Huang's algorithm), so the cost per pixel grows
+/// linearly with `y_radius` and is independent of `x_radius`.
+pub fn median_filter<P>(image: &Image<P>, x_radius: u32, y_radius: u32, border: BorderMode<P>)
+        -> Image<P>
+    where P: Pixel<Subpixel = u8> + 'static
+{
+    let (width, height) = image.dimensions();
+    let mut out = Image::<P>::new(width, height);
+    if width == 0 || height == 0 {
         return out;
     }
 
-    let half_k = k_height / 2;
+    let num_channels = P::channel_count() as usize;
+    let kernel_size = (2 * x_radius as u64 + 1) * (2 * y_radius as u64 + 1);
+    let rank = (kernel_size / 2) as u32;
+    let x_radius = x_radius as i64;
 
-    // Top margin - need to check lower bound only
-    for y in 0..half_k {
-        for x in 0..width {
-            for (i, k) in kernel.iter().enumerate() {
-                let y_unchecked = (y as i32) + i as i32 - k_height / 2;
-                let y_p = cmp::max(0, y_unchecked) as u32;
-                let p = unsafe { image.unsafe_get_pixel(x, y_p) };
-                accumulate(&mut acc, &p, *k);
-            }
+    // One histogram of 256 bins per channel, stored contiguously.
+    let mut hist = vec![0u32; 256 * num_channels];
 
-            let out_channels = out.get_pixel_mut(x, y as u32).channels_mut();
-            for (a, c) in acc.iter_mut().zip(out_channels.iter_mut()) {
-                *c = <P as Pixel>::Subpixel::clamp(*a);
-                *a = zero;
-            }
+    for y in 0..height {
+        for bin in hist.iter_mut() {
+            *bin = 0;
+        }
+        for x in -x_radius..x_radius + 1 {
+            update_column_histogram(&mut hist, image, x, y, y_radius, border, true);
+        }
+        write_histogram_median(&hist, rank, out.get_pixel_mut(0, y));
+
+        for x in 1..width as i64 {
+            update_column_histogram(&mut hist, image, x - x_radius - 1, y, y_radius, border, false);
+            update_column_histogram(&mut hist, image, x + x_radius, y, y_radius, border, true);
+            write_histogram_median(&hist, rank, out.get_pixel_mut(x as u32, y));
         }
     }
 
-    // Neither margin - don't need bounds check on either side
-    for y in half_k..(height as i32 - half_k) {
-        for x in 0..width {
-            for (i, k) in kernel.iter().enumerate() {
-                let y_unchecked = (y as i32) + i as i32 - k_height / 2;
-                let y_p = y_unchecked as u32;
-                let p = unsafe { image.unsafe_get_pixel(x, y_p) };
-                accumulate(&mut acc, &p, *k);
-            }
+    out
+}
 
-            let out_channels = out.get_pixel_mut(x, y as u32).channels_mut();
-            for (a, c) in acc.iter_mut().zip(out_channels.iter_mut()) {
-                *c = <P as Pixel>::Subpixel::clamp(*a);
-                *a = zero;
+/// Adds or removes the pixels in column x between rows y - `y_radius`
+/// and y + `y_radius` to or from the per-channel histograms. Out of bounds
+/// coordinates are handled as specified by `border`.
+fn update_column_histogram<P>(hist: &mut [u32], image: &Image<P>,
+                              x: i64, y: u32, y_radius: u32, border: BorderMode<P>, add: bool)
+    where P: Pixel<Subpixel = u8> + 'static
+{
+    let (y, y_radius) = (y as i64, y_radius as i64);
+
+    for y_unchecked in y - y_radius..y + y_radius + 1 {
+        let p = pixel_with_border(image, x, y_unchecked, border);
+        for (c, &v) in p.channels().iter().enumerate() {
+            let bin = &mut hist[256 * c + v as usize];
+            if add {
+                *bin += 1;
+            } else {
+                *bin -= 1;
             }
         }
     }
+}
 
-    // Right margin - need to check upper bound only
-    for y in (height as i32 - half_k)..(height as i32) {
-        for x in 0..width {
-            for (i, k) in kernel.iter().enumerate() {
-                let y_unchecked = (y as i32) + i as i32 - k_height / 2;
-                let y_p = cmp::min(y_unchecked, height as i32 - 1) as u32;
-                let p = unsafe { image.unsafe_get_pixel(x, y_p) };
-                accumulate(&mut acc, &p, *k);
-            }
-
-            let out_channels = out.get_pixel_mut(x, y as u32).channels_mut();
-            for (a, c) in acc.iter_mut().zip(out_channels.iter_mut()) {
-                *c = <P as Pixel>::Subpixel::clamp(*a);
-                *a = zero;
+/// Sets each channel of pixel to the value of rank `rank` in the corresponding histogram.
+fn write_histogram_median<P>(hist: &[u32], rank: u32, pixel: &mut P)
+    where P: Pixel<Subpixel = u8>
+{
+    for (c, channel) in pixel.channels_mut().iter_mut().enumerate() {
+        let mut count = 0;
+        for (value, &bin) in hist[256 * c..256 * (c + 1)].iter().enumerate() {
+            count += bin;
+            if count > rank {
+                *channel = value as u8;
+                break;
             }
         }
     }
-
-    out
 }
 
 fn accumulate<P, K>(acc: &mut [K], pixel: &P, weight: K)
@@ -411,10 +613,15 @@ fn accumulate<P, K>(acc: &mut [K], pixel: &P, weight: K)
 #[cfg(test)]
 mod test {
     use super::{
+        bilateral_filter,
         box_filter,
+        BorderMode,
         filter3x3,
         gaussian_blur_f32,
         horizontal_filter,
+        Kernel,
+        median_filter,
+        pixel_with_border,
         separable_filter,
         separable_filter_equal,
         vertical_filter
@@ -428,7 +635,9 @@ mod test {
         GrayImage,
         ImageBuffer,
         Luma,
-        Rgb
+        Rgb,
+        Rgba,
+        RgbImage
     };
     use definitions::{
         Clamp,
@@ -441,6 +650,86 @@ mod test {
     };
     use std::cmp;
 
+    #[test]
+    fn test_border_mode_map_coordinate() {
+        // Maps the coordinates -3..7 for an axis of length 4
+        let map = |border: BorderMode<Luma<u8>>| -> Vec<Option<u32>> {
+            (-3..7).map(|i| border.map_coordinate(i, 4)).collect()
+        };
+        let all = |v: Vec<u32>| -> Vec<Option<u32>> { v.into_iter().map(Some).collect() };
+
+        assert_eq!(map(BorderMode::Constant(Luma([0]))),
+            vec![None, None, None, Some(0), Some(1), Some(2), Some(3), None, None, None]);
+        assert_eq!(map(BorderMode::Replicate), all(vec![0, 0, 0, 0, 1, 2, 3, 3, 3, 3]));
+        assert_eq!(map(BorderMode::Reflect), all(vec![2, 1, 0, 0, 1, 2, 3, 3, 2, 1]));
+        assert_eq!(map(BorderMode::Reflect101), all(vec![3, 2, 1, 0, 1, 2, 3, 2, 1, 0]));
+        assert_eq!(map(BorderMode::Wrap), all(vec![1, 2, 3, 0, 1, 2, 3, 0, 1, 2]));
+    }
+
+    #[test]
+    fn test_border_mode_map_coordinate_far_outside_image() {
+        assert_eq!(BorderMode::Reflect::<Luma<u8>>.map_coordinate(-9, 3), Some(2));
+        assert_eq!(BorderMode::Reflect101::<Luma<u8>>.map_coordinate(9, 3), Some(1));
+        assert_eq!(BorderMode::Reflect101::<Luma<u8>>.map_coordinate(-5, 1), Some(0));
+        assert_eq!(BorderMode::Wrap::<Luma<u8>>.map_coordinate(-7, 3), Some(2));
+    }
+
+    #[test]
+    fn test_box_filter_with_border_modes() {
+        let image: GrayImage = ImageBuffer::from_raw(3, 1, vec![
+            3, 6, 9]).unwrap();
+
+        let filter = |border| box_filter(&image, 1, 0, border);
+        let expected = |v: Vec<u8>| -> GrayImage { ImageBuffer::from_raw(3, 1, v).unwrap() };
+
+        assert_pixels_eq!(filter(BorderMode::Constant(Luma([0]))), expected(vec![3, 6, 5]));
+        assert_pixels_eq!(filter(BorderMode::Constant(Luma([30]))), expected(vec![13, 6, 15]));
+        assert_pixels_eq!(filter(BorderMode::Replicate), expected(vec![4, 6, 8]));
+        assert_pixels_eq!(filter(BorderMode::Reflect), expected(vec![4, 6, 8]));
+        assert_pixels_eq!(filter(BorderMode::Reflect101), expected(vec![5, 6, 7]));
+        assert_pixels_eq!(filter(BorderMode::Wrap), expected(vec![6, 6, 6]));
+    }
+
+    #[test]
+    fn test_box_filter_with_constant_border_pads_both_axes() {
+        let image = GrayImage::from_pixel(3, 3, Luma([90u8]));
+        let expected: GrayImage = ImageBuffer::from_raw(3, 3, vec![
+            40, 60, 40,
+            60, 90, 60,
+            40, 60, 40]).unwrap();
+
+        assert_pixels_eq!(box_filter(&image, 1, 1, BorderMode::Constant(Luma([0]))), expected);
+    }
+
+    #[test]
+    fn test_separable_filter_matches_kernel_filter_for_all_border_modes() {
+        // Keep values small so that no results are clamped
+        let image = GrayImage::from_fn(5, 4, |x, y| Luma([((x + 2 * y) % 5) as u8]));
+        let h_kernel = vec![1i32, 2, 3];
+        let v_kernel = vec![2i32, 0, 1, 1, 1];
+        let mut kernel_2d = vec![];
+        for v in &v_kernel {
+            for h in &h_kernel {
+                kernel_2d.push(v * h);
+            }
+        }
+        let kernel = Kernel::new(&kernel_2d, 3, 5);
+
+        let borders = vec![
+            BorderMode::Constant(Luma([7])),
+            BorderMode::Replicate,
+            BorderMode::Reflect,
+            BorderMode::Reflect101,
+            BorderMode::Wrap
+        ];
+
+        for border in borders {
+            let separable = separable_filter(&image, &h_kernel, &v_kernel, border);
+            let direct: GrayImage = kernel.filter(&image, border, |c, a| *c = a as u8);
+            assert_pixels_eq!(separable, direct);
+        }
+    }
+
     #[test]
     fn test_box_filter() {
         let image: GrayImage = ImageBuffer::from_raw(3, 3, vec![
@@ -457,14 +746,264 @@ mod test {
             4, 5, 5,
             6, 7, 7]).unwrap();
 
-        assert_pixels_eq!(box_filter(&image, 1, 1), expected);
+        assert_pixels_eq!(box_filter(&image, 1, 1, BorderMode::Replicate), expected);
     }
 
     #[bench]
     fn bench_box_filter(b: &mut Bencher) {
         let image = gray_bench_image(500, 500);
         b.iter(|| {
-            let filtered = box_filter(&image, 7, 7);
+            let filtered = box_filter(&image, 7, 7, BorderMode::Replicate);
+            black_box(filtered);
+            });
+    }
+
+    #[test]
+    fn test_median_filter() {
+        let image: GrayImage = ImageBuffer::from_raw(3, 3, vec![
+            1, 2, 3,
+            4, 5, 6,
+            7, 8, 9]).unwrap();
+
+        let expected: GrayImage = ImageBuffer::from_raw(3, 3, vec![
+            2, 3, 3,
+            4, 5, 6,
+            7, 7, 8]).unwrap();
+
+        assert_pixels_eq!(median_filter(&image, 1, 1, BorderMode::Replicate), expected);
+    }
+
+    #[test]
+    fn test_median_filter_constant_border() {
+        let image = GrayImage::from_pixel(3, 3, Luma([10u8]));
+
+        // Windows centred on the corners contain five border pixels.
+        let expected: GrayImage = ImageBuffer::from_raw(3, 3, vec![
+            200, 10, 200,
+             10, 10,  10,
+            200, 10, 200]).unwrap();
+
+        assert_pixels_eq!(median_filter(&image, 1, 1, BorderMode::Constant(Luma([200]))), expected);
+    }
+
+    #[test]
+    fn test_median_filter_removes_salt_and_pepper_noise() {
+        let mut image = GrayImage::from_pixel(6, 5, Luma([100u8]));
+        image.put_pixel(1, 1, Luma([255]));
+        image.put_pixel(4, 2, Luma([0]));
+        image.put_pixel(2, 4, Luma([255]));
+
+        let expected = GrayImage::from_pixel(6, 5, Luma([100u8]));
+        assert_pixels_eq!(median_filter(&image, 1, 1, BorderMode::Replicate), expected);
+    }
+
+    #[test]
+    fn test_median_filter_filters_channels_independently() {
+        let image: RgbImage = ImageBuffer::from_raw(3, 1, vec![
+            10, 200, 7,
+            20, 100, 9,
+            30, 150, 8]).unwrap();
+
+        let expected: RgbImage = ImageBuffer::from_raw(3, 1, vec![
+            10, 200, 7,
+            20, 150, 8,
+            30, 150, 8]).unwrap();
+
+        assert_pixels_eq!(median_filter(&image, 1, 0, BorderMode::Replicate), expected);
+    }
+
+    /// Reference implementation of median_filter for grayscale images.
+    fn median_filter_reference(image: &GrayImage, x_radius: u32, y_radius: u32,
+                               border: BorderMode<Luma<u8>>) -> GrayImage {
+        let (width, height) = image.dimensions();
+        let mut out = GrayImage::new(width, height);
+        let (rx, ry) = (x_radius as i32, y_radius as i32);
+
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                let mut values = vec![];
+                for dy in -ry..ry + 1 {
+                    for dx in -rx..rx + 1 {
+                        let p = pixel_with_border(image, (x + dx) as i64, (y + dy) as i64, border);
+                        values.push(p[0]);
+                    }
+                }
+                values.sort();
+                out.put_pixel(x as u32, y as u32, Luma([values[values.len() / 2]]));
+            }
+        }
+
+        out
+    }
+
+    #[test]
+    fn test_median_filter_matches_reference_implementation() {
+        for height in 0..5 {
+            for width in 0..5 {
+                for x_radius in 0..4 {
+                    for y_radius in 0..4 {
+                        let image = gray_bench_image(width, height);
+                        for &border in &[BorderMode::Constant(Luma([7])), BorderMode::Replicate,
+                                         BorderMode::Reflect, BorderMode::Reflect101, BorderMode::Wrap] {
+                            let expected = median_filter_reference(&image, x_radius, y_radius, border);
+                            let actual = median_filter(&image, x_radius, y_radius, border);
+                            assert_pixels_eq!(actual, expected);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[bench]
+    fn bench_median_filter(b: &mut Bencher) {
+        let image = gray_bench_image(500, 500);
+        b.iter(|| {
+            let filtered = median_filter(&image, 7, 7, BorderMode::Replicate);
+            black_box(filtered);
+            });
+    }
+
+    #[bench]
+    fn bench_median_filter_rgb(b: &mut Bencher) {
+        let image = rgb_bench_image(200, 200);
+        b.iter(|| {
+            let filtered = median_filter(&image, 4, 4, BorderMode::Replicate);
+            black_box(filtered);
+            });
+    }
+
+    #[test]
+    fn test_bilateral_filter_preserves_constant_image() {
+        let image = GrayImage::from_pixel(5, 4, Luma([37u8]));
+        assert_pixels_eq!(bilateral_filter(&image, 1.5, 20.0, BorderMode::Replicate), image);
+    }
+
+    #[test]
+    fn test_bilateral_filter_constant_border() {
+        let image = GrayImage::from_pixel(5, 5, Luma([100u8]));
+        let filtered = bilateral_filter(&image, 1.0, 1000.0, BorderMode::Constant(Luma([200])));
+        assert!(filtered.get_pixel(0, 0)[0] > 100);
+        assert_eq!(filtered.get_pixel(2, 2)[0], 100);
+        assert_pixels_eq!(bilateral_filter(&image, 1.0, 1000.0, BorderMode::Reflect), image);
+    }
+
+    #[test]
+    fn test_bilateral_filter_preserves_edges() {
+        let image = GrayImage::from_fn(8, 8, |x, _| if x < 4 { Luma([20u8]) } else { Luma([220u8]) });
+
+        // A small range sigma gives negligible weight to pixels on the other side of the edge
+        assert_pixels_eq!(bilateral_filter(&image, 2.0, 10.0, BorderMode::Replicate), image);
+
+        // A very large range sigma gives a normal Gaussian blur, which blurs the edge
+        let blurred = bilateral_filter(&image, 2.0, 10000.0, BorderMode::Replicate);
+        assert!(blurred.get_pixel(3, 4)[0] > 40);
+        assert!(blurred.get_pixel(4, 4)[0] < 200);
+    }
+
+    #[test]
+    fn test_bilateral_filter_smooths_within_regions() {
+        let image: GrayImage = ImageBuffer::from_raw(5, 1, vec![
+            100, 104, 96, 102, 98]).unwrap();
+
+        let filtered = bilateral_filter(&image, 1.0, 30.0, BorderMode::Replicate);
+        for x in 1..4 {
+            let p = filtered.get_pixel(x, 0)[0] as i32;
+            let q = image.get_pixel(x, 0)[0] as i32;
+            assert!((p - 100).abs() < (q - 100).abs());
+        }
+    }
+
+    #[test]
+    fn test_bilateral_filter_rgb_uses_distance_between_colors() {
+        // Red and green pixels have equal luminance but should not be mixed
+        let image = RgbImage::from_fn(6, 6, |x, _| {
+            if x < 3 { Rgb([200u8, 0, 0]) } else { Rgb([0u8, 200, 0]) }
+        });
+        assert_pixels_eq!(bilateral_filter(&image, 1.5, 20.0, BorderMode::Replicate), image);
+    }
+
+    #[bench]
+    fn bench_bilateral_filter(b: &mut Bencher) {
+        let image = gray_bench_image(100, 100);
+        b.iter(|| {
+            let filtered = bilateral_filter(&image, 2.0, 20.0, BorderMode::Replicate);
+            black_box(filtered);
+            });
+    }
+
+    #[bench]
+    fn bench_bilateral_filter_rgb(b: &mut Bencher) {
+        let image = rgb_bench_image(100, 100);
+        b.iter(|| {
+            let filtered = bilateral_filter(&image, 2.0, 20.0, BorderMode::Replicate);
+            black_box(filtered);
+            });
+    }
+
+    #[test]
+    fn test_box_filter_luma_u16() {
+        let image: Image<Luma<u16>> = ImageBuffer::from_raw(3, 1, vec![
+            1000, 2000, 60000]).unwrap();
+
+        let expected: Image<Luma<u16>> = ImageBuffer::from_raw(3, 1, vec![
+            1333, 21000, 40666]).unwrap();
+
+        assert_pixels_eq!(box_filter(&image, 1, 0, BorderMode::Replicate), expected);
+    }
+
+    #[test]
+    fn test_box_filter_rgb() {
+        let image: RgbImage = ImageBuffer::from_raw(3, 1, vec![
+            3, 30, 0,
+            6, 60, 9,
+            9, 90, 0]).unwrap();
+
+        let expected: RgbImage = ImageBuffer::from_raw(3, 1, vec![
+            4, 40, 3,
+            6, 60, 3,
+            8, 80, 3]).unwrap();
+
+        assert_pixels_eq!(box_filter(&image, 1, 0, BorderMode::Replicate), expected);
+    }
+
+    #[test]
+    fn test_box_filter_rgba_matches_per_channel_gray() {
+        let image = rgb_bench_image(7, 6);
+        let rgba = ImageBuffer::from_fn(7, 6, |x, y| {
+            let p = image.get_pixel(x, y);
+            Rgba([p[0], p[1], p[2], 255 - p[0]])
+        });
+        let filtered = box_filter(&rgba, 2, 1, BorderMode::Reflect101);
+
+        for c in 0..4 {
+            let channel = GrayImage::from_fn(7, 6, |x, y| Luma([rgba.get_pixel(x, y)[c]]));
+            let expected = box_filter(&channel, 2, 1, BorderMode::Reflect101);
+            for (x, y, p) in expected.enumerate_pixels() {
+                assert_eq!(filtered.get_pixel(x, y)[c], p[0]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_box_filter_luma_f32() {
+        let image: Image<Luma<f32>> = ImageBuffer::from_raw(3, 3, vec![
+            1.0, 2.0, 3.0,
+            4.0, 5.0, 6.0,
+            7.0, 8.0, 9.5]).unwrap();
+
+        let filtered = box_filter(&image, 1, 1, BorderMode::Constant(Luma([0.0])));
+
+        assert!((filtered.get_pixel(1, 1)[0] - 45.5 / 9.0).abs() < 1e-5);
+        assert!((filtered.get_pixel(0, 0)[0] - 12.0 / 9.0).abs() < 1e-5);
+        assert!((filtered.get_pixel(2, 2)[0] - 28.5 / 9.0).abs() < 1e-5);
+    }
+
+    #[bench]
+    fn bench_box_filter_rgb(b: &mut Bencher) {
+        let image = rgb_bench_image(500, 500);
+        b.iter(|| {
+            let filtered = box_filter(&image, 7, 7, BorderMode::Replicate);
             black_box(filtered);
             });
     }
@@ -483,7 +1022,7 @@ mod test {
             6, 7, 7]).unwrap();
 
         let kernel = vec![1f32/3f32; 3];
-        let filtered = separable_filter_equal(&image, &kernel);
+        let filtered = separable_filter_equal(&image, &kernel, BorderMode::Replicate);
 
         assert_pixels_eq!(filtered, expected);
     }
@@ -501,7 +1040,7 @@ mod test {
             57, 63, 69]).unwrap();
 
         let kernel = vec![1i32; 3];
-        let filtered = separable_filter_equal(&image, &kernel);
+        let filtered = separable_filter_equal(&image, &kernel, BorderMode::Replicate);
 
         assert_pixels_eq!(filtered, expected);
     }
@@ -512,7 +1051,7 @@ mod test {
         let h_kernel = vec![1f32/5f32; 5];
         let v_kernel = vec![0.1f32, 0.4f32, 0.3f32, 0.1f32, 0.1f32];
         b.iter(|| {
-            let filtered = separable_filter(&image, &h_kernel, &v_kernel);
+            let filtered = separable_filter(&image, &h_kernel, &v_kernel, BorderMode::Replicate);
             black_box(filtered);
             });
     }
@@ -597,7 +1136,7 @@ mod test {
                                 (0..kernel_length).map(|i| i as f32 % 1.35).collect();
 
                             let expected = $reference_impl(&image, &kernel);
-                            let actual = $under_test(&image, &kernel);
+                            let actual = $under_test(&image, &kernel, BorderMode::Replicate);
 
                             assert_pixels_eq!(actual, expected);
                         }
@@ -632,7 +1171,7 @@ mod test {
             2, 2, 2]).unwrap();
 
         let kernel = vec![1f32/3f32; 3];
-        let filtered = horizontal_filter(&image, &kernel);
+        let filtered = horizontal_filter(&image, &kernel, BorderMode::Replicate);
 
         assert_pixels_eq!(filtered, expected);
     }
@@ -645,7 +1184,7 @@ mod test {
             1, 4, 1]).unwrap();
 
         let kernel = vec![1f32/10f32; 10];
-        black_box(horizontal_filter(&image, &kernel));
+        black_box(horizontal_filter(&image, &kernel, BorderMode::Replicate));
     }
 
     #[bench]
@@ -653,7 +1192,7 @@ mod test {
         let image = gray_bench_image(500, 500);
         let kernel = vec![1f32/5f32; 5];
         b.iter(|| {
-            let filtered = horizontal_filter(&image, &kernel);
+            let filtered = horizontal_filter(&image, &kernel, BorderMode::Replicate);
             black_box(filtered);
             });
     }
@@ -671,7 +1210,7 @@ mod test {
             2, 5, 2]).unwrap();
 
         let kernel = vec![1f32/3f32; 3];
-        let filtered = vertical_filter(&image, &kernel);
+        let filtered = vertical_filter(&image, &kernel, BorderMode::Replicate);
 
         assert_pixels_eq!(filtered, expected);
     }
@@ -684,7 +1223,7 @@ mod test {
             1, 4, 1]).unwrap();
 
         let kernel = vec![1f32/10f32; 10];
-        black_box(vertical_filter(&image, &kernel));
+        black_box(vertical_filter(&image, &kernel, BorderMode::Replicate));
     }
 
     #[bench]
@@ -692,7 +1231,7 @@ mod test {
         let image = gray_bench_image(500, 500);
         let kernel = vec![1f32/5f32; 5];
         b.iter(|| {
-            let filtered = vertical_filter(&image, &kernel);
+            let filtered = vertical_filter(&image, &kernel, BorderMode::Replicate);
             black_box(filtered);
             });
     }
@@ -0,0 +1,622 @@
+//! Computational geometry on sets of points, such as the points of a
+//! contour or the locations of detected corners.
+//!
+//! Functions taking a set of points accept any type convertible to a `Point<i32>`,
+//! including `corners::Corner` and `corners::SubPixelCorner`.
+
+use drawing::Point;
+use rect::{Rect, Region};
+use std::cmp::Ordering;
+use std::{f32, f64};
+
+/// A rectangle that need not be aligned with the image axes.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RotatedRect {
+    /// Centre of the rectangle.
+    pub center: (f32, f32),
+    /// Length of the side whose direction is given by `angle`.
+    pub width: f32,
+    /// Length of the side perpendicular to the width.
+    pub height: f32,
+    /// Angle in radians from the x-axis to the width side, in the range
+    /// (-π/2, π/2]. As y points down the image, positive angles are clockwise.
+    pub angle: f32,
+}
+
+impl RotatedRect {
+    /// The vertices of the rectangle, in the order given by walking along
+    /// the width side from the first vertex and continuing around the rectangle.
+    pub fn corners(&self) -> [(f32, f32); 4] {
+        let (cos, sin) = (self.angle.cos(), self.angle.sin());
+        let (w, h) = (self.width / 2.0, self.height / 2.0);
+        let corner = |u: f32, v: f32| {
+            (self.center.0 + u * cos - v * sin, self.center.1 + u * sin + v * cos)
+        };
+        [corner(-w, -h), corner(w, -h), corner(w, h), corner(-w, h)]
+    }
+
+    /// The smallest axis-aligned rectangle containing every pixel that
+    /// intersects this rectangle's vertices.
+    pub fn bounding_rect(&self) -> Rect {
+        let corners = self.corners();
+        let xs = corners.iter().map(|c| c.0);
+        let ys = corners.iter().map(|c| c.1);
+        rect_containing(min_max(xs), min_max(ys))
+    }
+
+    /// The vertices of the rectangle rounded to the nearest pixel, in the form
+    /// expected by `drawing::draw_convex_polygon` and `drawing::draw_hollow_polygon`.
+    pub fn to_polygon(&self) -> Vec<Point<i32>> {
+        rounded_polygon(self.corners().iter().cloned())
+    }
+}
+
+impl Region<f32> for RotatedRect {
+    fn contains(&self, x: f32, y: f32) -> bool {
+        let (cos, sin) = (self.angle.cos(), self.angle.sin());
+        let (dx, dy) = (x - self.center.0, y - self.center.1);
+        let (u, v) = (dx * cos + dy * sin, -dx * sin + dy * cos);
+        u.abs() <= self.width / 2.0 && v.abs() <= self.height / 2.0
+    }
+}
+
+/// A circle.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Circle {
+    /// Centre of the circle.
+    pub center: (f32, f32),
+    /// Radius of the circle.
+    pub radius: f32,
+}
+
+impl Circle {
+    /// The smallest axis-aligned rectangle containing every pixel that
+    /// intersects this circle's bounding square.
+    pub fn bounding_rect(&self) -> Rect {
+        let (x, y, r) = (self.center.0, self.center.1, self.radius);
+        rect_containing((x - r, x + r), (y - r, y + r))
+    }
+}
+
+impl Region<f32> for Circle {
+    fn contains(&self, x: f32, y: f32) -> bool {
+        let (dx, dy) = (x - self.center.0, y - self.center.1);
+        dx * dx + dy * dy <= self.radius * self.radius
+    }
+}
+
+/// An ellipse.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ellipse {
+    /// Centre of the ellipse.
+    pub center: (f32, f32),
+    /// Half the length of the major axis.
+    pub semi_major: f32,
+    /// Half the length of the minor axis.
+    pub semi_minor: f32,
+    /// Angle in radians from the x-axis to the major axis, in the range
+    /// (-π/2, π/2]. As y points down the image, positive angles are clockwise.
+    pub angle: f32,
+}
+
+impl Ellipse {
+    /// The point on the ellipse at parameter `t`, where `t` = 0 is an end of
+    /// the major axis and `t` increases clockwise around the ellipse.
+    pub fn point_at(&self, t: f32) -> (f32, f32) {
+        let (cos, sin) = (self.angle.cos(), self.angle.sin());
+        let (u, v) = (self.semi_major * t.cos(), self.semi_minor * t.sin());
+        (self.center.0 + u * cos - v * sin, self.center.1 + u * sin + v * cos)
+    }
+
+    /// The smallest axis-aligned rectangle containing every pixel that
+    /// intersects the ellipse's axis-aligned bounding box.
+    pub fn bounding_rect(&self) -> Rect {
+        let (cos, sin) = (self.angle.cos(), self.angle.sin());
+        let (a, b) = (self.semi_major, self.semi_minor);
+        let half_width = (a * a * cos * cos + b * b * sin * sin).sqrt();
+        let half_height = (a * a * sin * sin + b * b * cos * cos).sqrt();
+        let (x, y) = self.center;
+        rect_containing((x - half_width, x + half_width), (y - half_height, y + half_height))
+    }
+
+    /// Approximates the ellipse by a polygon with at most `num_vertices` vertices,
+    /// rounded to the nearest pixel, in the form expected by `drawing::draw_convex_polygon`
+    /// and `drawing::draw_hollow_polygon`.
+    pub fn to_polygon(&self, num_vertices: usize) -> Vec<Point<i32>> {
+        let step = 2.0 * f32::consts::PI / num_vertices as f32;
+        rounded_polygon((0..num_vertices).map(|i| self.point_at(i as f32 * step)))
+    }
+}
+
+impl Region<f32> for Ellipse {
+    fn contains(&self, x: f32, y: f32) -> bool {
+        let (cos, sin) = (self.angle.cos(), self.angle.sin());
+        let (dx, dy) = (x - self.center.0, y - self.center.1);
+        let (u, v) = (dx * cos + dy * sin, -dx * sin + dy * cos);
+        let (a, b) = (self.semi_major, self.semi_minor);
+        u * u * b * b + v * v * a * a <= a * a * b * b
+    }
+}
+
+/// Returns the vertices of the convex hull of a set of points, computed using a
+/// Graham scan. The vertices start from the topmost point (the leftmost of these
+/// if there are several) and are given in clockwise order as displayed in an image,
+/// i.e. with y increasing downwards. Points lying in the interior of hull edges are
+/// not included.
+///
+/// The output is suitable for passing to `drawing::draw_convex_polygon`.
+pub fn convex_hull<P>(points: &[P]) -> Vec<Point<i32>>
+    where P: Copy + Into<Point<i32>>
+{
+    let mut points = to_points(points);
+    points.sort_by_key(|p| (p.y, p.x));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    // As the pivot has the smallest y-coordinate, every other point lies at an
+    // angle in [0, π) from it, so ordering by cross product is a total order.
+    let pivot = points[0];
+    points[1..].sort_by(|&p, &q| {
+        match cross(pivot, p, q).cmp(&0) {
+            Ordering::Greater => Ordering::Less,
+            Ordering::Less => Ordering::Greater,
+            Ordering::Equal => squared_distance(pivot, p).cmp(&squared_distance(pivot, q)),
+        }
+    });
+
+    let mut hull: Vec<Point<i32>> = Vec::new();
+    for p in points {
+        while hull.len() >= 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0 {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    hull
+}
+
+/// Returns the rotated rectangle of minimum area containing a set of points,
+/// or `None` if there are no points.
+///
+/// One side of the minimum-area rectangle is collinear with an edge of the
+/// convex hull of the points, so it is found by trying each hull edge in turn.
+pub fn min_area_rect<P>(points: &[P]) -> Option<RotatedRect>
+    where P: Copy + Into<Point<i32>>
+{
+    let hull = convex_hull(points);
+    match hull.len() {
+        0 => return None,
+        1 => {
+            return Some(RotatedRect {
+                center: (hull[0].x as f32, hull[0].y as f32),
+                width: 0.0,
+                height: 0.0,
+                angle: 0.0,
+            })
+        }
+        _ => {}
+    }
+
+    let mut best: Option<(f64, RotatedRect)> = None;
+    for i in 0..hull.len() {
+        let (p, q) = (hull[i], hull[(i + 1) % hull.len()]);
+        let (dx, dy) = ((q.x - p.x) as f64, (q.y - p.y) as f64);
+        let length = (dx * dx + dy * dy).sqrt();
+        let (ux, uy) = (dx / length, dy / length);
+
+        // Extents of the hull along the edge and its normal.
+        let along = min_max(hull.iter().map(|h| h.x as f64 * ux + h.y as f64 * uy));
+        let across = min_max(hull.iter().map(|h| -(h.x as f64) * uy + h.y as f64 * ux));
+        let area = (along.1 - along.0) * (across.1 - across.0);
+
+        if best.map_or(true, |(a, _)| area < a) {
+            let (u, v) = ((along.0 + along.1) / 2.0, (across.0 + across.1) / 2.0);
+            let rect = RotatedRect {
+                center: ((u * ux - v * uy) as f32, (u * uy + v * ux) as f32),
+                width: (along.1 - along.0) as f32,
+                height: (across.1 - across.0) as f32,
+                angle: normalize_angle(uy.atan2(ux)) as f32,
+            };
+            best = Some((area, rect));
+        }
+    }
+    best.map(|(_, rect)| rect)
+}
+
+/// Adds a multiple of π to an angle to bring it into the range (-π/2, π/2].
+fn normalize_angle(angle: f64) -> f64 {
+    let mut angle = angle;
+    while angle > f64::consts::FRAC_PI_2 {
+        angle -= f64::consts::PI;
+    }
+    while angle <= -f64::consts::FRAC_PI_2 {
+        angle += f64::consts::PI;
+    }
+    angle
+}
+
+/// Returns the circle of minimum radius containing a set of points, or `None`
+/// if there are no points.
+pub fn min_enclosing_circle<P>(points: &[P]) -> Option<Circle>
+    where P: Copy + Into<Point<i32>>
+{
+    // The minimum enclosing circle of a set of points is determined by its
+    // convex hull, which is usually much smaller.
+    let hull: Vec<(f64, f64)> = convex_hull(points)
+        .iter()
+        .map(|p| (p.x as f64, p.y as f64))
+        .collect();
+    if hull.is_empty() {
+        return None;
+    }
+
+    // Welzl's algorithm, in its iterative form.
+    let mut circle = (hull[0], 0.0);
+    for i in 1..hull.len() {
+        if in_circle(circle, hull[i]) {
+            continue;
+        }
+        circle = (hull[i], 0.0);
+        for j in 0..i {
+            if in_circle(circle, hull[j]) {
+                continue;
+            }
+            circle = circle_from_diameter(hull[i], hull[j]);
+            for k in 0..j {
+                if !in_circle(circle, hull[k]) {
+                    circle = circumcircle(hull[i], hull[j], hull[k]);
+                }
+            }
+        }
+    }
+
+    let ((x, y), radius) = circle;
+    Some(Circle {
+        center: (x as f32, y as f32),
+        radius: radius as f32,
+    })
+}
+
+fn in_circle(circle: ((f64, f64), f64), p: (f64, f64)) -> bool {
+    let ((cx, cy), r) = circle;
+    let (dx, dy) = (p.0 - cx, p.1 - cy);
+    (dx * dx + dy * dy).sqrt() <= r * (1.0 + 1e-9) + 1e-9
+}
+
+fn circle_from_diameter(p: (f64, f64), q: (f64, f64)) -> ((f64, f64), f64) {
+    let center = ((p.0 + q.0) / 2.0, (p.1 + q.1) / 2.0);
+    let (dx, dy) = (p.0 - q.0, p.1 - q.1);
+    (center, (dx * dx + dy * dy).sqrt() / 2.0)
+}
+
+/// The circle through three points, or the smallest circle containing
+/// them if they are collinear.
+fn circumcircle(p: (f64, f64), q: (f64, f64), r: (f64, f64)) -> ((f64, f64), f64) {
+    let (bx, by) = (q.0 - p.0, q.1 - p.1);
+    let (cx, cy) = (r.0 - p.0, r.1 - p.1);
+    let d = 2.0 * (bx * cy - by * cx);
+    if d.abs() < 1e-12 {
+        let candidates = [circle_from_diameter(p, q), circle_from_diameter(p, r),
+                          circle_from_diameter(q, r)];
+        return *candidates.iter()
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .unwrap();
+    }
+    let (b2, c2) = (bx * bx + by * by, cx * cx + cy * cy);
+    let (ux, uy) = ((cy * b2 - by * c2) / d, (bx * c2 - cx * b2) / d);
+    ((p.0 + ux, p.1 + uy), (ux * ux + uy * uy).sqrt())
+}
+
+/// Fits an ellipse to a set of points by least-squares fitting of a conic
+/// section, or returns `None` if there are fewer than five points or the best
+/// fitting conic is not an ellipse.
+pub fn fit_ellipse<P>(points: &[P]) -> Option<Ellipse>
+    where P: Copy + Into<Point<i32>>
+{
+    let points = to_points(points);
+    if points.len() < 5 {
+        return None;
+    }
+
+    // Centre and scale the points to improve the conditioning of the fit,
+    // and so that the conic does not pass through the origin.
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.x as f64).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.y as f64).sum::<f64>() / n;
+    let scale = points.iter()
+        .map(|p| ((p.x as f64 - mean_x).powi(2) + (p.y as f64 - mean_y).powi(2)).sqrt())
+        .sum::<f64>() / n;
+    if scale == 0.0 {
+        return None;
+    }
+
+    // Solve the normal equations for the conic a x^2 + b xy + c y^2 + d x + e y = 1.
+    let mut lhs = [[0f64; 5]; 5];
+    let mut rhs = [0f64; 5];
+    for p in &points {
+        let (x, y) = ((p.x as f64 - mean_x) / scale, (p.y as f64 - mean_y) / scale);
+        let row = [x * x, x * y, y * y, x, y];
+        for i in 0..5 {
+            rhs[i] += row[i];
+            for j in 0..5 {
+                lhs[i][j] += row[i] * row[j];
+            }
+        }
+    }
+    let conic = match solve(lhs, rhs) {
+        Some(conic) => conic,
+        None => return None,
+    };
+    let (a, b, c, d, e) = (conic[0], conic[1], conic[2], conic[3], conic[4]);
+
+    let det = 4.0 * a * c - b * b;
+    if det <= 0.0 {
+        return None;
+    }
+    let x0 = (b * e - 2.0 * c * d) / det;
+    let y0 = (b * d - 2.0 * a * e) / det;
+    // Writing the conic about its centre gives q(x - x0, y - y0) = k,
+    // for the quadratic form q with matrix [[a, b/2], [b/2, c]].
+    let k = 1.0 - (d * x0 + e * y0) / 2.0;
+
+    let mean = (a + c) / 2.0;
+    let radius = (((a - c) / 2.0).powi(2) + (b / 2.0).powi(2)).sqrt();
+    let (small, large) = (mean - radius, mean + radius);
+    if small <= 0.0 || k <= 0.0 {
+        return None;
+    }
+
+    // The major axis is the eigenvector of the smaller eigenvalue, which is
+    // perpendicular to the direction 0.5 * atan2(b, a - c).
+    let angle = 0.5 * b.atan2(a - c) + f64::consts::FRAC_PI_2;
+    Some(Ellipse {
+        center: ((x0 * scale + mean_x) as f32, (y0 * scale + mean_y) as f32),
+        semi_major: ((k / small).sqrt() * scale) as f32,
+        semi_minor: ((k / large).sqrt() * scale) as f32,
+        angle: normalize_angle(angle) as f32,
+    })
+}
+
+/// Solves a 5x5 linear system by Gaussian elimination with partial pivoting.
+fn solve(mut lhs: [[f64; 5]; 5], mut rhs: [f64; 5]) -> Option<[f64; 5]> {
+    for col in 0..5 {
+        let pivot = (col..5)
+            .max_by(|&i, &j| lhs[i][col].abs().partial_cmp(&lhs[j][col].abs()).unwrap())
+            .unwrap();
+        if lhs[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        lhs.swap(col, pivot);
+        rhs.swap(col, pivot);
+        for row in col + 1..5 {
+            let factor = lhs[row][col] / lhs[col][col];
+            for k in col..5 {
+                lhs[row][k] -= factor * lhs[col][k];
+            }
+            rhs[row] -= factor * rhs[col];
+        }
+    }
+    let mut solution = [0f64; 5];
+    for row in (0..5).rev() {
+        let sum: f64 = (row + 1..5).map(|k| lhs[row][k] * solution[k]).sum();
+        solution[row] = (rhs[row] - sum) / lhs[row][row];
+    }
+    Some(solution)
+}
+
+fn to_points<P: Copy + Into<Point<i32>>>(points: &[P]) -> Vec<Point<i32>> {
+    points.iter().map(|&p| p.into()).collect()
+}
+
+/// Area of a simple polygon, computed using the shoelace formula.
+pub(crate) fn polygon_area(vertices: &[Point<i32>]) -> f64 {
+    let n = vertices.len();
+    let twice_area: i64 = (0..n)
+        .map(|i| {
+            let (p, q) = (vertices[i], vertices[(i + 1) % n]);
+            p.x as i64 * q.y as i64 - q.x as i64 * p.y as i64
+        })
+        .sum();
+    twice_area.abs() as f64 / 2.0
+}
+
+/// Cross product of the vectors from `o` to `a` and from `o` to `b`. This is positive
+/// if `o`, `a`, `b` turn clockwise as displayed with y increasing downwards.
+fn cross(o: Point<i32>, a: Point<i32>, b: Point<i32>) -> i64 {
+    (a.x - o.x) as i64 * (b.y - o.y) as i64 - (a.y - o.y) as i64 * (b.x - o.x) as i64
+}
+
+fn squared_distance(p: Point<i32>, q: Point<i32>) -> i64 {
+    let (dx, dy) = ((p.x - q.x) as i64, (p.y - q.y) as i64);
+    dx * dx + dy * dy
+}
+
+fn min_max<T: PartialOrd + Copy, I: Iterator<Item = T>>(mut values: I) -> (T, T) {
+    let first = values.next().unwrap();
+    values.fold((first, first), |(lo, hi), v| {
+        (if v < lo { v } else { lo }, if v > hi { v } else { hi })
+    })
+}
+
+/// The smallest rect containing all pixels whose coordinates lie within
+/// the given ranges, allowing for a small rounding error.
+fn rect_containing(xs: (f32, f32), ys: (f32, f32)) -> Rect {
+    let (left, right) = ((xs.0 + 1e-3).floor() as i32, (xs.1 - 1e-3).ceil() as i32);
+    let (top, bottom) = ((ys.0 + 1e-3).floor() as i32, (ys.1 - 1e-3).ceil() as i32);
+    Rect::at(left, top).of_size((right - left).max(0) as u32 + 1, (bottom - top).max(0) as u32 + 1)
+}
+
+/// Rounds the vertices of a polygon to the nearest pixel, dropping any
+/// that coincide with their predecessor.
+fn rounded_polygon<I: Iterator<Item = (f32, f32)>>(vertices: I) -> Vec<Point<i32>> {
+    let mut polygon: Vec<Point<i32>> = vertices
+        .map(|(x, y)| Point::new(x.round() as i32, y.round() as i32))
+        .collect();
+    polygon.dedup();
+    while polygon.len() > 1 && polygon[0] == polygon[polygon.len() - 1] {
+        polygon.pop();
+    }
+    polygon
+}
+
+#[cfg(test)]
+mod test {
+    use super::{convex_hull, fit_ellipse, min_area_rect, min_enclosing_circle, Circle,
+                Ellipse, RotatedRect};
+    use corners::{Corner, SubPixelCorner};
+    use drawing::Point;
+    use rect::{Rect, Region};
+    use std::f32;
+    use test::{Bencher, black_box};
+
+    fn points(coords: &[(i32, i32)]) -> Vec<Point<i32>> {
+        coords.iter().map(|&(x, y)| Point::new(x, y)).collect()
+    }
+
+    #[test]
+    fn test_convex_hull() {
+        let input = points(&[(1, 1), (0, 0), (4, 0), (2, 0), (2, 2), (4, 4), (0, 4), (3, 1),
+                             (1, 3), (2, 4), (0, 0)]);
+        assert_eq!(convex_hull(&input), points(&[(0, 0), (4, 0), (4, 4), (0, 4)]));
+    }
+
+    #[test]
+    fn test_convex_hull_degenerate() {
+        assert!(convex_hull(&points(&[])).is_empty());
+        assert_eq!(convex_hull(&points(&[(3, 2), (3, 2)])), points(&[(3, 2)]));
+        assert_eq!(convex_hull(&points(&[(2, 2), (0, 0), (1, 1), (3, 3)])),
+                   points(&[(0, 0), (3, 3)]));
+    }
+
+    #[test]
+    fn test_convex_hull_is_clockwise() {
+        let input = points(&[(5, 0), (9, 4), (5, 8), (1, 4), (5, 4), (6, 3)]);
+        assert_eq!(convex_hull(&input), points(&[(5, 0), (9, 4), (5, 8), (1, 4)]));
+    }
+
+    #[test]
+    fn test_geometry_of_corners() {
+        let corners = vec![
+            Corner::new(2, 3, 1.0), Corner::new(8, 3, 0.5), Corner::new(8, 5, 2.0),
+            Corner::new(2, 5, 1.5), Corner::new(4, 4, 1.0)];
+        assert_eq!(convex_hull(&corners), points(&[(2, 3), (8, 3), (8, 5), (2, 5)]));
+        assert_eq!(min_area_rect(&corners).unwrap().bounding_rect(), Rect::at(2, 3).of_size(7, 3));
+
+        let subpixel: Vec<_> = corners.iter().map(|&c| SubPixelCorner::from(c)).collect();
+        assert_eq!(min_enclosing_circle(&subpixel), min_enclosing_circle(&corners));
+        assert_eq!(Point::from(SubPixelCorner::new(1.6, 2.4, 1.0)), Point::new(2, 2));
+    }
+
+    #[test]
+    fn test_min_area_rect_axis_aligned() {
+        let input = points(&[(2, 3), (8, 3), (8, 5), (2, 5), (4, 4)]);
+        let rect = min_area_rect(&input).unwrap();
+        assert_approx_eq(rect.center, (5.0, 4.0));
+        assert_eq!((rect.width * rect.height).round(), 12.0);
+        assert_eq!(rect.bounding_rect(), Rect::at(2, 3).of_size(7, 3));
+    }
+
+    #[test]
+    fn test_min_area_rect_rotated() {
+        // A diamond, whose minimum-area rectangle is rotated by 45 degrees.
+        let input = points(&[(5, 0), (9, 4), (5, 8), (1, 4)]);
+        let rect = min_area_rect(&input).unwrap();
+        assert_approx_eq(rect.center, (5.0, 4.0));
+        assert!((rect.width * rect.height - 32.0).abs() < 1e-3);
+        assert!((rect.angle.abs() - f32::consts::FRAC_PI_4).abs() < 1e-5);
+        assert_eq!(rect.bounding_rect(), Rect::at(1, 0).of_size(9, 9));
+
+        let mut corners = rect.to_polygon();
+        corners.sort_by_key(|p| (p.y, p.x));
+        assert_eq!(corners, points(&[(5, 0), (1, 4), (9, 4), (5, 8)]));
+        assert!(rect.contains(5.0, 4.0));
+        assert!(!rect.contains(1.5, 0.5));
+    }
+
+    #[test]
+    fn test_min_area_rect_degenerate() {
+        assert_eq!(min_area_rect(&points(&[])), None);
+        assert_eq!(min_area_rect(&points(&[(3, 4)])),
+                   Some(RotatedRect { center: (3.0, 4.0), width: 0.0, height: 0.0, angle: 0.0 }));
+        let segment = min_area_rect(&points(&[(0, 0), (0, 6)])).unwrap();
+        assert_approx_eq(segment.center, (0.0, 3.0));
+        assert_eq!(segment.width.max(segment.height), 6.0);
+        assert_eq!(segment.width.min(segment.height), 0.0);
+    }
+
+    #[test]
+    fn test_min_enclosing_circle() {
+        assert_eq!(min_enclosing_circle(&points(&[])), None);
+        assert_eq!(min_enclosing_circle(&points(&[(2, 5)])),
+                   Some(Circle { center: (2.0, 5.0), radius: 0.0 }));
+
+        // Determined by two points.
+        let circle = min_enclosing_circle(&points(&[(0, 0), (10, 0), (5, 2), (4, -3)])).unwrap();
+        assert_approx_eq(circle.center, (5.0, 0.0));
+        assert!((circle.radius - 5.0).abs() < 1e-5);
+
+        // Determined by three points, the vertices of an acute triangle.
+        let circle = min_enclosing_circle(&points(&[(0, 0), (6, 0), (3, 4), (3, 1)])).unwrap();
+        assert_approx_eq(circle.center, (3.0, 7.0 / 8.0));
+        assert!((circle.radius - 25.0 / 8.0).abs() < 1e-5);
+        assert_eq!(circle.bounding_rect(), Rect::at(-1, -3).of_size(9, 8));
+        assert!(circle.contains(3.0, 4.0));
+        assert!(!circle.contains(0.0, 4.0));
+    }
+
+    #[test]
+    fn test_min_enclosing_circle_contains_all_points() {
+        let input: Vec<_> = (0..50)
+            .map(|i| Point::new((i * 37) % 23 - 11, (i * 17) % 29 - 14))
+            .collect();
+        let circle = min_enclosing_circle(&input).unwrap();
+        // Some points lie on the circle, so allow for rounding error
+        let larger = Circle { center: circle.center, radius: circle.radius + 1e-3 };
+        for p in &input {
+            assert!(larger.contains(p.x as f32, p.y as f32), "{:?}", p);
+        }
+        let smaller = Circle { center: circle.center, radius: circle.radius - 1e-3 };
+        assert!(input.iter().any(|p| !smaller.contains(p.x as f32, p.y as f32)));
+    }
+
+    #[test]
+    fn test_fit_ellipse() {
+        let expected = Ellipse {
+            center: (50.0, 40.0),
+            semi_major: 30.0,
+            semi_minor: 12.0,
+            angle: 0.5,
+        };
+        let input = expected.to_polygon(200);
+        let fitted = fit_ellipse(&input).unwrap();
+
+        assert_approx_eq(fitted.center, expected.center);
+        assert!((fitted.semi_major - 30.0).abs() < 0.3, "{:?}", fitted);
+        assert!((fitted.semi_minor - 12.0).abs() < 0.3, "{:?}", fitted);
+        assert!((fitted.angle - 0.5).abs() < 0.01, "{:?}", fitted);
+        assert_eq!(expected.bounding_rect(), Rect::at(23, 22).of_size(55, 37));
+    }
+
+    #[test]
+    fn test_fit_ellipse_degenerate() {
+        assert_eq!(fit_ellipse(&points(&[(0, 0), (1, 0), (0, 1), (1, 1)])), None);
+        assert_eq!(fit_ellipse(&points(&[(0, 0), (1, 1), (2, 2), (3, 3), (4, 4), (5, 5)])), None);
+    }
+
+    fn assert_approx_eq(actual: (f32, f32), expected: (f32, f32)) {
+        assert!((actual.0 - expected.0).abs() < 0.1 && (actual.1 - expected.1).abs() < 0.1,
+                "actual: {:?}, expected: {:?}", actual, expected);
+    }
+
+    #[bench]
+    fn bench_min_area_rect(b: &mut Bencher) {
+        let input: Vec<_> = (0..1000)
+            .map(|i| Point::new((i * 37) % 101, (i * 53) % 89))
+            .collect();
+        b.iter(|| {
+            let rect = min_area_rect(&input);
+            black_box(rect);
+        });
+    }
+}
@@ -18,6 +18,9 @@ pub enum Sign {
 
 /// A Haar filter whose value on an integral image is the weighted sum
 /// of the values of the integral image at the given points.
+///
+/// Tilted filters are rotated by 45 degrees, and must be evaluated on a tilted
+/// integral image, as computed by `integralimage::tilted_integral_image`.
 // TODO: these structs are pretty big. Look into instead just storing
 // TODO: the offsets between sample points. We should only need 10 bytes/filter,
 // TODO: meaning we could fit a typical cascade in L1 cache.
@@ -25,7 +28,8 @@ pub enum Sign {
 pub struct HaarFilter {
     points: [u32; 18],
     weights: [i8; 9],
-    count: usize
+    count: usize,
+    tilted: bool
 }
 
 /// Returns a vector of all valid Haar filters for an image with given width and height.
@@ -129,7 +133,8 @@ fn n_choose_k(n: u32, k: u32) -> u32 {
 }
 
 impl HaarFilter {
-    /// Evaluates the Haar filter on an integral image.
+    /// Evaluates the Haar filter on an integral image. If this filter is tilted then
+    /// `integral` must be a tilted integral image.
     pub fn evaluate<I>(&self, integral: &I ) -> i32
         where I: GenericImage<Pixel=Luma<u32>> {
 
@@ -141,6 +146,11 @@ impl HaarFilter {
         sum
     }
 
+    /// Returns true if this filter is rotated by 45 degrees.
+    pub fn is_tilted(&self) -> bool {
+        self.tilted
+    }
+
     /// Returns the following feature (with signs reversed if Sign == Sign::Negative).
     /// <pre>
     ///     A   B   C
@@ -233,6 +243,80 @@ impl HaarFilter {
             eval_points(top + dy1, left,       dx1, dy2),
             ]) * multiplier(sign)
     }
+
+    /// Returns the tilted equivalent of `two_region_horizontal` (with signs reversed
+    /// if Sign == Sign::Negative). The positive region has top corner pixel (x, top) and
+    /// extends dw1 pixels down and to the right and dh pixels down and to the left. The
+    /// negative region has top corner (x + dw1, top + dw1) and extends dw2 pixels down
+    /// and to the right and dh pixels down and to the left.
+    pub fn two_region_tilted_horizontal(top: u32, x: u32, dw1: u32, dw2: u32, dh: u32, sign: Sign)
+        -> HaarFilter {
+
+        tilted(combine_alternating(&[
+            tilted_eval_points(top,       x,       dw1, dh),
+            tilted_eval_points(top + dw1, x + dw1, dw2, dh)])) * multiplier(sign)
+    }
+
+    /// Returns the tilted equivalent of `two_region_vertical` (with signs reversed
+    /// if Sign == Sign::Negative). The positive region has top corner pixel (x, top) and
+    /// extends dw pixels down and to the right and dh1 pixels down and to the left. The
+    /// negative region has top corner (x - dh1, top + dh1) and extends dw pixels down
+    /// and to the right and dh2 pixels down and to the left.
+    pub fn two_region_tilted_vertical(top: u32, x: u32, dw: u32, dh1: u32, dh2: u32, sign: Sign)
+        -> HaarFilter {
+
+        tilted(combine_alternating(&[
+            tilted_eval_points(top,       x,       dw, dh1),
+            tilted_eval_points(top + dh1, x - dh1, dw, dh2)])) * multiplier(sign)
+    }
+
+    /// Returns the tilted equivalent of `three_region_horizontal` (with signs reversed
+    /// if Sign == Sign::Negative). The three regions are adjacent along the direction
+    /// down and to the right, with widths dw1, dw2 and dw3, and each extends dh pixels
+    /// down and to the left. The top corner of the first region is (x, top).
+    pub fn three_region_tilted_horizontal(
+        top: u32, x: u32, dw1: u32, dw2: u32, dw3: u32, dh: u32, sign: Sign)
+            -> HaarFilter {
+
+        tilted(combine_alternating(&[
+            tilted_eval_points(top,             x,             dw1, dh),
+            tilted_eval_points(top + dw1,       x + dw1,       dw2, dh),
+            tilted_eval_points(top + dw1 + dw2, x + dw1 + dw2, dw3, dh),
+            ])) * multiplier(sign)
+    }
+
+    /// Returns the tilted equivalent of `three_region_vertical` (with signs reversed
+    /// if Sign == Sign::Negative). The three regions are adjacent along the direction
+    /// down and to the left, with heights dh1, dh2 and dh3, and each extends dw pixels
+    /// down and to the right. The top corner of the first region is (x, top).
+    pub fn three_region_tilted_vertical(
+        top: u32, x: u32, dw: u32, dh1: u32, dh2: u32, dh3: u32, sign: Sign)
+            -> HaarFilter {
+
+        tilted(combine_alternating(&[
+            tilted_eval_points(top,             x,             dw, dh1),
+            tilted_eval_points(top + dh1,       x - dh1,       dw, dh2),
+            tilted_eval_points(top + dh1 + dh2, x - dh1 - dh2, dw, dh3),
+            ])) * multiplier(sign)
+    }
+
+    /// Returns the tilted equivalent of `four_region` (with signs reversed
+    /// if Sign == Sign::Negative). The top corner of the top region is (x, top).
+    /// The top and bottom regions are positive and the left and right regions
+    /// negative. The top and left regions have width dw1 and the top and right
+    /// regions have height dh1, where width is measured down and to the right
+    /// and height down and to the left.
+    pub fn four_region_tilted(
+        top: u32, x: u32, dw1: u32, dw2: u32, dh1: u32, dh2: u32, sign: Sign)
+            -> HaarFilter {
+
+        tilted(combine_alternating(&[
+            tilted_eval_points(top,             x,             dw1, dh1),
+            tilted_eval_points(top + dw1,       x + dw1,       dw2, dh1),
+            tilted_eval_points(top + dw1 + dh1, x + dw1 - dh1, dw2, dh2),
+            tilted_eval_points(top + dh1,       x - dh1,       dw1, dh2),
+            ])) * multiplier(sign)
+    }
 }
 
 /// See comment on `eval_points`.
@@ -272,6 +356,26 @@ fn eval_points(top: u32, left: u32, width: u32, height: u32) -> EvalPoints {
     )
 }
 
+/// Points at which to evaluate a tilted integral image to produce the sum of the
+/// pixel intensities of all points within a rectangle rotated by 45 degrees. The
+/// rectangle has top corner pixel (x, top) and extends width pixels down and to the
+/// right and height pixels down and to the left. Only valid when the rectangle is
+/// wholly contained in the image boundaries.
+fn tilted_eval_points(top: u32, x: u32, width: u32, height: u32) -> EvalPoints {
+    EvalPoints::new(
+        [(x + 1, top),
+         (x + 1 - height, top + height),
+         (x + width + 1, top + width),
+         (x + width + 1 - height, top + width + height)],
+        [1i8, -1i8, -1i8, 1i8]
+    )
+}
+
+/// Marks a filter as tilted.
+fn tilted(filter: HaarFilter) -> HaarFilter {
+    HaarFilter { tilted: true, ..filter }
+}
+
 /// Combine sets of evaluation points with alternating signs.
 /// The first entry of rects is counted with positive sign.
 // TODO: check that we don't have too many distinct points. This
@@ -309,7 +413,8 @@ fn combine_alternating(rects: &[EvalPoints]) -> HaarFilter {
     HaarFilter {
         points: points,
         weights: weights,
-        count: count }
+        count: count,
+        tilted: false }
 }
 
 fn multiplier(sign: Sign) -> i8 {
@@ -339,7 +444,13 @@ pub fn draw_haar_filter_mut<I>(image: &mut I, filter: HaarFilter)
         for x in 0..width {
             let mut weight = 0;
             for i in 0..filter.count {
-                if y < filter.points[2 * i + 1] && x < filter.points[2 * i] {
+                let (px, py) = (filter.points[2 * i], filter.points[2 * i + 1]);
+                let contributes = if filter.tilted {
+                    y < py && (x as i64 - px as i64 + 1).abs() < py as i64 - y as i64
+                } else {
+                    y < py && x < px
+                };
+                if contributes {
                     weight += filter.weights[i];
                 }
             }
@@ -370,10 +481,12 @@ mod test {
     };
     use image::{
         GrayImage,
-        ImageBuffer
+        ImageBuffer,
+        Luma
     };
     use integralimage::{
-        integral_image
+        integral_image,
+        tilted_integral_image
     };
     use utils::gray_bench_image;
     use test;
@@ -409,7 +522,8 @@ mod test {
         let expected = HaarFilter {
             points: [0, 0, 1, 0, 2, 0, 3, 0, 0, 1, 1, 1, 2, 1, 3, 1, 0, 0],
             weights: [1, -2, 2, -1, -1, 2, -2, 1, 0],
-            count: 8
+            count: 8,
+            tilted: false
         };
 
         assert_eq!(filter, expected);
@@ -421,7 +535,7 @@ mod test {
         // A   B   C
         //   +   -
         // D   E   F
-        let image = ImageBuffer::from_raw(5, 5, vec![
+        let image: GrayImage = ImageBuffer::from_raw(5, 5, vec![
             1u8,     2u8, 3u8,     4u8,     5u8,
                  /***+++++++++*****-----***/
             6u8, /**/7u8, 8u8,/**/ 9u8, /**/0u8,
@@ -430,7 +544,7 @@ mod test {
                  /***+++++++++*****-----***/
             6u8,     5u8, 4u8,     2u8,     1u8]).unwrap();
 
-        let integral = integral_image(&image);
+        let integral = integral_image::<_, u32>(&image);
         let filter = HaarFilter::two_region_horizontal(1, 1, 2, 1, 3, Sign::Positive);
         assert_eq!(filter.evaluate(&integral), 19i32);
     }
@@ -445,7 +559,7 @@ mod test {
         // E   F
         //   +
         // G   H
-        let image = ImageBuffer::from_raw(5, 5, vec![
+        let image: GrayImage = ImageBuffer::from_raw(5, 5, vec![
         /*****************/
         /*-*/1u8, 2u8,/*-*/ 3u8, 4u8, 5u8,
         /*****************/
@@ -456,7 +570,7 @@ mod test {
         /*****************/
              6u8, 5u8,      4u8, 2u8, 1u8]).unwrap();
 
-        let integral = integral_image(&image);
+        let integral = integral_image::<_, u32>(&image);
         let filter = HaarFilter::three_region_vertical(0, 0, 2, 1, 2, 1, Sign::Negative);
         assert_eq!(filter.evaluate(&integral), 20i32);
     }
@@ -469,7 +583,7 @@ mod test {
         // D   E   F
         //   -   +
         // G   H   I
-        let image = ImageBuffer::from_raw(5, 5, vec![
+        let image: GrayImage = ImageBuffer::from_raw(5, 5, vec![
         1u8,    2u8, 3u8,     4u8,     5u8,
             /************************/
         6u8,/**/7u8, 8u8,/**/ 9u8,/**/ 0u8,
@@ -479,7 +593,7 @@ mod test {
             /************************/
         6u8,    5u8, 4u8,     2u8,     1u8]).unwrap();
 
-        let integral = integral_image(&image);
+        let integral = integral_image::<_, u32>(&image);
         let filter = HaarFilter::four_region(1, 1, 2, 1, 1, 2, Sign::Positive);
 
         assert_eq!(filter.evaluate(&integral), -7i32);
@@ -559,12 +673,99 @@ mod test {
         assert_pixels_eq!(actual, expected);
     }
 
+    /// Sums the pixels in the tilted rectangle with top corner (x, top) extending
+    /// w pixels down and to the right and h pixels down and to the left.
+    fn tilted_region_sum(image: &GrayImage, top: u32, x: u32, w: u32, h: u32) -> i32 {
+        let mut sum = 0i32;
+        for i in 0..w {
+            for j in 0..h {
+                sum += image.get_pixel(x + i - j, top + i + j)[0] as i32;
+                sum += image.get_pixel(x + i - j, top + i + j + 1)[0] as i32;
+            }
+        }
+        sum
+    }
+
+    #[test]
+    fn test_two_region_tilted_horizontal() {
+        let image = gray_bench_image(10, 10);
+        let integral = tilted_integral_image::<_, u32>(&image);
+
+        let filter = HaarFilter::two_region_tilted_horizontal(1, 3, 2, 3, 2, Sign::Positive);
+        let expected = tilted_region_sum(&image, 1, 3, 2, 2) - tilted_region_sum(&image, 3, 5, 3, 2);
+
+        assert!(filter.is_tilted());
+        assert_eq!(filter.evaluate(&integral), expected);
+
+        let negative = HaarFilter::two_region_tilted_horizontal(1, 3, 2, 3, 2, Sign::Negative);
+        assert_eq!(negative.evaluate(&integral), -expected);
+    }
+
+    #[test]
+    fn test_two_region_tilted_vertical() {
+        let image = gray_bench_image(10, 10);
+        let integral = tilted_integral_image::<_, u32>(&image);
+
+        let filter = HaarFilter::two_region_tilted_vertical(0, 5, 2, 1, 3, Sign::Positive);
+        let expected = tilted_region_sum(&image, 0, 5, 2, 1) - tilted_region_sum(&image, 1, 4, 2, 3);
+
+        assert_eq!(filter.evaluate(&integral), expected);
+    }
+
+    #[test]
+    fn test_three_region_tilted() {
+        let image = gray_bench_image(12, 12);
+        let integral = tilted_integral_image::<_, u32>(&image);
+
+        let horizontal = HaarFilter::three_region_tilted_horizontal(0, 4, 1, 2, 3, 2, Sign::Positive);
+        let expected = tilted_region_sum(&image, 0, 4, 1, 2)
+            - tilted_region_sum(&image, 1, 5, 2, 2)
+            + tilted_region_sum(&image, 3, 7, 3, 2);
+        assert_eq!(horizontal.evaluate(&integral), expected);
+
+        let vertical = HaarFilter::three_region_tilted_vertical(1, 7, 2, 1, 2, 3, Sign::Positive);
+        let expected = tilted_region_sum(&image, 1, 7, 2, 1)
+            - tilted_region_sum(&image, 2, 6, 2, 2)
+            + tilted_region_sum(&image, 4, 4, 2, 3);
+        assert_eq!(vertical.evaluate(&integral), expected);
+    }
+
+    #[test]
+    fn test_four_region_tilted() {
+        let image = gray_bench_image(12, 12);
+        let integral = tilted_integral_image::<_, u32>(&image);
+
+        let filter = HaarFilter::four_region_tilted(0, 5, 2, 3, 1, 2, Sign::Positive);
+        let expected = tilted_region_sum(&image, 0, 5, 2, 1)
+            - tilted_region_sum(&image, 2, 7, 3, 1)
+            + tilted_region_sum(&image, 3, 6, 3, 2)
+            - tilted_region_sum(&image, 1, 4, 2, 2);
+        assert_eq!(filter.evaluate(&integral), expected);
+    }
+
+    #[test]
+    fn test_draw_haar_filter_two_region_tilted_horizontal() {
+        let image = GrayImage::from_pixel(5, 5, Luma([1u8]));
+
+        let filter = HaarFilter::two_region_tilted_horizontal(0, 1, 1, 1, 2, Sign::Positive);
+        let actual = draw_haar_filter(&image, filter);
+
+        let expected = ImageBuffer::from_raw(5, 5, vec![
+            1u8,   255u8, 1u8,   1u8,   1u8,
+            255u8, 255u8, 0u8,   1u8,   1u8,
+            255u8, 0u8,   0u8,   1u8,   1u8,
+            1u8,   0u8,   1u8,   1u8,   1u8,
+            1u8,   1u8,   1u8,   1u8,   1u8]).unwrap();
+
+        assert_pixels_eq!(actual, expected);
+    }
+
     #[bench]
     fn bench_evaluate_all_filters_10x10(b: &mut test::Bencher) {
         // 163350 filters in total
         let filters = enumerate_haar_filters(10, 10);
         let image = gray_bench_image(10, 10);
-        let integral = integral_image(&image);
+        let integral = integral_image::<_, u32>(&image);
 
         b.iter(|| {
             for filter in &filters {
@@ -0,0 +1,654 @@
+//! Line and circle detection via the [Hough transform].
+//!
+//! [Hough transform]: https://en.wikipedia.org/wiki/Hough_transform
+
+use image::{
+    GenericImage,
+    GrayImage,
+    ImageBuffer,
+    Luma
+};
+
+use definitions::{
+    Image,
+    Position,
+    Score
+};
+
+use drawing::{
+    draw_line_segment_mut
+};
+
+use gradients::{
+    horizontal_sobel,
+    vertical_sobel
+};
+
+use suppress::{
+    local_maxima,
+    suppress_non_maximum
+};
+
+use std::cmp;
+
+/// A detected line, in polar coordinates. The line consists of all points (x, y)
+/// satisfying x * cos(angle) + y * sin(angle) = r.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PolarLine {
+    /// Signed distance of the line from the origin (top-left of the image), in pixels.
+    pub r: f32,
+    /// Clockwise angle in degrees from the x-axis to the normal to the line.
+    /// Always lies in [0, 180).
+    pub angle_in_degrees: f32
+}
+
+/// Options for Hough line detection.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LineDetectionOptions {
+    /// Number of votes required to be detected as a line.
+    pub vote_threshold: u32,
+    /// Non-maxima suppression is applied to accumulator buckets before
+    /// applying `vote_threshold`. Buckets are suppressed if there is a bucket with
+    /// strictly more votes within this (L-infinity) distance in the accumulator.
+    pub suppression_radius: u32,
+    /// Width in degrees of each angle bucket in the accumulator.
+    pub angle_resolution: f32,
+    /// Width in pixels of each distance bucket in the accumulator.
+    pub rho_resolution: f32
+}
+
+impl LineDetectionOptions {
+    /// Options with the given threshold and suppression radius, using
+    /// buckets one degree wide and one pixel high.
+    pub fn new(vote_threshold: u32, suppression_radius: u32) -> LineDetectionOptions {
+        LineDetectionOptions {
+            vote_threshold: vote_threshold,
+            suppression_radius: suppression_radius,
+            angle_resolution: 1f32,
+            rho_resolution: 1f32
+        }
+    }
+}
+
+/// Accumulates line votes from the non-zero pixels of a binary image, e.g.
+/// the output of `edges::canny`. The returned image has one column per angle bucket
+/// and one row per distance bucket. The distance bucket with row index `i`
+/// corresponds to a signed distance of `(i - offset) * rho_resolution`, where
+/// `offset` is the second element of the returned pair.
+pub fn hough_accumulator(image: &GrayImage, options: LineDetectionOptions) -> (Image<Luma<u32>>, u32) {
+    assert!(options.angle_resolution > 0f32, "angle_resolution must be strictly positive");
+    assert!(options.rho_resolution > 0f32, "rho_resolution must be strictly positive");
+
+    let (width, height) = image.dimensions();
+    let max_r = (width as f32).hypot(height as f32);
+    let offset = (max_r / options.rho_resolution).ceil() as u32;
+    let num_angles = (180f32 / options.angle_resolution).ceil() as u32;
+    let num_rhos = 2 * offset + 1;
+
+    let trig: Vec<(f32, f32)> = (0..num_angles)
+        .map(|a| (a as f32 * options.angle_resolution).to_radians().sin_cos())
+        .collect();
+
+    let mut accumulator: Image<Luma<u32>> = ImageBuffer::new(num_angles, num_rhos);
+
+    for (x, y, p) in image.enumerate_pixels() {
+        if p[0] == 0 {
+            continue;
+        }
+        for (a, &(sin, cos)) in trig.iter().enumerate() {
+            let r = x as f32 * cos + y as f32 * sin;
+            let bucket = ((r / options.rho_resolution).round() as i32 + offset as i32) as u32;
+            unsafe {
+                let votes = accumulator.unsafe_get_pixel(a as u32, bucket)[0];
+                accumulator.unsafe_put_pixel(a as u32, bucket, Luma([votes + 1]));
+            }
+        }
+    }
+
+    (accumulator, offset)
+}
+
+/// Detects lines in a binary input image using the Hough transform.
+///
+/// Points are considered to be in the foreground (and thus vote for lines)
+/// if their intensity is non-zero. Lines are returned in decreasing order
+/// of votes received.
+///
+/// Lines with angles near 0 and near 180 degrees are treated as neighbours when
+/// suppressing non-maxima only if 180 is a multiple of `angle_resolution`.
+///
+/// See ./examples/hough.rs for example usage.
+pub fn detect_lines(image: &GrayImage, options: LineDetectionOptions) -> Vec<PolarLine> {
+    let (accumulator, offset) = hough_accumulator(image, options);
+    let (num_angles, num_rhos) = accumulator.dimensions();
+
+    // Lines with angles close to 0 and close to 180 degrees may be almost identical,
+    // so we pad the accumulator with wrapped columns before suppressing non-maxima.
+    // The first column only follows the last if 180 is a multiple of the angle resolution.
+    let wraps = (num_angles as f32 * options.angle_resolution - 180f32).abs() < 1e-3;
+    let pad = if wraps { cmp::min(options.suppression_radius, num_angles) } else { 0 };
+    let padded = ImageBuffer::from_fn(num_angles + 2 * pad, num_rhos, |x, bucket| {
+        if x < pad {
+            // An angle of a - 180 degrees with distance r is an angle of a with distance -r.
+            accumulator[(x + num_angles - pad, 2 * offset - bucket)]
+        } else if x >= num_angles + pad {
+            accumulator[(x - num_angles - pad, 2 * offset - bucket)]
+        } else {
+            accumulator[(x - pad, bucket)]
+        }
+    });
+    let maxima = suppress_non_maximum(&padded, options.suppression_radius);
+
+    let mut lines = vec![];
+    for a in 0..num_angles {
+        for bucket in 0..num_rhos {
+            let votes = maxima[(a + pad, bucket)][0];
+            if votes == 0 || votes < options.vote_threshold {
+                continue;
+            }
+            let line = PolarLine {
+                r: (bucket as i32 - offset as i32) as f32 * options.rho_resolution,
+                angle_in_degrees: a as f32 * options.angle_resolution
+            };
+            lines.push((votes, line));
+        }
+    }
+
+    // Stable sort, so ties remain in accumulator order.
+    lines.sort_by(|l, m| m.0.cmp(&l.0));
+    lines.into_iter().map(|(_, line)| line).collect()
+}
+
+/// Draws each element of `lines` on `image` in the provided `color`.
+///
+/// See ./examples/hough.rs for example usage.
+pub fn draw_polar_lines<I>(image: &I, lines: &[PolarLine], color: I::Pixel) -> Image<I::Pixel>
+    where I: GenericImage,
+          I::Pixel: 'static
+{
+    let mut out = ImageBuffer::new(image.width(), image.height());
+    out.copy_from(image, 0, 0);
+    draw_polar_lines_mut(&mut out, lines, color);
+    out
+}
+
+/// Draws each element of `lines` on `image` in the provided `color`.
+///
+/// See ./examples/hough.rs for example usage.
+pub fn draw_polar_lines_mut<I>(image: &mut I, lines: &[PolarLine], color: I::Pixel)
+    where I: GenericImage,
+          I::Pixel: 'static
+{
+    for line in lines {
+        if let Some((start, end)) = intersection_points(*line, image.width(), image.height()) {
+            draw_line_segment_mut(image, start, end, color);
+        }
+    }
+}
+
+/// Returns the points at which a line meets the boundary of the rectangle
+/// [0, width - 1] * [0, height - 1], or None if the line misses the rectangle.
+fn intersection_points(line: PolarLine, width: u32, height: u32) -> Option<((f32, f32), (f32, f32))> {
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let (sin, cos) = line.angle_in_degrees.to_radians().sin_cos();
+    let right = (width - 1) as f32;
+    let bottom = (height - 1) as f32;
+    let eps = 1e-6;
+    let mut points = vec![];
+
+    // Intersections with the top and bottom edges.
+    if cos.abs() > eps {
+        for &y in &[0f32, bottom] {
+            let x = (line.r - y * sin) / cos;
+            if x >= 0f32 && x <= right {
+                points.push((x, y));
+            }
+        }
+    }
+
+    // Intersections with the left and right edges.
+    if sin.abs() > eps {
+        for &x in &[0f32, right] {
+            let y = (line.r - x * cos) / sin;
+            if y >= 0f32 && y <= bottom {
+                points.push((x, y));
+            }
+        }
+    }
+
+    // A line through a corner may have been recorded more than once,
+    // so choose the pair of points which are furthest apart.
+    let mut best: Option<((f32, f32), (f32, f32))> = None;
+    let mut best_distance = -1f32;
+    for p in &points {
+        for q in &points {
+            let d = (p.0 - q.0).powi(2) + (p.1 - q.1).powi(2);
+            if d > best_distance {
+                best_distance = d;
+                best = Some((*p, *q));
+            }
+        }
+    }
+
+    best
+}
+
+/// A detected circle.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Circle {
+    /// x-coordinate of the circle's center.
+    pub x: u32,
+    /// y-coordinate of the circle's center.
+    pub y: u32,
+    /// Radius of the circle, in pixels.
+    pub radius: u32,
+    /// Number of edge pixels which voted for this circle.
+    pub votes: u32
+}
+
+impl Position for Circle {
+    fn x(&self) -> u32 {
+        self.x
+    }
+
+    fn y(&self) -> u32 {
+        self.y
+    }
+}
+
+impl Score for Circle {
+    fn score(&self) -> f32 {
+        self.votes as f32
+    }
+}
+
+/// Options for Hough circle detection.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CircleDetectionOptions {
+    /// Smallest radius to search for, in pixels.
+    pub min_radius: u32,
+    /// Largest radius to search for, in pixels. Radii larger than the
+    /// length of the image diagonal are not searched.
+    pub max_radius: u32,
+    /// Number of votes required to be detected as a circle.
+    pub vote_threshold: u32,
+    /// Circles are suppressed if there is a circle with more votes whose center
+    /// lies within this (L-infinity) distance of their center.
+    pub suppression_radius: u32
+}
+
+impl CircleDetectionOptions {
+    /// User-provided options, prior to validation.
+    pub fn new(min_radius: u32, max_radius: u32, vote_threshold: u32, suppression_radius: u32)
+            -> CircleDetectionOptions {
+        CircleDetectionOptions {
+            min_radius: min_radius,
+            max_radius: max_radius,
+            vote_threshold: vote_threshold,
+            suppression_radius: suppression_radius
+        }
+    }
+}
+
+/// Detects circles using the Hough transform.
+///
+/// `edges` is a binary image of the same size as `image`, e.g. the output of `edges::canny`.
+/// Each non-zero pixel of `edges` votes for the circle centers lying at each permitted radius
+/// along the direction of the intensity gradient of `image` at that pixel, in both directions.
+/// Using the gradient direction means that each edge pixel votes for two centers per radius
+/// rather than for a full circle of them.
+///
+/// Each accumulator bucket is credited with the votes for all buckets within one step of it
+/// in position or radius. For each candidate center only the radius receiving the most votes
+/// is considered, and candidates are then suppressed using `suppress::local_maxima`.
+/// Circles are returned in decreasing order of votes received.
+///
+/// Radii are processed one at a time, so memory use is a small multiple of the image
+/// size and does not depend on the number of radii searched.
+pub fn detect_circles(image: &GrayImage, edges: &GrayImage, options: CircleDetectionOptions) -> Vec<Circle> {
+    assert!(image.dimensions() == edges.dimensions(), "image and edges must have the same dimensions");
+    assert!(options.min_radius <= options.max_radius, "min_radius must not exceed max_radius");
+
+    let (width, height) = image.dimensions();
+    let (w, h) = (width as usize, height as usize);
+
+    // Edge pixels and centers both lie in the image, so larger radii receive no votes.
+    let max_radius = cmp::min(options.max_radius, (width as f32).hypot(height as f32).ceil() as u32 + 1);
+    if options.min_radius > max_radius {
+        return vec![];
+    }
+    let num_radii = (max_radius - options.min_radius) as usize + 1;
+
+    let gx = horizontal_sobel(image);
+    let gy = vertical_sobel(image);
+
+    // Positions and unit gradient directions of the edge pixels.
+    let mut directions = vec![];
+    for (x, y, p) in edges.enumerate_pixels() {
+        if p[0] == 0 {
+            continue;
+        }
+        let dx = gx[(x, y)][0] as f32;
+        let dy = gy[(x, y)][0] as f32;
+        let magnitude = dx.hypot(dy);
+        if magnitude == 0f32 {
+            continue;
+        }
+        directions.push((x as f32, y as f32, dx / magnitude, dy / magnitude));
+    }
+
+    // Edge pixels on a digitised circle lie at slightly varying distances from its center,
+    // and gradient directions are only approximate, so votes for a single circle are spread
+    // over neighbouring buckets. We count each bucket as receiving all the votes from the
+    // 3x3x3 block of buckets centered on it. To avoid storing the votes for every radius at
+    // once, we accumulate one radius at a time, keeping the previous and next radii to sum
+    // over, and track the radius with the most votes for each center as we go.
+    let votes_for_radius = |i: usize| -> Vec<u32> {
+        let mut votes = vec![0u32; w * h];
+        if i >= num_radii {
+            return votes;
+        }
+        let r = (options.min_radius as usize + i) as f32;
+        for &(x, y, ux, uy) in &directions {
+            for &sign in &[-1f32, 1f32] {
+                let cx = (x + sign * r * ux).round();
+                let cy = (y + sign * r * uy).round();
+                if cx < 0f32 || cx >= width as f32 || cy < 0f32 || cy >= height as f32 {
+                    continue;
+                }
+                votes[cy as usize * w + cx as usize] += 1;
+            }
+        }
+        box_sum_along_axis(&mut votes, 1, w);
+        box_sum_along_axis(&mut votes, w, h);
+        votes
+    };
+
+    // The radius index and number of votes of the best radius for each center.
+    let mut best = vec![(0usize, 0u32); w * h];
+    let mut previous = vec![0u32; w * h];
+    let mut current = votes_for_radius(0);
+    for i in 0..num_radii {
+        let next = votes_for_radius(i + 1);
+        for (j, b) in best.iter_mut().enumerate() {
+            let votes = previous[j] + current[j] + next[j];
+            if votes > b.1 {
+                *b = (i, votes);
+            }
+        }
+        previous = current;
+        current = next;
+    }
+
+    let mut candidates = vec![];
+    for y in 0..h {
+        for x in 0..w {
+            let (best_radius, votes) = best[y * w + x];
+            if votes == 0 || votes < options.vote_threshold {
+                continue;
+            }
+            candidates.push(Circle {
+                x: x as u32,
+                y: y as u32,
+                radius: options.min_radius + best_radius as u32,
+                votes: votes
+            });
+        }
+    }
+
+    let mut circles = local_maxima(&candidates, options.suppression_radius);
+    // Stable sort, so ties remain in raster order.
+    circles.sort_by(|c, d| d.votes.cmp(&c.votes));
+    circles
+}
+
+/// Replaces each element of `data` by the sum of it and its immediate neighbours along one
+/// axis of a multidimensional array, where consecutive elements along this axis are `stride`
+/// apart in `data` and the axis has length `len`.
+fn box_sum_along_axis(data: &mut [u32], stride: usize, len: usize) {
+    if len < 2 {
+        return;
+    }
+    let block = stride * len;
+    for start in 0..data.len() {
+        // Visit each line along this axis once, from its first element.
+        if (start % block) >= stride {
+            continue;
+        }
+        let mut previous = 0;
+        for i in 0..len {
+            let idx = start + i * stride;
+            let current = data[idx];
+            let next = if i + 1 < len { data[idx + stride] } else { 0 };
+            data[idx] = previous + current + next;
+            previous = current;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::{
+        GrayImage,
+        ImageBuffer,
+        Luma
+    };
+    use drawing::{
+        draw_filled_circle_mut,
+        draw_line_segment_mut
+    };
+    use edges::canny;
+    use test::{
+        Bencher,
+        black_box
+    };
+
+    fn assert_lines_eq(actual: &[PolarLine], expected: &[PolarLine]) {
+        assert_eq!(actual.len(), expected.len(), "actual: {:?}, expected: {:?}", actual, expected);
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert!((a.r - e.r).abs() < 1e-4 && (a.angle_in_degrees - e.angle_in_degrees).abs() < 1e-4,
+                "actual: {:?}, expected: {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_detect_horizontal_line() {
+        let mut image = GrayImage::new(80, 10);
+        draw_line_segment_mut(&mut image, (0f32, 5f32), (79f32, 5f32), Luma([255u8]));
+
+        let lines = detect_lines(&image, LineDetectionOptions::new(50, 5));
+        assert_lines_eq(&lines, &[PolarLine { r: 5f32, angle_in_degrees: 90f32 }]);
+    }
+
+    #[test]
+    fn test_detect_vertical_line() {
+        let mut image = GrayImage::new(10, 80);
+        draw_line_segment_mut(&mut image, (3f32, 0f32), (3f32, 79f32), Luma([255u8]));
+
+        let lines = detect_lines(&image, LineDetectionOptions::new(50, 5));
+        assert_lines_eq(&lines, &[PolarLine { r: 3f32, angle_in_degrees: 0f32 }]);
+    }
+
+    #[test]
+    fn test_detect_diagonal_line() {
+        let mut image = GrayImage::new(20, 20);
+        draw_line_segment_mut(&mut image, (0f32, 19f32), (19f32, 0f32), Luma([255u8]));
+
+        let lines = detect_lines(&image, LineDetectionOptions::new(15, 5));
+        let r = 19f32 / 2f32.sqrt();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].angle_in_degrees, 45f32);
+        assert!((lines[0].r - r).abs() <= 0.5f32);
+    }
+
+    #[test]
+    fn test_detect_lines_with_coarse_resolution() {
+        let mut image = GrayImage::new(20, 10);
+        draw_line_segment_mut(&mut image, (0f32, 6f32), (19f32, 6f32), Luma([255u8]));
+
+        let mut options = LineDetectionOptions::new(15, 5);
+        options.angle_resolution = 10f32;
+        options.rho_resolution = 2f32;
+
+        let lines = detect_lines(&image, options);
+        assert_lines_eq(&lines, &[PolarLine { r: 6f32, angle_in_degrees: 90f32 }]);
+    }
+
+    #[test]
+    fn test_detect_lines_with_non_dividing_angle_resolution() {
+        // The last angle bucket is 175 degrees, so the first bucket is not its neighbour.
+        let mut image = GrayImage::new(60, 60);
+        draw_line_segment_mut(&mut image, (10f32, 0f32), (10f32, 59f32), Luma([255u8]));
+        let (sin, cos) = 175f32.to_radians().sin_cos();
+        for y in 0..60 {
+            let x = (-10f32 - y as f32 * sin) / cos;
+            image.put_pixel(x.round() as u32, y, Luma([255u8]));
+        }
+
+        let mut options = LineDetectionOptions::new(40, 1);
+        options.angle_resolution = 7f32;
+
+        let lines = detect_lines(&image, options);
+        assert_lines_eq(&lines, &[
+            PolarLine { r: 10f32, angle_in_degrees: 0f32 },
+            PolarLine { r: -10f32, angle_in_degrees: 175f32 }
+        ]);
+    }
+
+    #[test]
+    fn test_detect_lines_respects_vote_threshold() {
+        let mut image = GrayImage::new(20, 10);
+        draw_line_segment_mut(&mut image, (0f32, 5f32), (19f32, 5f32), Luma([255u8]));
+
+        let lines = detect_lines(&image, LineDetectionOptions::new(21, 5));
+        assert!(lines.is_empty());
+    }
+
+    #[test]
+    fn test_hough_accumulator_dimensions() {
+        let image = GrayImage::new(3, 4);
+        let (accumulator, offset) = hough_accumulator(&image, LineDetectionOptions::new(1, 1));
+        assert_eq!(offset, 5);
+        assert_eq!(accumulator.dimensions(), (180, 11));
+    }
+
+    #[test]
+    fn test_hough_accumulator_wide_image() {
+        // width * width overflows u32
+        let image = GrayImage::new(70000, 1);
+        let mut options = LineDetectionOptions::new(1, 1);
+        options.angle_resolution = 90f32;
+        let (accumulator, offset) = hough_accumulator(&image, options);
+        assert_eq!(offset, 70000);
+        assert_eq!(accumulator.dimensions(), (2, 140001));
+    }
+
+    #[test]
+    fn test_draw_polar_lines() {
+        let image = GrayImage::new(5, 4);
+        let lines = [PolarLine { r: 2f32, angle_in_degrees: 90f32 },
+                     PolarLine { r: 1f32, angle_in_degrees: 0f32 }];
+
+        let expected: GrayImage = ImageBuffer::from_raw(5, 4, vec![
+            0, 9, 0, 0, 0,
+            0, 9, 0, 0, 0,
+            9, 9, 9, 9, 9,
+            0, 9, 0, 0, 0]).unwrap();
+
+        assert_pixels_eq!(draw_polar_lines(&image, &lines, Luma([9u8])), expected);
+    }
+
+    #[test]
+    fn test_draw_polar_lines_outside_image() {
+        let image = GrayImage::new(5, 4);
+        let lines = [PolarLine { r: 20f32, angle_in_degrees: 45f32 }];
+        assert_pixels_eq!(draw_polar_lines(&image, &lines, Luma([9u8])), image);
+    }
+
+    fn hough_bench_image(width: u32, height: u32) -> GrayImage {
+        let mut image = GrayImage::new(width, height);
+        let (w, h) = (width as f32, height as f32);
+        draw_line_segment_mut(&mut image, (0f32, 0f32), (w - 1f32, h - 1f32), Luma([255u8]));
+        draw_line_segment_mut(&mut image, (w / 3f32, 0f32), (w / 3f32, h - 1f32), Luma([255u8]));
+        draw_line_segment_mut(&mut image, (0f32, h / 2f32), (w - 1f32, h / 4f32), Luma([255u8]));
+        image
+    }
+
+    #[bench]
+    fn bench_detect_lines(b: &mut Bencher) {
+        let image = hough_bench_image(200, 150);
+        let options = LineDetectionOptions::new(50, 10);
+        b.iter(|| {
+            let lines = detect_lines(&image, options);
+            black_box(lines);
+        });
+    }
+
+    fn circle_image(width: u32, height: u32, circles: &[(i32, i32, i32)]) -> GrayImage {
+        let mut image = GrayImage::new(width, height);
+        for &(x, y, r) in circles {
+            draw_filled_circle_mut(&mut image, (x, y), r, Luma([255u8]));
+        }
+        image
+    }
+
+    #[test]
+    fn test_detect_circles() {
+        let image = circle_image(60, 50, &[(20, 22, 10), (45, 25, 6)]);
+        let edges = canny(&image, 50f32, 100f32);
+
+        let options = CircleDetectionOptions::new(4, 15, 40, 5);
+        let mut circles = detect_circles(&image, &edges, options);
+        circles.sort_by_key(|c| c.x);
+
+        assert_eq!(circles.len(), 2, "{:?}", circles);
+        for (circle, &(x, y, r)) in circles.iter().zip([(20, 22, 10), (45, 25, 6)].iter()) {
+            assert!((circle.x as i32 - x).abs() <= 1, "{:?}", circles);
+            assert!((circle.y as i32 - y).abs() <= 1, "{:?}", circles);
+            assert!((circle.radius as i32 - r).abs() <= 1, "{:?}", circles);
+        }
+    }
+
+    #[test]
+    fn test_detect_circles_respects_radius_range() {
+        let image = circle_image(60, 50, &[(25, 25, 10)]);
+        let edges = canny(&image, 50f32, 100f32);
+
+        let options = CircleDetectionOptions::new(15, 20, 40, 5);
+        let circles = detect_circles(&image, &edges, options);
+        assert!(circles.is_empty(), "{:?}", circles);
+    }
+
+    #[test]
+    fn test_detect_circles_with_unbounded_radius() {
+        let image = circle_image(60, 50, &[(25, 25, 10)]);
+        let edges = canny(&image, 50f32, 100f32);
+
+        let bounded = detect_circles(&image, &edges, CircleDetectionOptions::new(4, 100, 40, 5));
+        let unbounded = detect_circles(&image, &edges, CircleDetectionOptions::new(4, ::std::u32::MAX, 40, 5));
+        assert_eq!(unbounded, bounded);
+        assert!(detect_circles(&image, &edges, CircleDetectionOptions::new(::std::u32::MAX, ::std::u32::MAX, 1, 5)).is_empty());
+    }
+
+    #[test]
+    fn test_detect_circles_on_empty_image() {
+        let image = GrayImage::new(10, 10);
+        let options = CircleDetectionOptions::new(1, 5, 1, 1);
+        assert!(detect_circles(&image, &image, options).is_empty());
+    }
+
+    #[bench]
+    fn bench_detect_circles(b: &mut Bencher) {
+        let image = circle_image(100, 100, &[(30, 30, 15), (70, 60, 20)]);
+        let edges = canny(&image, 50f32, 100f32);
+        let options = CircleDetectionOptions::new(5, 25, 40, 10);
+        b.iter(|| {
+            let circles = detect_circles(&image, &edges, options);
+            black_box(circles);
+        });
+    }
+}
@@ -4,17 +4,30 @@
 extern crate image;
 
 use image::{
-    Luma,
-    GrayImage,
     GenericImage,
-    ImageBuffer
+    Pixel,
+    Primitive
 };
 
 use definitions::{
     Image
 };
 
-/// Compute the 2d running sum of a grayscale image.
+use filter::{
+    BorderMode,
+    pixel_with_border
+};
+
+use map::{
+    ChannelMap,
+    WithChannel
+};
+
+use conv::ValueInto;
+use math::cast;
+use num::Num;
+
+/// Computes the 2d running sum of an image. Channels are summed independently.
 ///
 /// An integral image I has width and height one greater than its source image F,
 /// and is defined by I(x, y) = sum of F(x', y') for x' < x, y' < y, i.e. each pixel
@@ -28,26 +41,157 @@ use definitions::{
 /// in constant time. Specifically, given a rectangle [l, r] * [t, b] in F,
 /// the sum of the pixels in this rectangle is
 /// I(r + 1, b + 1) - I(r + 1, t) - I(l, b + 1) + I(l, t).
-// TODO: Support more formats, make faster, add a new IntegralImage type
-// TODO: to make it harder to make off-by-one errors when computing sums of regions.
-pub fn integral_image(image: &GrayImage) -> Image<Luma<u32>> {
+///
+/// The accumulator type T is chosen by the caller. A `u32` accumulator can overflow
+/// for 8bpp images with more than 2^24 pixels, in which case use `u64` or `f64`.
+// TODO: Add a new IntegralImage type to make it harder to make off-by-one
+// TODO: errors when computing sums of regions.
+pub fn integral_image<P, T>(image: &Image<P>) -> Image<ChannelMap<P, T>>
+    where P: WithChannel<T> + 'static,
+          <P as Pixel>::Subpixel: ValueInto<T>,
+          T: Primitive + 'static
+{
+    integral_image_impl(image, false)
+}
+
+/// Computes the 2d running sum of the squares of the intensities in an image.
+/// Channels are summed independently.
+///
+/// See the [`integral_image`](fn.integral_image.html) documentation for more information
+/// on integral images. Combined with an integral image, this allows the variance of any
+/// rectangular region to be computed in constant time - see [`variance`](fn.variance.html).
+pub fn integral_squared_image<P, T>(image: &Image<P>) -> Image<ChannelMap<P, T>>
+    where P: WithChannel<T> + 'static,
+          <P as Pixel>::Subpixel: ValueInto<T>,
+          T: Primitive + 'static
+{
+    integral_image_impl(image, true)
+}
+
+fn integral_image_impl<P, T>(image: &Image<P>, square: bool) -> Image<ChannelMap<P, T>>
+    where P: WithChannel<T> + 'static,
+          <P as Pixel>::Subpixel: ValueInto<T>,
+          T: Primitive + 'static
+{
     let (in_width, in_height) = image.dimensions();
     let out_width = in_width + 1;
     let out_height = in_height + 1;
 
-    let mut out = ImageBuffer::from_pixel(out_width, out_height, Luma([0u32]));
+    let mut out = Image::<ChannelMap<P, T>>::new(out_width, out_height);
 
     if in_width == 0 || in_height == 0 {
         return out;
     }
 
+    let num_channels = P::channel_count() as usize;
+    let mut sum = vec![T::zero(); num_channels];
+
     for y in 1..out_height {
-        let mut sum = 0;
+        for s in sum.iter_mut() {
+            *s = T::zero();
+        }
         for x in 1..out_width {
-            unsafe {
-                sum += image.unsafe_get_pixel(x - 1, y - 1)[0] as u32;
-                let above = out.unsafe_get_pixel(x, y - 1)[0];
-                out.unsafe_put_pixel(x, y, Luma([above + sum]))
+            let (p, above) = unsafe {
+                (image.unsafe_get_pixel(x - 1, y - 1), out.unsafe_get_pixel(x, y - 1))
+            };
+            let out_channels = out.get_pixel_mut(x, y).channels_mut();
+            for c in 0..num_channels {
+                let v: T = cast(p.channels()[c]);
+                sum[c] = sum[c] + if square { v * v } else { v };
+                out_channels[c] = above.channels()[c] + sum[c];
+            }
+        }
+    }
+
+    out
+}
+
+/// Computes the 45 degree rotated running sum of an image, as used to evaluate
+/// tilted Haar features. Channels are summed independently.
+///
+/// A tilted integral image T has width two greater and height one greater than its
+/// source image F, and is defined by T(x, y) = sum of F(x', y') for y' < y and
+/// |x' - x + 1| < y - y', i.e. each pixel in the tilted integral image contains the
+/// sum of all input pixels in the triangle with apex (x - 1, y - 1) extending upwards
+/// at 45 degrees on either side.
+///
+/// Given the rectangle whose top corner is the pixel (x, y) and which extends `w` pixels
+/// down and to the right and `h` pixels down and to the left, the sum of the pixels in
+/// this rectangle is
+/// T(x + w - h + 1, y + w + h) - T(x - h + 1, y + h) - T(x + w + 1, y + w) + T(x + 1, y).
+/// See [Lienhart and Maydt](http://www.lienhart.de/Prof._Dr._Rainer_Lienhart/Source_Code_files/ICIP2002.pdf)
+/// for more details.
+pub fn tilted_integral_image<P, T>(image: &Image<P>) -> Image<ChannelMap<P, T>>
+    where P: WithChannel<T> + 'static,
+          <P as Pixel>::Subpixel: ValueInto<T>,
+          T: Primitive + 'static
+{
+    let (in_width, in_height) = image.dimensions();
+    let mut out = Image::<ChannelMap<P, T>>::new(in_width + 2, in_height + 1);
+
+    if in_width == 0 || in_height == 0 {
+        return out;
+    }
+
+    let num_channels = P::channel_count() as usize;
+    let (width, height) = (in_width as usize, in_height as usize);
+
+    // Entry k of anti_diagonal is the sum of all pixels (x, y) with x + y < k,
+    // and entry k of diagonal is the sum of all pixels with y - x < k - width + 1.
+    // These give the running sums of triangles whose apex lies outside the image.
+    let num_diagonals = width + height;
+    let mut anti_diagonal = vec![T::zero(); num_diagonals * num_channels];
+    let mut diagonal = vec![T::zero(); num_diagonals * num_channels];
+    for y in 0..height {
+        for x in 0..width {
+            let p = unsafe { image.unsafe_get_pixel(x as u32, y as u32) };
+            for c in 0..num_channels {
+                let v: T = cast(p.channels()[c]);
+                let a = (x + y + 1) * num_channels + c;
+                let d = (y + width - x) * num_channels + c;
+                anti_diagonal[a] = anti_diagonal[a] + v;
+                diagonal[d] = diagonal[d] + v;
+            }
+        }
+    }
+    for k in num_channels..num_diagonals * num_channels {
+        anti_diagonal[k] = anti_diagonal[k] + anti_diagonal[k - num_channels];
+        diagonal[k] = diagonal[k] + diagonal[k - num_channels];
+    }
+
+    // Writing R(x, y) for T(x + 1, y + 1), we have
+    // R(x, y) = R(x - 1, y - 1) + R(x + 1, y - 1) - R(x, y - 2) + F(x, y) + F(x, y - 1).
+    for y in 0..height {
+        for x_out in 0..width + 2 {
+            for c in 0..num_channels {
+                let left = if x_out > 0 {
+                    out.get_pixel(x_out as u32 - 1, y as u32).channels()[c]
+                } else if y >= 2 {
+                    anti_diagonal[(y - 2) * num_channels + c]
+                } else {
+                    T::zero()
+                };
+                let right = if x_out < width + 1 {
+                    out.get_pixel(x_out as u32 + 1, y as u32).channels()[c]
+                } else if y >= 2 {
+                    diagonal[(y - 2) * num_channels + c]
+                } else {
+                    T::zero()
+                };
+                let above = if y >= 1 {
+                    out.get_pixel(x_out as u32, y as u32 - 1).channels()[c]
+                } else {
+                    T::zero()
+                };
+                let mut current = left + right;
+                if x_out >= 1 && x_out <= width {
+                    let x = x_out as u32 - 1;
+                    current = current + cast(image.get_pixel(x, y as u32).channels()[c]);
+                    if y >= 1 {
+                        current = current + cast(image.get_pixel(x, y as u32 - 1).channels()[c]);
+                    }
+                }
+                out.get_pixel_mut(x_out as u32, y as u32 + 1).channels_mut()[c] = current - above;
             }
         }
     }
@@ -56,75 +200,141 @@ pub fn integral_image(image: &GrayImage) -> Image<Luma<u32>> {
 }
 
 /// Sums the pixels in positions [left, right] * [top, bottom] in F, where `integral_image` is the
-/// integral image of F.
+/// integral image of F. Returns a pixel containing the sum of each channel.
 // TODO: better type-safety. It's too easy to pass the original image in here by mistake.
-pub fn sum_image_pixels(integral_image: &Image<Luma<u32>>, left: u32, top: u32, right: u32, bottom: u32) -> u32 {
-    let sum = integral_image.get_pixel(right + 1, bottom + 1)[0] as i32
-            - integral_image.get_pixel(right + 1, top)[0] as i32
-            - integral_image.get_pixel(left, bottom + 1)[0] as i32
-            + integral_image.get_pixel(left, top)[0] as i32;
-    sum as u32
+pub fn sum_image_pixels<P>(integral_image: &Image<P>, left: u32, top: u32, right: u32, bottom: u32) -> P
+    where P: Pixel + 'static
+{
+    let mut sum = *integral_image.get_pixel(right + 1, bottom + 1);
+    {
+        let tr = integral_image.get_pixel(right + 1, top);
+        let bl = integral_image.get_pixel(left, bottom + 1);
+        let tl = integral_image.get_pixel(left, top);
+        // Ordered so that no intermediate value is negative, as the
+        // subpixel type may be unsigned.
+        for (c, s) in sum.channels_mut().iter_mut().enumerate() {
+            *s = (*s - tr.channels()[c]) - (bl.channels()[c] - tl.channels()[c]);
+        }
+    }
+    sum
+}
+
+/// Computes the variance of each channel of the pixels in positions [left, right] * [top, bottom]
+/// in F, where `integral_image` is the integral image of F and `integral_squared_image` is the
+/// integral squared image of F.
+pub fn variance<P>(integral_image: &Image<P>, integral_squared_image: &Image<P>,
+                   left: u32, top: u32, right: u32, bottom: u32) -> ChannelMap<P, f64>
+    where P: WithChannel<f64> + 'static,
+          <P as Pixel>::Subpixel: ValueInto<f64>
+{
+    let n = (right - left + 1) as f64 * (bottom - top + 1) as f64;
+    let sum = sum_image_pixels(integral_image, left, top, right, bottom);
+    let sum_squares = sum_image_pixels(integral_squared_image, left, top, right, bottom);
+
+    let mut out = ChannelMap::<P, f64>::from_channels(0.0, 0.0, 0.0, 0.0);
+    for (c, v) in out.channels_mut().iter_mut().enumerate() {
+        let mean: f64 = cast::<_, f64>(sum.channels()[c]) / n;
+        let mean_squares: f64 = cast::<_, f64>(sum_squares.channels()[c]) / n;
+        *v = f64::max(0.0, mean_squares - mean * mean);
+    }
+    out
 }
 
 /// Computes the running sum of one row of image, padded
-/// at the beginning and end. The padding is by continuity.
+/// at the beginning and end. Padding values are determined by `border`.
 /// Takes a reference to buffer so that this can be reused
 /// for all rows in an image.
-// TODO: faster, more formats
-pub fn row_running_sum(image: &GrayImage, row: u32, buffer: &mut [u32], padding: u32) {
-
+///
+/// Sums are computed independently for each channel, and stored
+/// interleaved in buffer, i.e. the running sum of channel c at
+/// padded position i is stored in `buffer[i * num_channels + c]`.
+pub fn row_running_sum<P, K>(image: &Image<P>, row: u32, buffer: &mut [K], padding: u32,
+                             border: BorderMode<P>)
+    where P: Pixel + 'static,
+          <P as Pixel>::Subpixel: ValueInto<K>,
+          K: Num + Copy
+{
     let (width, height) = image.dimensions();
-    assert!(buffer.len() >= (width + 2 * padding) as usize,
-        format!("Buffer length {} is less than {} + 2 * {}", buffer.len(), width, padding));
+    let num_channels = P::channel_count() as usize;
+    assert!(buffer.len() >= (width + 2 * padding) as usize * num_channels,
+        format!("Buffer length {} is less than ({} + 2 * {}) * {}",
+                buffer.len(), width, padding, num_channels));
     assert!(row < height, format!("row out of bounds: {} >= {}", row, height));
 
-    unsafe {
-        let mut sum = 0;
-        for x in 0..padding {
-            sum += image.unsafe_get_pixel(0, row)[0] as u32;
-            *buffer.get_unchecked_mut(x as usize) = sum;
-        }
+    let mut sum = vec![K::zero(); num_channels];
+    for x in 0..padding {
+        let x_unchecked = x as i64 - padding as i64;
+        let p = pixel_with_border(image, x_unchecked, row as i64, border);
+        let start = x as usize * num_channels;
+        accumulate_running_sum(&mut sum, &p, &mut buffer[start..start + num_channels]);
+    }
 
-        for x in 0..width {
-            sum += image.unsafe_get_pixel(x, row)[0] as u32;
-            *buffer.get_unchecked_mut((x + padding) as usize) = sum;
-        }
+    for x in 0..width {
+        let p = unsafe { image.unsafe_get_pixel(x, row) };
+        let start = (x + padding) as usize * num_channels;
+        accumulate_running_sum(&mut sum, &p, &mut buffer[start..start + num_channels]);
+    }
 
-        for x in 0..padding {
-            sum += image.unsafe_get_pixel(width - 1, row)[0] as u32;
-            *buffer.get_unchecked_mut((x + width + padding) as usize) = sum;
-        }
+    for x in 0..padding {
+        let x_unchecked = (x + width) as i64;
+        let p = pixel_with_border(image, x_unchecked, row as i64, border);
+        let start = (x + width + padding) as usize * num_channels;
+        accumulate_running_sum(&mut sum, &p, &mut buffer[start..start + num_channels]);
     }
 }
 
 /// Computes the running sum of one column of image, padded
-/// at the top and bottom. The padding is by continuity.
+/// at the top and bottom. Padding values are determined by `border`.
 /// Takes a reference to buffer so that this can be reused
 /// for all columns in an image.
-// TODO: faster, more formats
-pub fn column_running_sum(image: &GrayImage, column: u32, buffer: &mut [u32], padding: u32) {
-
+///
+/// Sums are computed independently for each channel, and stored
+/// interleaved in buffer, i.e. the running sum of channel c at
+/// padded position i is stored in `buffer[i * num_channels + c]`.
+pub fn column_running_sum<P, K>(image: &Image<P>, column: u32, buffer: &mut [K], padding: u32,
+                                border: BorderMode<P>)
+    where P: Pixel + 'static,
+          <P as Pixel>::Subpixel: ValueInto<K>,
+          K: Num + Copy
+{
     let (width, height) = image.dimensions();
-    assert!(buffer.len() >= (height + 2 * padding) as usize,
-        format!("Buffer length {} is less than {} + 2 * {}", buffer.len(), height, padding));
+    let num_channels = P::channel_count() as usize;
+    assert!(buffer.len() >= (height + 2 * padding) as usize * num_channels,
+        format!("Buffer length {} is less than ({} + 2 * {}) * {}",
+                buffer.len(), height, padding, num_channels));
     assert!(column < width, format!("column out of bounds: {} >= {}", column, width));
 
-    unsafe {
-        let mut sum = 0;
-        for y in 0..padding {
-            sum += image.unsafe_get_pixel(column, 0)[0] as u32;
-            *buffer.get_unchecked_mut(y as usize) = sum;
-        }
+    let mut sum = vec![K::zero(); num_channels];
+    for y in 0..padding {
+        let y_unchecked = y as i64 - padding as i64;
+        let p = pixel_with_border(image, column as i64, y_unchecked, border);
+        let start = y as usize * num_channels;
+        accumulate_running_sum(&mut sum, &p, &mut buffer[start..start + num_channels]);
+    }
 
-        for y in 0..height {
-            sum += image.unsafe_get_pixel(column, y)[0] as u32;
-            *buffer.get_unchecked_mut((y + padding) as usize) = sum;
-        }
+    for y in 0..height {
+        let p = unsafe { image.unsafe_get_pixel(column, y) };
+        let start = (y + padding) as usize * num_channels;
+        accumulate_running_sum(&mut sum, &p, &mut buffer[start..start + num_channels]);
+    }
 
-        for y in 0..padding {
-            sum += image.unsafe_get_pixel(column, height - 1)[0] as u32;
-            *buffer.get_unchecked_mut((y + height + padding) as usize) = sum;
-        }
+    for y in 0..padding {
+        let y_unchecked = (y + height) as i64;
+        let p = pixel_with_border(image, column as i64, y_unchecked, border);
+        let start = (y + height + padding) as usize * num_channels;
+        accumulate_running_sum(&mut sum, &p, &mut buffer[start..start + num_channels]);
+    }
+}
+
+/// Adds the channels of pixel to sum, and writes the new sums to out.
+fn accumulate_running_sum<P, K>(sum: &mut [K], pixel: &P, out: &mut [K])
+    where P: Pixel,
+          <P as Pixel>::Subpixel: ValueInto<K>,
+          K: Num + Copy
+{
+    for ((s, o), &c) in sum.iter_mut().zip(out.iter_mut()).zip(pixel.channels().iter()) {
+        *s = *s + cast(c);
+        *o = *s;
     }
 }
 
@@ -140,7 +350,9 @@ mod test {
         GenericImage,
         GrayImage,
         ImageBuffer,
-        Luma
+        Luma,
+        Rgb,
+        RgbImage
     };
     use quickcheck::{
         quickcheck,
@@ -157,17 +369,17 @@ mod test {
             1, 2,
             3, 4]).unwrap();
 
-        let integral = ::integralimage::integral_image(&image);
-
-        assert_eq!(sum_image_pixels(&integral, 0, 0, 0, 0), 1);
-        assert_eq!(sum_image_pixels(&integral, 0, 0, 1, 0), 3);
-        assert_eq!(sum_image_pixels(&integral, 0, 0, 0, 1), 4);
-        assert_eq!(sum_image_pixels(&integral, 0, 0, 1, 1), 10);
-        assert_eq!(sum_image_pixels(&integral, 1, 0, 1, 0), 2);
-        assert_eq!(sum_image_pixels(&integral, 1, 0, 1, 1), 6);
-        assert_eq!(sum_image_pixels(&integral, 0, 1, 0, 1), 3);
-        assert_eq!(sum_image_pixels(&integral, 0, 1, 1, 1), 7);
-        assert_eq!(sum_image_pixels(&integral, 1, 1, 1, 1), 4);
+        let integral = integral_image::<_, u32>(&image);
+
+        assert_eq!(sum_image_pixels(&integral, 0, 0, 0, 0)[0], 1);
+        assert_eq!(sum_image_pixels(&integral, 0, 0, 1, 0)[0], 3);
+        assert_eq!(sum_image_pixels(&integral, 0, 0, 0, 1)[0], 4);
+        assert_eq!(sum_image_pixels(&integral, 0, 0, 1, 1)[0], 10);
+        assert_eq!(sum_image_pixels(&integral, 1, 0, 1, 0)[0], 2);
+        assert_eq!(sum_image_pixels(&integral, 1, 0, 1, 1)[0], 6);
+        assert_eq!(sum_image_pixels(&integral, 0, 1, 0, 1)[0], 3);
+        assert_eq!(sum_image_pixels(&integral, 0, 1, 1, 1)[0], 7);
+        assert_eq!(sum_image_pixels(&integral, 1, 1, 1, 1)[0], 4);
     }
 
     #[test]
@@ -182,14 +394,79 @@ mod test {
             0,  1,  3,  6,
             0,  5, 12, 21]).unwrap();
 
-        assert_pixels_eq!(integral_image(&image), expected);
+        assert_pixels_eq!(integral_image::<_, u32>(&image), expected);
+    }
+
+    #[test]
+    fn test_integral_image_rgb() {
+        let image: RgbImage = ImageBuffer::from_raw(2, 2, vec![
+            1, 10, 100,
+            2, 20, 200,
+            3, 30, 255,
+            4, 40, 255]).unwrap();
+
+        let integral = integral_image::<_, u64>(&image);
+
+        assert_eq!(*integral.get_pixel(2, 1), Rgb([3, 30, 300]));
+        assert_eq!(*integral.get_pixel(1, 2), Rgb([4, 40, 355]));
+        assert_eq!(*integral.get_pixel(2, 2), Rgb([10, 100, 810]));
+        assert_eq!(sum_image_pixels(&integral, 1, 0, 1, 1), Rgb([6, 60, 455]));
+    }
+
+    #[test]
+    fn test_integral_image_f64_luma_f32() {
+        let image: Image<Luma<f32>> = ImageBuffer::from_raw(2, 1, vec![
+            0.5, 1.25]).unwrap();
+
+        let integral = integral_image::<_, f64>(&image);
+
+        assert_eq!(sum_image_pixels(&integral, 0, 0, 1, 0), Luma([1.75]));
+    }
+
+    #[test]
+    fn test_integral_image_u64_does_not_overflow() {
+        // Sum of all pixels is 16 * u32::MAX
+        let image: Image<Luma<u32>> = ImageBuffer::from_pixel(4, 4, Luma([::std::u32::MAX]));
+
+        let integral = integral_image::<_, u64>(&image);
+
+        assert_eq!(sum_image_pixels(&integral, 0, 0, 3, 3), Luma([68719476720u64]));
+    }
+
+    #[test]
+    fn test_integral_squared_image() {
+        let image: GrayImage = ImageBuffer::from_raw(3, 2, vec![
+            1, 2, 3,
+            4, 5, 6]).unwrap();
+
+        let expected: Image<Luma<u32>> = ImageBuffer::from_raw(4, 3, vec![
+            0,  0,  0,  0,
+            0,  1,  5, 14,
+            0, 17, 46, 91]).unwrap();
+
+        assert_pixels_eq!(integral_squared_image::<_, u32>(&image), expected);
+    }
+
+    #[test]
+    fn test_variance() {
+        let image: RgbImage = ImageBuffer::from_raw(2, 2, vec![
+            1, 5, 0,
+            3, 5, 0,
+            1, 5, 0,
+            3, 5, 8]).unwrap();
+
+        let integral = integral_image::<_, u64>(&image);
+        let integral_squared = integral_squared_image::<_, u64>(&image);
+
+        assert_eq!(variance(&integral, &integral_squared, 0, 0, 1, 1), Rgb([1.0, 0.0, 12.0]));
+        assert_eq!(variance(&integral, &integral_squared, 1, 0, 1, 1), Rgb([0.0, 0.0, 16.0]));
     }
 
     #[bench]
     fn bench_integral_image(b: &mut test::Bencher) {
         let image = gray_bench_image(500, 500);
         b.iter(|| {
-            let integral = integral_image(&image);
+            let integral = integral_image::<_, u32>(&image);
             test::black_box(integral);
             });
     }
@@ -223,7 +500,55 @@ mod test {
     fn test_integral_image_matches_reference_implementation() {
         fn prop(image: GrayTestImage) -> TestResult {
             let expected = integral_image_ref(&image.0);
-            let actual = integral_image(&image.0);
+            let actual = integral_image::<_, u32>(&image.0);
+            match pixel_diff_summary(&actual, &expected) {
+                None => TestResult::passed(),
+                Some(err) => TestResult::error(err)
+            }
+        }
+        quickcheck(prop as fn(GrayTestImage) -> TestResult);
+    }
+
+    /// Simple implementation of tilted_integral_image to validate faster versions against.
+    fn tilted_integral_image_ref(image: &GrayImage) -> Image<Luma<u32>> {
+        let (width, height) = image.dimensions();
+        let mut out = ImageBuffer::new(width + 2, height + 1);
+
+        for y in 0..height as i32 + 1 {
+            for x in 0..width as i32 + 2 {
+                let mut sum = 0u32;
+                for (ix, iy, p) in image.enumerate_pixels() {
+                    let (ix, iy) = (ix as i32, iy as i32);
+                    if iy < y && (ix - x + 1).abs() < y - iy {
+                        sum += p[0] as u32;
+                    }
+                }
+                out.put_pixel(x as u32, y as u32, Luma([sum]));
+            }
+        }
+
+        out
+    }
+
+    #[test]
+    fn test_tilted_integral_image() {
+        let image: GrayImage = ImageBuffer::from_raw(3, 2, vec![
+            1, 2, 3,
+            4, 5, 6]).unwrap();
+
+        let expected: Image<Luma<u32>> = ImageBuffer::from_raw(5, 3, vec![
+            0, 0, 0, 0, 0,
+            0, 1, 2, 3, 0,
+            1, 7, 11, 11, 3]).unwrap();
+
+        assert_pixels_eq!(tilted_integral_image::<_, u32>(&image), expected);
+    }
+
+    #[test]
+    fn test_tilted_integral_image_matches_reference_implementation() {
+        fn prop(image: GrayTestImage) -> TestResult {
+            let expected = tilted_integral_image_ref(&image.0);
+            let actual = tilted_integral_image::<_, u32>(&image.0);
             match pixel_diff_summary(&actual, &expected) {
                 None => TestResult::passed(),
                 Some(err) => TestResult::error(err)
@@ -232,6 +557,49 @@ mod test {
         quickcheck(prop as fn(GrayTestImage) -> TestResult);
     }
 
+    #[test]
+    fn test_tilted_integral_image_rectangle_sums() {
+        let image = gray_bench_image(9, 8);
+        let tilted = tilted_integral_image::<_, u32>(&image);
+        let t = |x: u32, y: u32| tilted.get_pixel(x, y)[0] as i64;
+
+        for y in 0..8 {
+            for x in 0..9 {
+                for w in 1..5 {
+                    for h in 1..5 {
+                        // Skip rectangles that extend outside the image
+                        if x + 1 < h || x + w > 9 || y + w + h > 8 {
+                            continue;
+                        }
+                        let actual = t(x + w - h + 1, y + w + h) - t(x + 1 - h, y + h)
+                            - t(x + w + 1, y + w) + t(x + 1, y);
+
+                        // Pixel (x + i - j, y + i + j) is i steps down and to the
+                        // right and j steps down and to the left of the top corner.
+                        // The rectangle contains this pixel and the one below it.
+                        let mut expected = 0i64;
+                        for i in 0..w {
+                            for j in 0..h {
+                                expected += image.get_pixel(x + i - j, y + i + j)[0] as i64;
+                                expected += image.get_pixel(x + i - j, y + i + j + 1)[0] as i64;
+                            }
+                        }
+                        assert_eq!(actual, expected);
+                    }
+                }
+            }
+        }
+    }
+
+    #[bench]
+    fn bench_tilted_integral_image(b: &mut test::Bencher) {
+        let image = gray_bench_image(500, 500);
+        b.iter(|| {
+            let integral = tilted_integral_image::<_, u32>(&image);
+            test::black_box(integral);
+            });
+    }
+
     #[test]
     fn test_row_running_sum() {
         let image: GrayImage = ImageBuffer::from_raw(3, 2, vec![
@@ -241,7 +609,25 @@ mod test {
         let expected = [1, 2, 4, 7, 10];
 
         let mut buffer = [0; 5];
-        row_running_sum(&image, 0, &mut buffer, 1);
+        row_running_sum(&image, 0, &mut buffer, 1, BorderMode::Replicate);
+
+        assert_eq!(buffer, expected);
+    }
+
+    #[test]
+    fn test_row_running_sum_rgb() {
+        let image: RgbImage = ImageBuffer::from_raw(2, 1, vec![
+            1, 2, 3,
+            4, 5, 6]).unwrap();
+
+        let expected = [
+            0, 0, 0,
+            1, 2, 3,
+            5, 7, 9,
+            5, 7, 9];
+
+        let mut buffer = [0; 12];
+        row_running_sum(&image, 0, &mut buffer, 1, BorderMode::Constant(Rgb([0, 0, 0])));
 
         assert_eq!(buffer, expected);
     }
@@ -251,7 +637,7 @@ mod test {
         let image = gray_bench_image(1000, 1);
         let mut buffer = [0; 1010];
         b.iter(|| {
-            row_running_sum(&image, 0, &mut buffer, 5);
+            row_running_sum(&image, 0, &mut buffer, 5, BorderMode::Replicate);
             });
     }
 
@@ -265,7 +651,7 @@ mod test {
         let expected = [1, 2, 4, 7, 10];
 
         let mut buffer = [0; 5];
-        column_running_sum(&image, 0, &mut buffer, 1);
+        column_running_sum(&image, 0, &mut buffer, 1, BorderMode::Replicate);
 
         assert_eq!(buffer, expected);
     }
@@ -275,7 +661,7 @@ mod test {
         let image = gray_bench_image(100, 1000);
         let mut buffer = [0; 1010];
         b.iter(|| {
-            column_running_sum(&image, 0, &mut buffer, 5);
+            column_running_sum(&image, 0, &mut buffer, 5, BorderMode::Replicate);
             });
     }
 }
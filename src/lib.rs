@@ -18,23 +18,32 @@ extern crate rayon;
 #[macro_use]
 pub mod utils;
 pub mod affine;
+pub mod blobs;
+pub mod contours;
 pub mod contrast;
 pub mod corners;
 pub mod definitions;
+pub mod distance_transform;
 pub mod drawing;
 pub mod edges;
 pub mod filter;
+pub mod geometry;
 pub mod gradients;
 pub mod haar;
 pub mod hog;
+pub mod hough;
 pub mod stats;
 pub mod integralimage;
 pub mod localbinarypatterns;
 pub mod map;
 pub mod math;
+pub mod morphology;
 pub mod multiarray;
 pub mod noise;
+pub mod orb;
 pub mod pixelops;
+pub mod pyramid;
+pub mod ransac;
 pub mod rect;
 pub mod regionlabelling;
 pub mod suppress;
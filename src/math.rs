@@ -1,6 +1,7 @@
 //! Assorted mathematical helper functions.
 
 use conv::ValueInto;
+use definitions::Clamp;
 
 /// L2 norm of a vector.
 pub fn l2_norm(xs: &[f32]) -> f32 {
@@ -14,3 +15,18 @@ pub fn cast<T, U>(x: T) -> U where T: ValueInto<U> {
         Err(_) => panic!("Failed to convert"),
     }
 }
+
+/// Converts `x` to a subpixel type using `Clamp`, rounding to the nearest
+/// value rather than truncating when the subpixel type is an integer type.
+/// Values of float subpixel types are unchanged, other than being clamped.
+pub(crate) fn round_clamp<S>(x: f32) -> S
+    where S: Clamp<f32> + ValueInto<f32> + Copy
+{
+    // Clamp is exact for float types, so this only rounds integer types.
+    let truncated = S::clamp(x);
+    if x - cast::<_, f32>(truncated) >= 0.5 {
+        S::clamp(x.round())
+    } else {
+        truncated
+    }
+}
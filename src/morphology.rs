@@ -0,0 +1,446 @@
+//! Functions for computing [morphological operators] on 8bpp grayscale images.
+//!
+//! The `dilate`, `erode`, `open` and `close` functions and their in-place variants treat
+//! their input as a binary image: pixels with non-zero intensity are foreground and all
+//! others are background. They use a structuring element given by a ball of radius `k`
+//! under the provided `distance_transform::Norm`, and are computed via a distance
+//! transform, so their running time does not depend on `k`.
+//!
+//! The `*_with_element` functions accept an arbitrary `StructuringElement`, and compute
+//! grayscale dilations and erosions (i.e. maxima and minima over the element). For binary
+//! images these coincide with the binary operators.
+//!
+//! [morphological operators]: https://en.wikipedia.org/wiki/Mathematical_morphology
+
+use image::{
+    GenericImage,
+    GrayImage,
+    ImageBuffer,
+    Luma
+};
+
+use definitions::{
+    HasBlack,
+    HasWhite
+};
+
+use distance_transform::{
+    chamfer_distances,
+    DistanceFrom
+};
+
+pub use distance_transform::Norm;
+
+use std::cmp;
+
+/// Sets all pixels within distance `k` of a foreground pixel to white,
+/// and all other pixels to black.
+pub fn dilate(image: &GrayImage, norm: Norm, k: u8) -> GrayImage {
+    let mut out = image.clone();
+    dilate_mut(&mut out, norm, k);
+    out
+}
+
+/// Sets all pixels within distance `k` of a foreground pixel to white,
+/// and all other pixels to black. See `dilate`.
+pub fn dilate_mut(image: &mut GrayImage, norm: Norm, k: u8) {
+    let distances = chamfer_distances(image, norm, DistanceFrom::Foreground);
+    for (p, d) in image.iter_mut().zip(distances.iter()) {
+        *p = if *d <= k as u32 { 255 } else { 0 };
+    }
+}
+
+/// Sets all pixels within distance `k` of a background pixel to black,
+/// and all other pixels to white. Pixels outside the image are not
+/// treated as background.
+pub fn erode(image: &GrayImage, norm: Norm, k: u8) -> GrayImage {
+    let mut out = image.clone();
+    erode_mut(&mut out, norm, k);
+    out
+}
+
+/// Sets all pixels within distance `k` of a background pixel to black,
+/// and all other pixels to white. See `erode`.
+pub fn erode_mut(image: &mut GrayImage, norm: Norm, k: u8) {
+    let distances = chamfer_distances(image, norm, DistanceFrom::Background);
+    for (p, d) in image.iter_mut().zip(distances.iter()) {
+        *p = if *d <= k as u32 { 0 } else { 255 };
+    }
+}
+
+/// Erosion followed by dilation. Removes foreground regions
+/// which are too small to contain the structuring element.
+pub fn open(image: &GrayImage, norm: Norm, k: u8) -> GrayImage {
+    let mut out = image.clone();
+    open_mut(&mut out, norm, k);
+    out
+}
+
+/// Erosion followed by dilation. See `open`.
+pub fn open_mut(image: &mut GrayImage, norm: Norm, k: u8) {
+    erode_mut(image, norm, k);
+    dilate_mut(image, norm, k);
+}
+
+/// Dilation followed by erosion. Fills holes and gaps in the
+/// foreground which are too small to contain the structuring element.
+pub fn close(image: &GrayImage, norm: Norm, k: u8) -> GrayImage {
+    let mut out = image.clone();
+    close_mut(&mut out, norm, k);
+    out
+}
+
+/// Dilation followed by erosion. See `close`.
+pub fn close_mut(image: &mut GrayImage, norm: Norm, k: u8) {
+    dilate_mut(image, norm, k);
+    erode_mut(image, norm, k);
+}
+
+/// A structuring element, i.e. a set of offsets relative to a center pixel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructuringElement {
+    offsets: Vec<(i32, i32)>
+}
+
+impl StructuringElement {
+    /// Construct a structuring element from a list of (dx, dy) offsets from its center.
+    pub fn from_offsets(offsets: &[(i32, i32)]) -> StructuringElement {
+        let mut offsets = offsets.to_vec();
+        offsets.sort();
+        offsets.dedup();
+        StructuringElement { offsets: offsets }
+    }
+
+    /// Construct a structuring element from an image. The element contains the
+    /// non-zero pixels of the image, relative to the pixel at (`center_x`, `center_y`).
+    pub fn from_image(image: &GrayImage, center_x: u32, center_y: u32) -> StructuringElement {
+        let offsets: Vec<(i32, i32)> = image
+            .enumerate_pixels()
+            .filter(|&(_, _, p)| p[0] > 0)
+            .map(|(x, y, _)| (x as i32 - center_x as i32, y as i32 - center_y as i32))
+            .collect();
+        StructuringElement::from_offsets(&offsets)
+    }
+
+    /// The (2 * `radius` + 1) square centered on the origin, i.e. the LInf ball of this radius.
+    pub fn square(radius: u32) -> StructuringElement {
+        StructuringElement::from_predicate(radius, |dx, dy| cmp::max(dx.abs(), dy.abs()) <= radius as i32)
+    }
+
+    /// The L1 ball of the given radius, centered on the origin.
+    pub fn diamond(radius: u32) -> StructuringElement {
+        StructuringElement::from_predicate(radius, |dx, dy| dx.abs() + dy.abs() <= radius as i32)
+    }
+
+    /// The Euclidean ball of the given radius, centered on the origin.
+    pub fn disk(radius: u32) -> StructuringElement {
+        let r = radius as i32;
+        StructuringElement::from_predicate(radius, |dx, dy| dx * dx + dy * dy <= r * r)
+    }
+
+    /// The offsets contained in this element, in lexicographic order.
+    pub fn offsets(&self) -> &[(i32, i32)] {
+        &self.offsets
+    }
+
+    fn from_predicate<F>(radius: u32, contains: F) -> StructuringElement
+        where F: Fn(i32, i32) -> bool
+    {
+        let r = radius as i32;
+        let mut offsets = vec![];
+        for dx in -r..r + 1 {
+            for dy in -r..r + 1 {
+                if contains(dx, dy) {
+                    offsets.push((dx, dy));
+                }
+            }
+        }
+        StructuringElement { offsets: offsets }
+    }
+}
+
+/// Grayscale dilation: each output pixel is the maximum of the input pixels at
+/// (x - dx, y - dy) for (dx, dy) in `element`. Offsets which fall outside the image
+/// are ignored. Output pixels for which all offsets fall outside the image are black.
+pub fn dilate_with_element(image: &GrayImage, element: &StructuringElement) -> GrayImage {
+    extremum_over_element(image, element, -1, Luma::black(), cmp::max)
+}
+
+/// Grayscale erosion: each output pixel is the minimum of the input pixels at
+/// (x + dx, y + dy) for (dx, dy) in `element`. Offsets which fall outside the image
+/// are ignored. Output pixels for which all offsets fall outside the image are white.
+pub fn erode_with_element(image: &GrayImage, element: &StructuringElement) -> GrayImage {
+    extremum_over_element(image, element, 1, Luma::white(), cmp::min)
+}
+
+/// Erosion followed by dilation, using the given structuring element.
+pub fn open_with_element(image: &GrayImage, element: &StructuringElement) -> GrayImage {
+    dilate_with_element(&erode_with_element(image, element), element)
+}
+
+/// Dilation followed by erosion, using the given structuring element.
+pub fn close_with_element(image: &GrayImage, element: &StructuringElement) -> GrayImage {
+    erode_with_element(&dilate_with_element(image, element), element)
+}
+
+fn extremum_over_element<F>(image: &GrayImage,
+                            element: &StructuringElement,
+                            direction: i32,
+                            initial: Luma<u8>,
+                            select: F)
+                            -> GrayImage
+    where F: Fn(u8, u8) -> u8
+{
+    let (width, height) = image.dimensions();
+    let mut out = ImageBuffer::from_pixel(width, height, initial);
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut acc = initial[0];
+            for &(dx, dy) in element.offsets() {
+                let px = x as i32 + direction * dx;
+                let py = y as i32 + direction * dy;
+                if px < 0 || px >= width as i32 || py < 0 || py >= height as i32 {
+                    continue;
+                }
+                let p = unsafe { image.unsafe_get_pixel(px as u32, py as u32)[0] };
+                acc = select(acc, p);
+            }
+            unsafe { out.unsafe_put_pixel(x, y, Luma([acc])); }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::{
+        GrayImage,
+        ImageBuffer,
+        Luma
+    };
+    use quickcheck::{
+        quickcheck,
+        TestResult
+    };
+    use utils::{
+        GrayTestImage,
+        pixel_diff_summary
+    };
+    use test::{
+        Bencher,
+        black_box
+    };
+
+    fn binarize(image: &GrayImage) -> GrayImage {
+        ImageBuffer::from_fn(image.width(), image.height(), |x, y| {
+            if image.get_pixel(x, y)[0] > 127 { Luma([255u8]) } else { Luma([0u8]) }
+        })
+    }
+
+    #[test]
+    fn test_dilate_l1() {
+        let image: GrayImage = ImageBuffer::from_raw(5, 5, vec![
+            0,   0,   0,   0,   0,
+            0,   0,   0,   0,   0,
+            0,   0, 255,   0,   0,
+            0,   0,   0,   0,   0,
+            0,   0,   0,   0,   0]).unwrap();
+
+        let expected: GrayImage = ImageBuffer::from_raw(5, 5, vec![
+            0,   0,   0,   0,   0,
+            0,   0, 255,   0,   0,
+            0, 255, 255, 255,   0,
+            0,   0, 255,   0,   0,
+            0,   0,   0,   0,   0]).unwrap();
+
+        assert_pixels_eq!(dilate(&image, Norm::L1, 1), expected);
+    }
+
+    #[test]
+    fn test_dilate_linf() {
+        let image: GrayImage = ImageBuffer::from_raw(5, 5, vec![
+            0,   0,   0,   0,   0,
+            0,   0,   0,   0,   0,
+            0,   0, 255,   0,   0,
+            0,   0,   0,   0,   0,
+            0,   0,   0,   0,   0]).unwrap();
+
+        let expected: GrayImage = ImageBuffer::from_raw(5, 5, vec![
+            0,   0,   0,   0,   0,
+            0, 255, 255, 255,   0,
+            0, 255, 255, 255,   0,
+            0, 255, 255, 255,   0,
+            0,   0,   0,   0,   0]).unwrap();
+
+        assert_pixels_eq!(dilate(&image, Norm::LInf, 1), expected);
+    }
+
+    #[test]
+    fn test_dilate_empty_image_stays_empty() {
+        let image = GrayImage::new(4, 3);
+        assert_pixels_eq!(dilate(&image, Norm::L1, 255), image);
+    }
+
+    #[test]
+    fn test_erode_linf() {
+        let image: GrayImage = ImageBuffer::from_raw(5, 5, vec![
+            0,   0,   0,   0,   0,
+            0, 255, 255, 255,   0,
+            0, 255, 255, 255,   0,
+            0, 255, 255, 255, 255,
+            0,   0,   0, 255, 255]).unwrap();
+
+        let expected: GrayImage = ImageBuffer::from_raw(5, 5, vec![
+            0,   0,   0,   0,   0,
+            0,   0,   0,   0,   0,
+            0,   0, 255,   0,   0,
+            0,   0,   0,   0,   0,
+            0,   0,   0,   0, 255]).unwrap();
+
+        assert_pixels_eq!(erode(&image, Norm::LInf, 1), expected);
+    }
+
+    #[test]
+    fn test_open_removes_isolated_pixels() {
+        let image: GrayImage = ImageBuffer::from_raw(6, 5, vec![
+            255,   0,   0,   0,   0,   0,
+              0,   0, 255, 255, 255,   0,
+              0,   0, 255, 255, 255,   0,
+              0,   0, 255, 255, 255,   0,
+              0,   0,   0,   0,   0, 255]).unwrap();
+
+        let expected: GrayImage = ImageBuffer::from_raw(6, 5, vec![
+              0,   0,   0,   0,   0,   0,
+              0,   0, 255, 255, 255,   0,
+              0,   0, 255, 255, 255,   0,
+              0,   0, 255, 255, 255,   0,
+              0,   0,   0,   0,   0,   0]).unwrap();
+
+        assert_pixels_eq!(open(&image, Norm::LInf, 1), expected);
+    }
+
+    #[test]
+    fn test_close_fills_holes() {
+        let image: GrayImage = ImageBuffer::from_raw(7, 7, vec![
+            0,   0,   0,   0,   0,   0,   0,
+            0,   0,   0,   0,   0,   0,   0,
+            0,   0, 255, 255, 255,   0,   0,
+            0,   0, 255,   0, 255,   0,   0,
+            0,   0, 255, 255, 255,   0,   0,
+            0,   0,   0,   0,   0,   0,   0,
+            0,   0,   0,   0,   0,   0,   0]).unwrap();
+
+        let expected: GrayImage = ImageBuffer::from_raw(7, 7, vec![
+            0,   0,   0,   0,   0,   0,   0,
+            0,   0,   0,   0,   0,   0,   0,
+            0,   0, 255, 255, 255,   0,   0,
+            0,   0, 255, 255, 255,   0,   0,
+            0,   0, 255, 255, 255,   0,   0,
+            0,   0,   0,   0,   0,   0,   0,
+            0,   0,   0,   0,   0,   0,   0]).unwrap();
+
+        assert_pixels_eq!(close(&image, Norm::L1, 1), expected);
+    }
+
+    #[test]
+    fn test_element_from_image() {
+        let mask: GrayImage = ImageBuffer::from_raw(3, 2, vec![
+            1, 0, 1,
+            0, 1, 0]).unwrap();
+
+        let element = StructuringElement::from_image(&mask, 1, 1);
+        assert_eq!(element.offsets(), &[(-1, -1), (0, 0), (1, -1)]);
+    }
+
+    #[test]
+    fn test_dilate_with_asymmetric_element() {
+        let image: GrayImage = ImageBuffer::from_raw(4, 1, vec![
+            0, 7, 0, 0]).unwrap();
+
+        let element = StructuringElement::from_offsets(&[(0, 0), (2, 0)]);
+
+        let expected: GrayImage = ImageBuffer::from_raw(4, 1, vec![
+            0, 7, 0, 7]).unwrap();
+
+        assert_pixels_eq!(dilate_with_element(&image, &element), expected);
+    }
+
+    #[test]
+    fn test_erode_with_element_is_grayscale_minimum() {
+        let image: GrayImage = ImageBuffer::from_raw(3, 3, vec![
+            9, 8, 7,
+            6, 5, 4,
+            3, 2, 1]).unwrap();
+
+        let expected: GrayImage = ImageBuffer::from_raw(3, 3, vec![
+            5, 4, 4,
+            2, 1, 1,
+            2, 1, 1]).unwrap();
+
+        assert_pixels_eq!(erode_with_element(&image, &StructuringElement::square(1)), expected);
+    }
+
+    #[test]
+    fn test_dilate_matches_element_implementation() {
+        fn prop(image: GrayTestImage, k: u8) -> TestResult {
+            let k = k % 4;
+            let image = binarize(&image.0);
+            for &(norm, ref element) in [(Norm::L1, StructuringElement::diamond(k as u32)),
+                                     (Norm::LInf, StructuringElement::square(k as u32))].iter() {
+                let expected = dilate_with_element(&image, element);
+                let actual = dilate(&image, norm, k);
+                if let Some(err) = pixel_diff_summary(&actual, &expected) {
+                    return TestResult::error(err);
+                }
+            }
+            TestResult::passed()
+        }
+        quickcheck(prop as fn(GrayTestImage, u8) -> TestResult);
+    }
+
+    #[test]
+    fn test_erode_matches_element_implementation() {
+        fn prop(image: GrayTestImage, k: u8) -> TestResult {
+            let k = k % 4;
+            let image = binarize(&image.0);
+            for &(norm, ref element) in [(Norm::L1, StructuringElement::diamond(k as u32)),
+                                     (Norm::LInf, StructuringElement::square(k as u32))].iter() {
+                let expected = erode_with_element(&image, element);
+                let actual = erode(&image, norm, k);
+                if let Some(err) = pixel_diff_summary(&actual, &expected) {
+                    return TestResult::error(err);
+                }
+            }
+            TestResult::passed()
+        }
+        quickcheck(prop as fn(GrayTestImage, u8) -> TestResult);
+    }
+
+    fn morphology_bench_image(width: u32, height: u32) -> GrayImage {
+        ImageBuffer::from_fn(width, height, |x, y| {
+            if (x / 10 + y / 7) % 3 == 0 { Luma([255u8]) } else { Luma([0u8]) }
+        })
+    }
+
+    #[bench]
+    fn bench_dilate_l1_radius_5(b: &mut Bencher) {
+        let image = morphology_bench_image(200, 200);
+        b.iter(|| {
+            let dilated = dilate(&image, Norm::L1, 5);
+            black_box(dilated);
+        });
+    }
+
+    #[bench]
+    fn bench_dilate_with_element_disk_radius_5(b: &mut Bencher) {
+        let image = morphology_bench_image(200, 200);
+        let element = StructuringElement::disk(5);
+        b.iter(|| {
+            let dilated = dilate_with_element(&image, &element);
+            black_box(dilated);
+        });
+    }
+}
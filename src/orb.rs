@@ -0,0 +1,558 @@
+//! Functions for computing ORB (Oriented FAST and Rotated BRIEF) binary
+//! feature descriptors, and for matching binary descriptors between images.
+//!
+//! See [ORB: an efficient alternative to SIFT or SURF](http://www.willowgarage.com/sites/default/files/orb_final.pdf).
+
+use image::{
+    GenericImage,
+    GrayImage
+};
+
+use rand::{
+    SeedableRng,
+    StdRng
+};
+
+use rand::distributions::{
+    IndependentSample,
+    Normal
+};
+
+use corners::{
+    corners_fast9,
+    fast_pyramid,
+    Corner
+};
+
+use filter::gaussian_blur_f32;
+use suppress::local_maxima;
+
+use std::cmp::Ordering;
+
+/// Radius of the circular patch used to compute keypoint orientations
+/// and binary descriptors.
+const PATCH_RADIUS: i32 = 15;
+
+/// Number of point pairs compared when computing a descriptor.
+const DESCRIPTOR_BITS: usize = 256;
+
+/// Standard deviation of the Gaussian blur applied before sampling descriptor pairs.
+const SMOOTHING_SIGMA: f32 = 2.0;
+
+/// Seed for the random number generator used to create the descriptor sampling pattern.
+const PATTERN_SEED: usize = 1;
+
+/// A 256-bit binary descriptor.
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+pub struct BinaryDescriptor {
+    /// The descriptor bits. Bit `i` is stored in bit `i % 64` of `bits[i / 64]`.
+    pub bits: [u64; 4]
+}
+
+impl BinaryDescriptor {
+    /// Returns the value of the `i`th bit of this descriptor.
+    pub fn bit(&self, i: usize) -> bool {
+        (self.bits[i / 64] >> (i % 64)) & 1 == 1
+    }
+
+    /// The number of bits that differ between two descriptors.
+    pub fn hamming_distance(&self, other: &BinaryDescriptor) -> u32 {
+        self.bits.iter()
+            .zip(other.bits.iter())
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum()
+    }
+}
+
+/// An oriented keypoint detected in an image pyramid.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct OrbKeypoint {
+    /// x-coordinate of the keypoint in the original image.
+    pub x: f32,
+    /// y-coordinate of the keypoint in the original image.
+    pub y: f32,
+    /// Score of the keypoint.
+    pub score: f32,
+    /// The pyramid level in which the keypoint was detected. Level 0 is the original image.
+    pub level: u32,
+    /// The ratio between the size of the original image and the size
+    /// of the level in which the keypoint was detected.
+    pub scale: f32,
+    /// Orientation of the keypoint in radians, measured clockwise
+    /// from the positive x-axis (as image y-coordinates increase downwards).
+    pub angle: f32
+}
+
+/// A keypoint and its descriptor.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct OrbFeature {
+    /// Location, scale and orientation of the feature.
+    pub keypoint: OrbKeypoint,
+    /// Binary descriptor of the patch around the keypoint.
+    pub descriptor: BinaryDescriptor
+}
+
+/// Options for ORB feature detection.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct OrbOptions {
+    /// Maximum number of pyramid levels to search for keypoints.
+    pub num_levels: u32,
+    /// Ratio between the sizes of consecutive pyramid levels. Must be greater than 1.
+    pub scale_factor: f32,
+    /// Threshold used by the FAST-9 corner detector at each level.
+    pub fast_threshold: u8,
+    /// The maximum number of features to return. The features with
+    /// the highest scores are kept.
+    pub max_features: usize
+}
+
+impl OrbOptions {
+    /// Options returning at most `max_features` features, searching 8 levels
+    /// with a scale factor of 1.2 and using a FAST threshold of 20.
+    pub fn new(max_features: usize) -> OrbOptions {
+        OrbOptions {
+            num_levels: 8,
+            scale_factor: 1.2,
+            fast_threshold: 20,
+            max_features: max_features
+        }
+    }
+}
+
+/// Detects ORB features: FAST-9 corners found at multiple scales, each
+/// with an intensity centroid orientation and a rotated BRIEF descriptor.
+///
+/// Corners at each pyramid level are suppressed if they do not have the
+/// greatest score in the 3x3 block centred on them. Corners too close to the
+/// edge of their level to compute a descriptor are discarded. Features are
+/// returned in descending order of score.
+pub fn orb(image: &GrayImage, options: OrbOptions) -> Vec<OrbFeature> {
+    let pattern = sampling_pattern();
+    let mut features = vec![];
+
+    for (level, (scale, level_image)) in fast_pyramid(image, options.num_levels, options.scale_factor)
+            .iter()
+            .enumerate() {
+        let corners = local_maxima(&corners_fast9(level_image, options.fast_threshold), 1);
+        let smoothed = gaussian_blur_f32(level_image, SMOOTHING_SIGMA);
+
+        for corner in corners {
+            if let Some((angle, descriptor)) = describe(level_image, &smoothed, &pattern, &corner) {
+                features.push(OrbFeature {
+                    keypoint: OrbKeypoint {
+                        x: corner.x as f32 * scale,
+                        y: corner.y as f32 * scale,
+                        score: corner.score,
+                        level: level as u32,
+                        scale: *scale,
+                        angle: angle
+                    },
+                    descriptor: descriptor
+                });
+            }
+        }
+    }
+
+    features.sort_by(|a, b| {
+        b.keypoint.score.partial_cmp(&a.keypoint.score).unwrap_or(Ordering::Equal)
+    });
+    features.truncate(options.max_features);
+    features
+}
+
+/// Computes oriented BRIEF descriptors for corners detected in `image`,
+/// e.g. by `corners::corners_fast9`. Corners too close to the edge of the
+/// image to compute a descriptor are discarded, so the output may be shorter
+/// than the input.
+pub fn describe_corners(image: &GrayImage, corners: &[Corner]) -> Vec<OrbFeature> {
+    let pattern = sampling_pattern();
+    let smoothed = gaussian_blur_f32(image, SMOOTHING_SIGMA);
+
+    corners.iter()
+        .filter_map(|corner| {
+            describe(image, &smoothed, &pattern, corner).map(|(angle, descriptor)| {
+                OrbFeature {
+                    keypoint: OrbKeypoint {
+                        x: corner.x as f32,
+                        y: corner.y as f32,
+                        score: corner.score,
+                        level: 0,
+                        scale: 1.0,
+                        angle: angle
+                    },
+                    descriptor: descriptor
+                }
+            })
+        })
+        .collect()
+}
+
+/// Computes the orientation of the patch centred at (x, y), defined as the
+/// angle from the patch centre to its intensity centroid. Only pixels within
+/// distance `radius` of the centre are considered, and pixels outside the image
+/// are ignored.
+///
+/// Returns an angle in radians in the range [-π, π], measured clockwise from the
+/// positive x-axis (as image y-coordinates increase downwards).
+pub fn intensity_centroid_angle(image: &GrayImage, x: u32, y: u32, radius: u32) -> f32 {
+    let (width, height) = image.dimensions();
+    let r = radius as i32;
+    let mut m01 = 0f32;
+    let mut m10 = 0f32;
+
+    for dy in -r..r + 1 {
+        let py = y as i32 + dy;
+        if py < 0 || py >= height as i32 {
+            continue;
+        }
+        for dx in -r..r + 1 {
+            let px = x as i32 + dx;
+            if px < 0 || px >= width as i32 || dx * dx + dy * dy > r * r {
+                continue;
+            }
+            let intensity = unsafe { image.unsafe_get_pixel(px as u32, py as u32)[0] } as f32;
+            m10 += dx as f32 * intensity;
+            m01 += dy as f32 * intensity;
+        }
+    }
+
+    m01.atan2(m10)
+}
+
+/// Returns the orientation and descriptor of the patch around `corner`,
+/// or None if the patch does not lie entirely within the image.
+fn describe(image: &GrayImage,
+            smoothed: &GrayImage,
+            pattern: &[[(f32, f32); 2]],
+            corner: &Corner) -> Option<(f32, BinaryDescriptor)> {
+    let (width, height) = image.dimensions();
+    let (x, y) = (corner.x as i32, corner.y as i32);
+    if x < PATCH_RADIUS || y < PATCH_RADIUS
+        || x + PATCH_RADIUS >= width as i32 || y + PATCH_RADIUS >= height as i32 {
+        return None;
+    }
+
+    let angle = intensity_centroid_angle(image, corner.x, corner.y, PATCH_RADIUS as u32);
+    let (sin, cos) = angle.sin_cos();
+
+    // Rotating a point within the patch circle and rounding to the
+    // nearest pixel always yields a point within the patch square.
+    let sample = |p: (f32, f32)| -> u8 {
+        let px = x + (cos * p.0 - sin * p.1).round() as i32;
+        let py = y + (sin * p.0 + cos * p.1).round() as i32;
+        unsafe { smoothed.unsafe_get_pixel(px as u32, py as u32)[0] }
+    };
+
+    let mut bits = [0u64; 4];
+    for (i, pair) in pattern.iter().enumerate() {
+        if sample(pair[0]) < sample(pair[1]) {
+            bits[i / 64] |= 1 << (i % 64);
+        }
+    }
+
+    Some((angle, BinaryDescriptor { bits: bits }))
+}
+
+/// The pairs of points compared to compute a descriptor, as offsets from the
+/// patch centre. Points are drawn from an isotropic Gaussian with standard
+/// deviation one fifth of the patch size, and moved onto the patch circle if they
+/// fall outside it. A fixed seed is used so that descriptors are comparable
+/// between calls.
+fn sampling_pattern() -> Vec<[(f32, f32); 2]> {
+    let seed_array: &[_] = &[PATTERN_SEED];
+    let mut rng: StdRng = SeedableRng::from_seed(seed_array);
+    let normal = Normal::new(0.0, (2 * PATCH_RADIUS + 1) as f64 / 5.0);
+    let radius = PATCH_RADIUS as f32;
+
+    let mut point = || {
+        let x = normal.ind_sample(&mut rng) as f32;
+        let y = normal.ind_sample(&mut rng) as f32;
+        let norm = (x * x + y * y).sqrt();
+        if norm > radius {
+            (x * radius / norm, y * radius / norm)
+        } else {
+            (x, y)
+        }
+    };
+
+    (0..DESCRIPTOR_BITS).map(|_| [point(), point()]).collect()
+}
+
+/// A match between two descriptors.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct Match {
+    /// Index of the matched descriptor in the first set.
+    pub first: usize,
+    /// Index of the matched descriptor in the second set.
+    pub second: usize,
+    /// Hamming distance between the matched descriptors.
+    pub distance: u32
+}
+
+/// Options for brute-force descriptor matching.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct MatchOptions {
+    /// A match is accepted only if its distance is at most `ratio_threshold` times
+    /// the distance to the second-best candidate. A value of 1.0 disables the ratio test.
+    pub ratio_threshold: f32,
+    /// If true, a match is accepted only if each descriptor is the other's best match.
+    pub cross_check: bool
+}
+
+impl MatchOptions {
+    /// Options with the given ratio threshold and cross-check setting.
+    pub fn new(ratio_threshold: f32, cross_check: bool) -> MatchOptions {
+        MatchOptions {
+            ratio_threshold: ratio_threshold,
+            cross_check: cross_check
+        }
+    }
+}
+
+/// Finds the best match in `second` for each descriptor in `first`, by Hamming distance.
+/// Matches failing the ratio test or cross-check are discarded. Ties are broken in favour
+/// of the lowest index. The returned matches are ordered by index in `first`.
+pub fn match_descriptors(first: &[BinaryDescriptor],
+                         second: &[BinaryDescriptor],
+                         options: MatchOptions) -> Vec<Match> {
+    let mut matches = vec![];
+
+    for (i, d) in first.iter().enumerate() {
+        let (j, best, second_best) = match two_nearest(d, second) {
+            Some(n) => n,
+            None => continue
+        };
+        if let Some(s) = second_best {
+            if best as f32 > options.ratio_threshold * s as f32 {
+                continue;
+            }
+        }
+        if options.cross_check {
+            match two_nearest(&second[j], first) {
+                Some((k, _, _)) if k == i => {},
+                _ => continue
+            }
+        }
+        matches.push(Match { first: i, second: j, distance: best });
+    }
+
+    matches
+}
+
+/// Returns the index and distance of the nearest candidate to `query`, and the
+/// distance to the second nearest candidate if there is more than one candidate.
+fn two_nearest(query: &BinaryDescriptor, candidates: &[BinaryDescriptor])
+        -> Option<(usize, u32, Option<u32>)> {
+    let mut best: Option<(usize, u32)> = None;
+    let mut second_best: Option<u32> = None;
+
+    for (j, c) in candidates.iter().enumerate() {
+        let distance = query.hamming_distance(c);
+        match best {
+            Some((_, b)) if distance >= b => {
+                if second_best.map_or(true, |s| distance < s) {
+                    second_best = Some(distance);
+                }
+            },
+            _ => {
+                second_best = best.map(|(_, b)| b);
+                best = Some((j, distance));
+            }
+        }
+    }
+
+    best.map(|(j, b)| (j, b, second_best))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        describe_corners,
+        intensity_centroid_angle,
+        match_descriptors,
+        orb,
+        BinaryDescriptor,
+        Match,
+        MatchOptions,
+        OrbOptions
+    };
+    use affine::translate;
+    use corners::{
+        corners_fast9,
+        Corner
+    };
+    use filter::gaussian_blur_f32;
+    use image::{
+        GrayImage,
+        Luma
+    };
+    use image::imageops::rotate90;
+    use noise::gaussian_noise;
+    use test::{
+        Bencher,
+        black_box
+    };
+    use std::f32;
+
+    fn textured_image(width: u32, height: u32) -> GrayImage {
+        let flat = GrayImage::from_pixel(width, height, Luma([128u8]));
+        gaussian_blur_f32(&gaussian_noise(&flat, 0.0, 60.0, 7), 1.5)
+    }
+
+    fn descriptor(bits: [u64; 4]) -> BinaryDescriptor {
+        BinaryDescriptor { bits: bits }
+    }
+
+    #[test]
+    fn test_hamming_distance() {
+        let a = descriptor([0, 0, 0, 0]);
+        let b = descriptor([0b1011, 0, 1 << 63, u64::max_value()]);
+        assert_eq!(a.hamming_distance(&a), 0);
+        assert_eq!(a.hamming_distance(&b), 3 + 1 + 64);
+        assert_eq!(b.hamming_distance(&a), 3 + 1 + 64);
+        assert!(b.bit(0) && b.bit(1) && !b.bit(2) && b.bit(3));
+        assert!(b.bit(191) && !b.bit(190) && b.bit(192));
+    }
+
+    #[test]
+    fn test_intensity_centroid_angle() {
+        let right = GrayImage::from_fn(41, 41, |x, _| Luma([if x > 20 { 255u8 } else { 0u8 }]));
+        let below = GrayImage::from_fn(41, 41, |_, y| Luma([if y > 20 { 255u8 } else { 0u8 }]));
+        let left = GrayImage::from_fn(41, 41, |x, _| Luma([if x < 20 { 255u8 } else { 0u8 }]));
+
+        assert!(intensity_centroid_angle(&right, 20, 20, 15).abs() < 1e-4);
+        assert!((intensity_centroid_angle(&below, 20, 20, 15) - f32::consts::FRAC_PI_2).abs() < 1e-4);
+        assert!((intensity_centroid_angle(&left, 20, 20, 15).abs() - f32::consts::PI).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_describe_corners_discards_corners_near_border() {
+        let image = textured_image(50, 50);
+        let corners = vec![
+            Corner::new(25, 25, 1.0),
+            Corner::new(14, 25, 1.0),
+            Corner::new(25, 35, 1.0),
+            Corner::new(34, 34, 1.0)
+        ];
+        let features = describe_corners(&image, &corners);
+        let locations: Vec<_> = features.iter().map(|f| (f.keypoint.x, f.keypoint.y)).collect();
+        assert_eq!(locations, vec![(25.0, 25.0), (34.0, 34.0)]);
+    }
+
+    #[test]
+    fn test_descriptor_is_rotation_invariant() {
+        let image = textured_image(61, 61);
+        let rotated = rotate90(&image);
+
+        let original = describe_corners(&image, &[Corner::new(30, 30, 1.0)]);
+        let turned = describe_corners(&rotated, &[Corner::new(30, 30, 1.0)]);
+
+        let angle_difference = turned[0].keypoint.angle - original[0].keypoint.angle;
+        let wrapped = (angle_difference + 2.0 * f32::consts::PI) % (2.0 * f32::consts::PI);
+        assert!((wrapped - f32::consts::FRAC_PI_2).abs() < 1e-3);
+
+        let distance = original[0].descriptor.hamming_distance(&turned[0].descriptor);
+        assert!(distance < 20, "distance {}", distance);
+    }
+
+    #[test]
+    fn test_match_descriptors_ratio_test() {
+        let first = vec![descriptor([0, 0, 0, 0])];
+        // Second-best is 3 bits away, best is 1 bit away
+        let second = vec![descriptor([0b111, 0, 0, 0]), descriptor([0b1, 0, 0, 0])];
+
+        assert_eq!(match_descriptors(&first, &second, MatchOptions::new(0.5, false)),
+                   vec![Match { first: 0, second: 1, distance: 1 }]);
+        assert_eq!(match_descriptors(&first, &second, MatchOptions::new(0.3, false)),
+                   vec![]);
+        // A single candidate always passes the ratio test
+        assert_eq!(match_descriptors(&first, &second[..1], MatchOptions::new(0.3, false)),
+                   vec![Match { first: 0, second: 0, distance: 3 }]);
+    }
+
+    #[test]
+    fn test_match_descriptors_cross_check() {
+        let first = vec![descriptor([0, 0, 0, 0]), descriptor([0b11, 0, 0, 0])];
+        let second = vec![descriptor([0b1, 0, 0, 0])];
+
+        // Both descriptors in first are distance 1 from second[0], which prefers first[0]
+        assert_eq!(match_descriptors(&first, &second, MatchOptions::new(1.0, false)),
+                   vec![Match { first: 0, second: 0, distance: 1 },
+                        Match { first: 1, second: 0, distance: 1 }]);
+        assert_eq!(match_descriptors(&first, &second, MatchOptions::new(1.0, true)),
+                   vec![Match { first: 0, second: 0, distance: 1 }]);
+    }
+
+    #[test]
+    fn test_match_descriptors_empty() {
+        let first = vec![descriptor([0, 0, 0, 0])];
+        assert_eq!(match_descriptors(&first, &[], MatchOptions::new(0.8, true)), vec![]);
+        assert_eq!(match_descriptors(&[], &first, MatchOptions::new(0.8, true)), vec![]);
+    }
+
+    #[test]
+    fn test_orb_matches_translated_image() {
+        let image = textured_image(120, 120);
+        let shifted = translate(&image, (7, 4));
+
+        let options = OrbOptions::new(200);
+        let first = orb(&image, options);
+        let second = orb(&shifted, options);
+        assert!(first.len() > 20, "only {} features", first.len());
+
+        let first_descriptors: Vec<_> = first.iter().map(|f| f.descriptor).collect();
+        let second_descriptors: Vec<_> = second.iter().map(|f| f.descriptor).collect();
+        let matches = match_descriptors(&first_descriptors, &second_descriptors,
+                                        MatchOptions::new(0.8, true));
+        assert!(matches.len() > 10, "only {} matches", matches.len());
+
+        let consistent = matches.iter().filter(|m| {
+            let a = first[m.first].keypoint;
+            let b = second[m.second].keypoint;
+            (b.x - a.x - 7.0).abs() <= a.scale && (b.y - a.y - 4.0).abs() <= a.scale
+        }).count();
+        assert!(consistent * 10 >= matches.len() * 9,
+            "{} of {} matches consistent with translation", consistent, matches.len());
+    }
+
+    #[test]
+    fn test_orb_respects_max_features() {
+        let image = textured_image(120, 120);
+        let features = orb(&image, OrbOptions::new(15));
+        assert_eq!(features.len(), 15);
+        for w in features.windows(2) {
+            assert!(w[0].keypoint.score >= w[1].keypoint.score);
+        }
+    }
+
+    #[test]
+    fn test_describe_corners_of_fast_corners() {
+        let image = textured_image(80, 80);
+        let corners = corners_fast9(&image, 20);
+        let features = describe_corners(&image, &corners);
+        assert!(!features.is_empty());
+        assert!(features.len() <= corners.len());
+    }
+
+    #[bench]
+    fn bench_orb(b: &mut Bencher) {
+        let image = textured_image(200, 200);
+        b.iter(|| {
+            let features = orb(&image, OrbOptions::new(500));
+            black_box(features);
+        });
+    }
+
+    #[bench]
+    fn bench_match_descriptors(b: &mut Bencher) {
+        let image = textured_image(200, 200);
+        let descriptors: Vec<_> = orb(&image, OrbOptions::new(500))
+            .iter()
+            .map(|f| f.descriptor)
+            .collect();
+        b.iter(|| {
+            let matches = match_descriptors(&descriptors, &descriptors, MatchOptions::new(0.8, true));
+            black_box(matches);
+        });
+    }
+}
@@ -0,0 +1,419 @@
+//! Functions for building Gaussian, Laplacian and arbitrary scale image pyramids.
+
+use image::{
+    GenericImage,
+    ImageBuffer,
+    Pixel
+};
+
+use definitions::{
+    Clamp,
+    Image
+};
+
+use affine::{
+    resize,
+    ResizeFilter
+};
+
+use filter::normalized_gaussian_blur_f32;
+
+use map::{
+    ChannelMap,
+    WithChannel
+};
+
+use conv::ValueInto;
+use math::cast;
+
+/// Standard deviation of the Gaussian blur applied before decimation.
+const REDUCE_SIGMA: f32 = 1.0;
+
+/// Returns a Gaussian pyramid with at most `levels` levels. The first level is a
+/// copy of `image` and each subsequent level is computed from the previous one
+/// using `downsample`. Fewer levels are returned if a 1x1 level is reached.
+pub fn gaussian_pyramid<P>(image: &Image<P>, levels: u32) -> Vec<Image<P>>
+    where P: WithChannel<f32> + 'static,
+          P::Subpixel: ValueInto<f32> + Clamp<f32>
+{
+    let mut pyramid: Vec<Image<P>> = vec![];
+    if levels == 0 {
+        return pyramid;
+    }
+
+    pyramid.push(image.clone());
+    while (pyramid.len() as u32) < levels {
+        let next = {
+            let last = &pyramid[pyramid.len() - 1];
+            if last.width() == 1 && last.height() == 1 {
+                break;
+            }
+            downsample(last)
+        };
+        pyramid.push(next);
+    }
+
+    pyramid
+}
+
+/// Returns a pyramid with at most `levels` levels whose sizes decrease by a factor of
+/// `scale_factor` per level, which need not be 2. Level i is `image` resized to
+/// (width / scale_factor^i, height / scale_factor^i), rounding to the nearest integer,
+/// using `affine::resize` with the `Bilinear` filter, which smooths when downsampling.
+/// The first level is a copy of `image`. Fewer levels are returned if a level would be empty.
+///
+/// `scale_factor` is required to be greater than 1.
+pub fn scale_pyramid<P>(image: &Image<P>, levels: u32, scale_factor: f32) -> Vec<Image<P>>
+    where P: Pixel + Send + Sync + 'static,
+          P::Subpixel: ValueInto<f32> + Clamp<f32> + Send + Sync
+{
+    assert!(scale_factor > 1.0, "scale_factor must be greater than 1");
+
+    let (width, height) = image.dimensions();
+    let mut pyramid: Vec<Image<P>> = vec![];
+    let mut scale = 1f32;
+
+    for level in 0..levels {
+        let level_width = (width as f32 / scale).round() as u32;
+        let level_height = (height as f32 / scale).round() as u32;
+        if level_width == 0 || level_height == 0 {
+            break;
+        }
+        pyramid.push(if level == 0 {
+            image.clone()
+        } else {
+            resize(image, level_width, level_height, ResizeFilter::Bilinear)
+        });
+        scale *= scale_factor;
+    }
+
+    pyramid
+}
+
+/// Blurs an image with a Gaussian of standard deviation 1 and keeps every second
+/// pixel in each direction. An image of size (w, h) is reduced to size
+/// ((w + 1) / 2, (h + 1) / 2), and output pixel (x, y) is the blurred value
+/// at (2 * x, 2 * y).
+pub fn downsample<P>(image: &Image<P>) -> Image<P>
+    where P: WithChannel<f32> + 'static,
+          P::Subpixel: ValueInto<f32> + Clamp<f32>
+{
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 {
+        return ImageBuffer::new(width, height);
+    }
+
+    let blurred = normalized_gaussian_blur_f32(image, REDUCE_SIGMA);
+    ImageBuffer::from_fn((width + 1) / 2, (height + 1) / 2, |x, y| {
+        unsafe { blurred.unsafe_get_pixel(2 * x, 2 * y) }
+    })
+}
+
+/// Upsamples an image to size (`width`, `height`). Output pixel (x, y) is the
+/// value of the input at (x / 2, y / 2), computed using bilinear interpolation
+/// and clamped to the image bounds. This is the inverse of `downsample` in the sense
+/// that pixels at even coordinates are copied directly from the input.
+pub fn upsample<P>(image: &Image<P>, width: u32, height: u32) -> Image<P>
+    where P: Pixel + 'static,
+          P::Subpixel: ValueInto<f32> + Clamp<f32>
+{
+    upsample_impl(image, width, height)
+}
+
+/// A Laplacian pyramid, which stores an image as a sequence of band-pass images
+/// plus a low resolution residual, and allows exact reconstruction of the original.
+pub struct LaplacianPyramid<P: WithChannel<f32>> {
+    /// Differences between each level of a Gaussian pyramid and the upsampled next
+    /// level, finest first.
+    pub bands: Vec<Image<ChannelMap<P, f32>>>,
+    /// The coarsest level of the Gaussian pyramid.
+    pub residual: Image<P>
+}
+
+impl<P> LaplacianPyramid<P>
+    where P: WithChannel<f32> + 'static,
+          P::Subpixel: ValueInto<f32> + Clamp<f32>
+{
+    /// Reconstructs the image from which this pyramid was built.
+    pub fn reconstruct(&self) -> Image<P> {
+        let mut current = self.residual.clone();
+
+        for band in self.bands.iter().rev() {
+            let (width, height) = band.dimensions();
+            let mut expanded: Image<ChannelMap<P, f32>> = upsample_impl(&current, width, height);
+            for (e, b) in expanded.iter_mut().zip(band.iter()) {
+                *e += *b;
+            }
+            let mut next: Image<P> = ImageBuffer::new(width, height);
+            for (n, e) in next.iter_mut().zip(expanded.iter()) {
+                *n = P::Subpixel::clamp(*e);
+            }
+            current = next;
+        }
+
+        current
+    }
+}
+
+/// Returns a Laplacian pyramid with at most `levels` levels, counting the residual.
+/// Fewer levels are returned if the Gaussian pyramid of `image` reaches a 1x1 level.
+///
+/// Panics if `levels` is 0.
+pub fn laplacian_pyramid<P>(image: &Image<P>, levels: u32) -> LaplacianPyramid<P>
+    where P: WithChannel<f32> + 'static,
+          P::Subpixel: ValueInto<f32> + Clamp<f32>
+{
+    assert!(levels > 0, "a Laplacian pyramid must have at least one level");
+
+    let mut gaussian = gaussian_pyramid(image, levels);
+    let residual = gaussian.pop().unwrap();
+
+    let bands = gaussian.iter().enumerate().map(|(i, level)| {
+        let (width, height) = level.dimensions();
+        let next = if i + 1 < gaussian.len() { &gaussian[i + 1] } else { &residual };
+        let mut band: Image<ChannelMap<P, f32>> = upsample_impl(next, width, height);
+        for (b, l) in band.iter_mut().zip(level.iter()) {
+            let l: f32 = cast(*l);
+            *b = l - *b;
+        }
+        band
+    }).collect();
+
+    LaplacianPyramid {
+        bands: bands,
+        residual: residual
+    }
+}
+
+/// See `upsample`. The output pixel type may differ from the input pixel type,
+/// but they must have the same number of channels.
+fn upsample_impl<P, Q>(image: &Image<P>, width: u32, height: u32) -> Image<Q>
+    where P: Pixel + 'static,
+          Q: Pixel + 'static,
+          P::Subpixel: ValueInto<f32>,
+          Q::Subpixel: Clamp<f32>
+{
+    let mut out = ImageBuffer::new(width, height);
+    let (in_width, in_height) = image.dimensions();
+    if in_width == 0 || in_height == 0 {
+        return out;
+    }
+
+    let num_channels = P::channel_count() as usize;
+    debug_assert!(num_channels == Q::channel_count() as usize);
+
+    // Input coordinates and interpolation weight for each output column
+    let columns: Vec<(u32, u32, f32)> = (0..width)
+        .map(|x| interpolation_coordinates(x, in_width))
+        .collect();
+
+    for y in 0..height {
+        let (y0, y1, wy) = interpolation_coordinates(y, in_height);
+        for (x, &(x0, x1, wx)) in columns.iter().enumerate() {
+            let (tl, tr, bl, br) = unsafe {(
+                image.unsafe_get_pixel(x0, y0),
+                image.unsafe_get_pixel(x1, y0),
+                image.unsafe_get_pixel(x0, y1),
+                image.unsafe_get_pixel(x1, y1)
+            )};
+            let pix = out.get_pixel_mut(x as u32, y);
+            for c in 0..num_channels {
+                let top = (1.0 - wx) * cast::<_, f32>(tl.channels()[c])
+                    + wx * cast::<_, f32>(tr.channels()[c]);
+                let bottom = (1.0 - wx) * cast::<_, f32>(bl.channels()[c])
+                    + wx * cast::<_, f32>(br.channels()[c]);
+                pix.channels_mut()[c] = Q::Subpixel::clamp((1.0 - wy) * top + wy * bottom);
+            }
+        }
+    }
+
+    out
+}
+
+/// Returns the two input coordinates between which output coordinate
+/// `i` falls when upsampling by a factor of two, and the weight of the second.
+fn interpolation_coordinates(i: u32, len: u32) -> (u32, u32, f32) {
+    let lower = i / 2;
+    if lower >= len - 1 {
+        (len - 1, len - 1, 0.0)
+    } else if i % 2 == 0 {
+        (lower, lower, 0.0)
+    } else {
+        (lower, lower + 1, 0.5)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        downsample,
+        gaussian_pyramid,
+        laplacian_pyramid,
+        scale_pyramid,
+        upsample
+    };
+    use image::{
+        GrayImage,
+        ImageBuffer,
+        Luma,
+        Rgb,
+        RgbImage
+    };
+    use utils::{
+        gray_bench_image,
+        rgb_bench_image
+    };
+    use test::{
+        Bencher,
+        black_box
+    };
+
+    #[test]
+    fn test_gaussian_pyramid_dimensions() {
+        let image = GrayImage::new(13, 6);
+        let pyramid = gaussian_pyramid(&image, 10);
+        let dimensions: Vec<_> = pyramid.iter().map(|l| l.dimensions()).collect();
+        assert_eq!(dimensions, vec![(13, 6), (7, 3), (4, 2), (2, 1), (1, 1)]);
+
+        assert_eq!(gaussian_pyramid(&image, 2).len(), 2);
+        assert_eq!(gaussian_pyramid(&image, 0).len(), 0);
+    }
+
+    #[test]
+    fn test_scale_pyramid_dimensions() {
+        let image = GrayImage::new(20, 10);
+        let pyramid = scale_pyramid(&image, 10, 1.5);
+        let dimensions: Vec<_> = pyramid.iter().map(|l| l.dimensions()).collect();
+        assert_eq!(dimensions, vec![(20, 10), (13, 7), (9, 4), (6, 3), (4, 2), (3, 1), (2, 1), (1, 1)]);
+
+        assert_eq!(scale_pyramid(&image, 2, 1.5).len(), 2);
+        assert_eq!(scale_pyramid(&image, 0, 1.5).len(), 0);
+    }
+
+    #[test]
+    fn test_scale_pyramid_of_constant_image_is_constant() {
+        let image = RgbImage::from_pixel(30, 25, Rgb([10u8, 100, 200]));
+        for level in scale_pyramid(&image, 5, 1.2) {
+            assert!(level.pixels().all(|p| *p == Rgb([10u8, 100, 200])));
+        }
+    }
+
+    #[test]
+    fn test_gaussian_pyramid_of_constant_image_is_constant() {
+        let image = RgbImage::from_pixel(20, 15, Rgb([10u8, 100, 200]));
+        for level in gaussian_pyramid(&image, 4) {
+            assert!(level.pixels().all(|p| *p == Rgb([10u8, 100, 200])));
+        }
+        for v in 0..256 {
+            let image = GrayImage::from_pixel(9, 7, Luma([v as u8]));
+            for level in gaussian_pyramid(&image, 3) {
+                assert!(level.pixels().all(|p| p[0] == v as u8), "value {}", v);
+            }
+        }
+    }
+
+    #[test]
+    fn test_downsample_f32_is_not_quantised() {
+        let image = ImageBuffer::from_pixel(12, 10, Luma([1000.05f32]));
+        let small = downsample(&image);
+        assert_eq!(small.dimensions(), (6, 5));
+        for p in small.pixels() {
+            assert!((p[0] - 1000.05).abs() < 1e-3, "value {}", p[0]);
+        }
+    }
+
+    #[test]
+    fn test_downsample_averages_neighbourhoods() {
+        // Alternating columns of 0 and 200 are blurred towards their mean
+        let image = GrayImage::from_fn(16, 16, |x, _| Luma([if x % 2 == 0 { 0u8 } else { 200u8 }]));
+        let small = downsample(&image);
+        assert_eq!(small.dimensions(), (8, 8));
+        for x in 1..7 {
+            let p = small.get_pixel(x, 4)[0];
+            assert!(p > 80 && p < 120, "pixel {} has value {}", x, p);
+        }
+    }
+
+    #[test]
+    fn test_upsample() {
+        let image: GrayImage = ImageBuffer::from_raw(3, 2, vec![
+            0, 10, 30,
+            20, 50, 70]).unwrap();
+
+        let expected: GrayImage = ImageBuffer::from_raw(6, 4, vec![
+            0, 5, 10, 20, 30, 30,
+            10, 20, 30, 40, 50, 50,
+            20, 35, 50, 60, 70, 70,
+            20, 35, 50, 60, 70, 70]).unwrap();
+
+        assert_pixels_eq!(upsample(&image, 6, 4), expected);
+
+        let odd: GrayImage = ImageBuffer::from_raw(5, 3, vec![
+            0, 5, 10, 20, 30,
+            10, 20, 30, 40, 50,
+            20, 35, 50, 60, 70]).unwrap();
+
+        assert_pixels_eq!(upsample(&image, 5, 3), odd);
+    }
+
+    #[test]
+    fn test_laplacian_pyramid_reconstruction_is_exact_luma() {
+        let image = gray_bench_image(37, 22);
+        let pyramid = laplacian_pyramid(&image, 4);
+        assert_eq!(pyramid.bands.len(), 3);
+        assert_eq!(pyramid.residual.dimensions(), (5, 3));
+        assert_pixels_eq!(pyramid.reconstruct(), image);
+    }
+
+    #[test]
+    fn test_laplacian_pyramid_reconstruction_is_exact_rgb() {
+        let image = rgb_bench_image(30, 41);
+        let pyramid = laplacian_pyramid(&image, 3);
+        assert_pixels_eq!(pyramid.reconstruct(), image);
+    }
+
+    #[test]
+    fn test_laplacian_pyramid_reconstruction_is_exact_u16() {
+        let image = ImageBuffer::from_fn(17, 9, |x, y| Luma([(x * 1000 + y * 3001) as u16]));
+        let pyramid = laplacian_pyramid(&image, 10);
+        assert_eq!(pyramid.residual.dimensions(), (1, 1));
+        assert_pixels_eq!(pyramid.reconstruct(), image);
+    }
+
+    #[test]
+    fn test_laplacian_pyramid_of_constant_image_has_constant_bands() {
+        let image = GrayImage::from_pixel(12, 12, Luma([77u8]));
+        let pyramid = laplacian_pyramid(&image, 3);
+        for band in &pyramid.bands {
+            let first = band.get_pixel(0, 0)[0];
+            assert!(band.pixels().all(|p| p[0] == first));
+        }
+        assert_pixels_eq!(pyramid.reconstruct(), image);
+    }
+
+    #[test]
+    fn test_laplacian_pyramid_single_level() {
+        let image = gray_bench_image(8, 8);
+        let pyramid = laplacian_pyramid(&image, 1);
+        assert!(pyramid.bands.is_empty());
+        assert_pixels_eq!(pyramid.residual, image);
+    }
+
+    #[bench]
+    fn bench_gaussian_pyramid(b: &mut Bencher) {
+        let image = gray_bench_image(256, 256);
+        b.iter(|| {
+            let pyramid = gaussian_pyramid(&image, 5);
+            black_box(pyramid);
+        });
+    }
+
+    #[bench]
+    fn bench_laplacian_pyramid(b: &mut Bencher) {
+        let image = gray_bench_image(256, 256);
+        b.iter(|| {
+            let pyramid = laplacian_pyramid(&image, 5);
+            black_box(pyramid);
+        });
+    }
+}
@@ -0,0 +1,716 @@
+//! Functions for robustly estimating geometric transformations between
+//! sets of corresponding points using RANSAC.
+//!
+//! See [Random sample consensus](https://en.wikipedia.org/wiki/Random_sample_consensus).
+
+use nalgebra::{
+    Affine2,
+    DMatrix,
+    DVector,
+    Matrix3,
+    Vector3
+};
+
+use rand::{
+    SeedableRng,
+    StdRng
+};
+
+use rand::distributions::{
+    IndependentSample,
+    Range
+};
+
+/// A pair of corresponding points in two images.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Correspondence {
+    /// Location of the point in the first image.
+    pub from: (f32, f32),
+    /// Location of the point in the second image.
+    pub to: (f32, f32)
+}
+
+impl Correspondence {
+    /// A correspondence between `from` in the first image and `to` in the second.
+    pub fn new(from: (f32, f32), to: (f32, f32)) -> Correspondence {
+        Correspondence {from: from, to: to}
+    }
+}
+
+/// A transformation mapping points in one image to points in another,
+/// which can be estimated from point correspondences.
+pub trait Model: Sized {
+    /// The smallest number of correspondences from which a model can be estimated.
+    fn min_samples() -> usize;
+
+    /// Returns the model minimising the squared error over the given
+    /// correspondences, or None if the model is not determined by them.
+    fn estimate(correspondences: &[Correspondence]) -> Option<Self>;
+
+    /// The transformation as a matrix acting on homogeneous coordinates.
+    fn matrix(&self) -> Matrix3<f32>;
+
+    /// Distance between the image of `correspondence.from`
+    /// under this model and `correspondence.to`.
+    fn error(&self, correspondence: &Correspondence) -> f32 {
+        let (x, y) = correspondence.from;
+        let p = self.matrix() * Vector3::new(x, y, 1f32);
+        let dx = p[0] / p[2] - correspondence.to.0;
+        let dy = p[1] / p[2] - correspondence.to.1;
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+/// A translation.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct TranslationModel {
+    /// Horizontal offset.
+    pub dx: f32,
+    /// Vertical offset.
+    pub dy: f32
+}
+
+impl TranslationModel {
+    /// The transformation in the form expected by `affine::affine`.
+    pub fn affine(&self) -> Affine2<f32> {
+        Affine2::from_matrix_unchecked(self.matrix())
+    }
+}
+
+impl Model for TranslationModel {
+    fn min_samples() -> usize {
+        1
+    }
+
+    fn estimate(correspondences: &[Correspondence]) -> Option<TranslationModel> {
+        if correspondences.is_empty() {
+            return None;
+        }
+        let n = correspondences.len() as f64;
+        let (mut dx, mut dy) = (0f64, 0f64);
+        for c in correspondences {
+            dx += (c.to.0 - c.from.0) as f64;
+            dy += (c.to.1 - c.from.1) as f64;
+        }
+        Some(TranslationModel { dx: (dx / n) as f32, dy: (dy / n) as f32 })
+    }
+
+    fn matrix(&self) -> Matrix3<f32> {
+        Matrix3::new(
+            1.0, 0.0, self.dx,
+            0.0, 1.0, self.dy,
+            0.0, 0.0, 1.0)
+    }
+}
+
+/// A rotation and uniform scaling followed by a translation.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct SimilarityModel {
+    /// Scale factor.
+    pub scale: f32,
+    /// Clockwise rotation in radians (as image y-coordinates increase downwards).
+    pub angle: f32,
+    /// Horizontal offset.
+    pub dx: f32,
+    /// Vertical offset.
+    pub dy: f32
+}
+
+impl SimilarityModel {
+    /// The transformation in the form expected by `affine::affine`.
+    pub fn affine(&self) -> Affine2<f32> {
+        Affine2::from_matrix_unchecked(self.matrix())
+    }
+}
+
+impl Model for SimilarityModel {
+    fn min_samples() -> usize {
+        2
+    }
+
+    fn estimate(correspondences: &[Correspondence]) -> Option<SimilarityModel> {
+        if correspondences.len() < 2 {
+            return None;
+        }
+        let (from_mean, to_mean) = means(correspondences);
+
+        // Solve for u = a * x - b * y + dx, v = b * x + a * y + dy
+        // in coordinates relative to the means.
+        let (mut sxx, mut sa, mut sb) = (0f64, 0f64, 0f64);
+        for c in correspondences {
+            let (x, y) = (c.from.0 as f64 - from_mean.0, c.from.1 as f64 - from_mean.1);
+            let (u, v) = (c.to.0 as f64 - to_mean.0, c.to.1 as f64 - to_mean.1);
+            sxx += x * x + y * y;
+            sa += x * u + y * v;
+            sb += x * v - y * u;
+        }
+        if sxx <= 0.0 {
+            return None;
+        }
+        let a = sa / sxx;
+        let b = sb / sxx;
+
+        Some(SimilarityModel {
+            scale: (a * a + b * b).sqrt() as f32,
+            angle: b.atan2(a) as f32,
+            dx: (to_mean.0 - a * from_mean.0 + b * from_mean.1) as f32,
+            dy: (to_mean.1 - b * from_mean.0 - a * from_mean.1) as f32
+        })
+    }
+
+    fn matrix(&self) -> Matrix3<f32> {
+        let (sin, cos) = self.angle.sin_cos();
+        let a = self.scale * cos;
+        let b = self.scale * sin;
+        Matrix3::new(
+            a, -b, self.dx,
+            b, a, self.dy,
+            0.0, 0.0, 1.0)
+    }
+}
+
+/// A general affine transformation.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct AffineModel {
+    affine: Affine2<f32>
+}
+
+impl AffineModel {
+    /// Construct a model from an affine transformation.
+    pub fn new(affine: Affine2<f32>) -> AffineModel {
+        AffineModel { affine: affine }
+    }
+
+    /// The transformation in the form expected by `affine::affine`.
+    pub fn affine(&self) -> Affine2<f32> {
+        self.affine
+    }
+}
+
+impl Model for AffineModel {
+    fn min_samples() -> usize {
+        3
+    }
+
+    fn estimate(correspondences: &[Correspondence]) -> Option<AffineModel> {
+        if correspondences.len() < 3 {
+            return None;
+        }
+        let (from_mean, to_mean) = means(correspondences);
+
+        // Solve the normal equations for each output coordinate
+        // in coordinates relative to the means.
+        let (mut sxx, mut sxy, mut syy) = (0f64, 0f64, 0f64);
+        let (mut sxu, mut syu, mut sxv, mut syv) = (0f64, 0f64, 0f64, 0f64);
+        for c in correspondences {
+            let (x, y) = (c.from.0 as f64 - from_mean.0, c.from.1 as f64 - from_mean.1);
+            let (u, v) = (c.to.0 as f64 - to_mean.0, c.to.1 as f64 - to_mean.1);
+            sxx += x * x;
+            sxy += x * y;
+            syy += y * y;
+            sxu += x * u;
+            syu += y * u;
+            sxv += x * v;
+            syv += y * v;
+        }
+        let det = sxx * syy - sxy * sxy;
+        if det.abs() <= 1e-9 * sxx * syy || det == 0.0 {
+            return None;
+        }
+
+        let a = (syy * sxu - sxy * syu) / det;
+        let b = (sxx * syu - sxy * sxu) / det;
+        let c = (syy * sxv - sxy * syv) / det;
+        let d = (sxx * syv - sxy * sxv) / det;
+
+        let matrix = Matrix3::new(
+            a as f32, b as f32, (to_mean.0 - a * from_mean.0 - b * from_mean.1) as f32,
+            c as f32, d as f32, (to_mean.1 - c * from_mean.0 - d * from_mean.1) as f32,
+            0.0, 0.0, 1.0);
+
+        Some(AffineModel::new(Affine2::from_matrix_unchecked(matrix)))
+    }
+
+    fn matrix(&self) -> Matrix3<f32> {
+        *self.affine.matrix()
+    }
+}
+
+/// A projective transformation, in the form expected by `affine::warp_perspective`.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct HomographyModel {
+    /// The transformation, normalised so that its bottom right entry is 1.
+    pub matrix: Matrix3<f32>
+}
+
+impl Model for HomographyModel {
+    fn min_samples() -> usize {
+        4
+    }
+
+    fn estimate(correspondences: &[Correspondence]) -> Option<HomographyModel> {
+        if correspondences.len() < 4 {
+            return None;
+        }
+
+        // Normalise both point sets to improve the conditioning of the normal equations.
+        let from: Vec<_> = correspondences.iter().map(|c| c.from).collect();
+        let to: Vec<_> = correspondences.iter().map(|c| c.to).collect();
+        let (from_normalized, from_transform) = match normalize_points(&from) {
+            Some(n) => n,
+            None => return None
+        };
+        let (to_normalized, to_transform) = match normalize_points(&to) {
+            Some(n) => n,
+            None => return None
+        };
+
+        // Each correspondence gives two linear equations in the eight unknown
+        // entries h11, h12, h13, h21, h22, h23, h31, h32 of the homography.
+        let mut ata = DMatrix::from_element(8, 8, 0f64);
+        let mut atb = DVector::from_element(8, 0f64);
+        for (&(x, y), &(u, v)) in from_normalized.iter().zip(to_normalized.iter()) {
+            let rows = [
+                ([x, y, 1.0, 0.0, 0.0, 0.0, -u * x, -u * y], u),
+                ([0.0, 0.0, 0.0, x, y, 1.0, -v * x, -v * y], v)
+            ];
+            for &(ref row, rhs) in &rows {
+                for i in 0..8 {
+                    atb[i] += row[i] * rhs;
+                    for j in 0..8 {
+                        ata[(i, j)] += row[i] * row[j];
+                    }
+                }
+            }
+        }
+
+        let h = match ata.try_inverse() {
+            Some(inv) => inv * atb,
+            None => return None
+        };
+        let normalized = Matrix3::new(
+            h[0], h[1], h[2],
+            h[3], h[4], h[5],
+            h[6], h[7], 1.0);
+
+        let to_inverse = match to_transform.try_inverse() {
+            Some(inv) => inv,
+            None => return None
+        };
+        let full = to_inverse * normalized * from_transform;
+        let scale = full[(2, 2)];
+        if scale.abs() < 1e-12 {
+            return None;
+        }
+
+        let mut matrix = Matrix3::new(0f32, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+        for r in 0..3 {
+            for c in 0..3 {
+                matrix[(r, c)] = (full[(r, c)] / scale) as f32;
+            }
+        }
+        if !matrix.iter().all(|e| e.is_finite()) {
+            return None;
+        }
+
+        Some(HomographyModel { matrix: matrix })
+    }
+
+    fn matrix(&self) -> Matrix3<f32> {
+        self.matrix
+    }
+}
+
+/// Options for RANSAC.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct RansacOptions {
+    /// A correspondence is an inlier for a model if the model's
+    /// error on it is at most this value.
+    pub inlier_threshold: f32,
+    /// The maximum number of random samples to try.
+    pub max_iterations: usize,
+    /// Sampling stops early once the probability that at least one sample
+    /// contained only inliers exceeds this value, assuming the inlier ratio
+    /// of the best model found so far.
+    pub confidence: f32,
+    /// Seed for the random number generator used to pick samples.
+    pub seed: usize
+}
+
+impl RansacOptions {
+    /// Options with the given inlier threshold and seed, trying at most
+    /// 1000 samples with a confidence of 0.99.
+    pub fn new(inlier_threshold: f32, seed: usize) -> RansacOptions {
+        RansacOptions {
+            inlier_threshold: inlier_threshold,
+            max_iterations: 1000,
+            confidence: 0.99,
+            seed: seed
+        }
+    }
+}
+
+/// A model estimated by RANSAC, and which correspondences it fits.
+#[derive(Clone,Debug,PartialEq)]
+pub struct RansacResult<M> {
+    /// The estimated model.
+    pub model: M,
+    /// Entry i is true if the ith correspondence is an inlier for the model.
+    pub inliers: Vec<bool>
+}
+
+impl<M> RansacResult<M> {
+    /// The number of inliers for the model.
+    pub fn num_inliers(&self) -> usize {
+        self.inliers.iter().filter(|&&i| i).count()
+    }
+}
+
+/// Estimates a model mapping `from` points to `to` points using RANSAC.
+///
+/// Models are repeatedly estimated from random minimal samples of the
+/// correspondences, and the model with the most inliers is re-estimated
+/// from all of its inliers. Returns None if there are fewer correspondences
+/// than the model requires or if no sample produced a model.
+pub fn ransac<M: Model>(correspondences: &[Correspondence], options: RansacOptions)
+        -> Option<RansacResult<M>> {
+    let n = correspondences.len();
+    let k = M::min_samples();
+    if n < k {
+        return None;
+    }
+
+    let seed_array: &[_] = &[options.seed];
+    let mut rng: StdRng = SeedableRng::from_seed(seed_array);
+    let uniform = Range::new(0, n);
+
+    let mut best: Option<(M, usize)> = None;
+    let mut required_iterations = options.max_iterations;
+    let mut sample_indices = Vec::with_capacity(k);
+    let mut sample = Vec::with_capacity(k);
+    let mut iteration = 0;
+
+    while iteration < required_iterations {
+        iteration += 1;
+
+        sample_indices.clear();
+        while sample_indices.len() < k {
+            let i = uniform.ind_sample(&mut rng);
+            if !sample_indices.contains(&i) {
+                sample_indices.push(i);
+            }
+        }
+        sample.clear();
+        sample.extend(sample_indices.iter().map(|&i| correspondences[i]));
+
+        let model = match M::estimate(&sample) {
+            Some(m) => m,
+            None => continue
+        };
+        let count = count_inliers(&model, correspondences, options.inlier_threshold);
+        if best.as_ref().map_or(true, |&(_, c)| count > c) {
+            best = Some((model, count));
+            required_iterations = options.max_iterations
+                .min(iterations_for_confidence(count as f64 / n as f64, k, options.confidence));
+        }
+    }
+
+    best.map(|(model, count)| {
+        let inliers = inlier_mask(&model, correspondences, options.inlier_threshold);
+        let inlier_correspondences: Vec<_> = correspondences.iter()
+            .zip(inliers.iter())
+            .filter(|&(_, &inlier)| inlier)
+            .map(|(c, _)| *c)
+            .collect();
+
+        // Only accept the refined model if it does not lose inliers
+        if let Some(refined) = M::estimate(&inlier_correspondences) {
+            let refined_inliers = inlier_mask(&refined, correspondences, options.inlier_threshold);
+            if refined_inliers.iter().filter(|&&i| i).count() >= count {
+                return RansacResult { model: refined, inliers: refined_inliers };
+            }
+        }
+        RansacResult { model: model, inliers: inliers }
+    })
+}
+
+/// The number of samples of size `sample_size` needed to draw at least one sample
+/// containing only inliers with probability `confidence`, given the ratio of inliers.
+fn iterations_for_confidence(inlier_ratio: f64, sample_size: usize, confidence: f32) -> usize {
+    let all_inliers = inlier_ratio.powi(sample_size as i32);
+    if all_inliers >= 1.0 {
+        return 1;
+    }
+    if all_inliers <= 0.0 {
+        return usize::max_value();
+    }
+    let iterations = (1.0 - confidence as f64).ln() / (1.0 - all_inliers).ln();
+    if iterations.is_finite() && iterations >= 0.0 {
+        iterations.ceil() as usize
+    } else {
+        usize::max_value()
+    }
+}
+
+fn inlier_mask<M: Model>(model: &M, correspondences: &[Correspondence], threshold: f32) -> Vec<bool> {
+    correspondences.iter().map(|c| model.error(c) <= threshold).collect()
+}
+
+fn count_inliers<M: Model>(model: &M, correspondences: &[Correspondence], threshold: f32) -> usize {
+    correspondences.iter().filter(|c| model.error(c) <= threshold).count()
+}
+
+/// Mean locations of the `from` and `to` points.
+fn means(correspondences: &[Correspondence]) -> ((f64, f64), (f64, f64)) {
+    let n = correspondences.len() as f64;
+    let mut sums = [0f64; 4];
+    for c in correspondences {
+        sums[0] += c.from.0 as f64;
+        sums[1] += c.from.1 as f64;
+        sums[2] += c.to.0 as f64;
+        sums[3] += c.to.1 as f64;
+    }
+    ((sums[0] / n, sums[1] / n), (sums[2] / n, sums[3] / n))
+}
+
+/// Translates and scales points so that their centroid is at the origin and their
+/// mean distance from it is sqrt(2). Returns the transformed points and the
+/// transformation applied, or None if all points coincide.
+fn normalize_points(points: &[(f32, f32)]) -> Option<(Vec<(f64, f64)>, Matrix3<f64>)> {
+    let n = points.len() as f64;
+    let cx = points.iter().map(|p| p.0 as f64).sum::<f64>() / n;
+    let cy = points.iter().map(|p| p.1 as f64).sum::<f64>() / n;
+    let mean_distance = points.iter()
+        .map(|p| ((p.0 as f64 - cx).powi(2) + (p.1 as f64 - cy).powi(2)).sqrt())
+        .sum::<f64>() / n;
+    if mean_distance == 0.0 {
+        return None;
+    }
+
+    let s = 2f64.sqrt() / mean_distance;
+    let normalized = points.iter()
+        .map(|p| (s * (p.0 as f64 - cx), s * (p.1 as f64 - cy)))
+        .collect();
+    let transform = Matrix3::new(
+        s, 0.0, -s * cx,
+        0.0, s, -s * cy,
+        0.0, 0.0, 1.0);
+
+    Some((normalized, transform))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        ransac,
+        AffineModel,
+        Correspondence,
+        HomographyModel,
+        Model,
+        RansacOptions,
+        SimilarityModel,
+        TranslationModel
+    };
+    use affine::{
+        affine,
+        Interpolation
+    };
+    use image::{
+        GrayImage,
+        Luma
+    };
+    use nalgebra::{
+        Matrix3,
+        Vector3
+    };
+    use rand::{
+        SeedableRng,
+        StdRng
+    };
+    use rand::distributions::{
+        IndependentSample,
+        Range
+    };
+    use test::{
+        Bencher,
+        black_box
+    };
+
+    fn grid_points() -> Vec<(f32, f32)> {
+        let mut points = vec![];
+        for y in 0..6 {
+            for x in 0..5 {
+                points.push((x as f32 * 17.0 + 3.0, y as f32 * 11.0 + 5.0 + x as f32));
+            }
+        }
+        points
+    }
+
+    fn correspondences_for(matrix: Matrix3<f32>, points: &[(f32, f32)]) -> Vec<Correspondence> {
+        points.iter().map(|&(x, y)| {
+            let p = matrix * Vector3::new(x, y, 1.0);
+            Correspondence::new((x, y), (p[0] / p[2], p[1] / p[2]))
+        }).collect()
+    }
+
+    /// Replaces the target of every `period`th correspondence with a random point.
+    fn add_outliers(correspondences: &mut [Correspondence], period: usize) -> Vec<bool> {
+        let seed_array: &[_] = &[3];
+        let mut rng: StdRng = SeedableRng::from_seed(seed_array);
+        let range = Range::new(-200f32, 200f32);
+        let mut is_inlier = vec![true; correspondences.len()];
+        for i in (0..correspondences.len()).filter(|i| i % period == 0) {
+            correspondences[i].to = (range.ind_sample(&mut rng), range.ind_sample(&mut rng));
+            is_inlier[i] = false;
+        }
+        is_inlier
+    }
+
+    fn assert_matrices_near(actual: Matrix3<f32>, expected: Matrix3<f32>, tolerance: f32) {
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert!((a - e).abs() <= tolerance, "actual {:?}, expected {:?}", actual, expected);
+        }
+    }
+
+    fn similarity_matrix() -> Matrix3<f32> {
+        let (sin, cos) = 0.3f32.sin_cos();
+        Matrix3::new(
+            1.5 * cos, -1.5 * sin, 12.0,
+            1.5 * sin, 1.5 * cos, -7.0,
+            0.0, 0.0, 1.0)
+    }
+
+    fn affine_matrix() -> Matrix3<f32> {
+        Matrix3::new(
+            1.2, 0.3, -4.0,
+            -0.2, 0.8, 9.0,
+            0.0, 0.0, 1.0)
+    }
+
+    fn homography_matrix() -> Matrix3<f32> {
+        Matrix3::new(
+            1.1, 0.2, 5.0,
+            -0.1, 0.9, 3.0,
+            0.001, 0.002, 1.0)
+    }
+
+    #[test]
+    fn test_translation_estimate() {
+        let correspondences = vec![
+            Correspondence::new((0.0, 0.0), (2.0, 3.0)),
+            Correspondence::new((5.0, 1.0), (7.0, 4.0))
+        ];
+        let model = TranslationModel::estimate(&correspondences).unwrap();
+        assert_eq!(model, TranslationModel { dx: 2.0, dy: 3.0 });
+        assert_eq!(model.error(&Correspondence::new((1.0, 1.0), (6.0, 8.0))), 5.0);
+    }
+
+    #[test]
+    fn test_similarity_estimate() {
+        let correspondences = correspondences_for(similarity_matrix(), &grid_points());
+        let model = SimilarityModel::estimate(&correspondences[..2]).unwrap();
+        assert!((model.scale - 1.5).abs() < 1e-4);
+        assert!((model.angle - 0.3).abs() < 1e-4);
+        assert_matrices_near(model.matrix(), similarity_matrix(), 1e-3);
+    }
+
+    #[test]
+    fn test_affine_estimate() {
+        let correspondences = correspondences_for(affine_matrix(), &grid_points());
+        let model = AffineModel::estimate(&correspondences).unwrap();
+        assert_matrices_near(model.matrix(), affine_matrix(), 1e-3);
+        assert_matrices_near(*model.affine().matrix(), affine_matrix(), 1e-3);
+    }
+
+    #[test]
+    fn test_affine_estimate_collinear() {
+        let points = vec![(0.0, 0.0), (1.0, 1.0), (2.0, 2.0), (3.0, 3.0)];
+        let correspondences = correspondences_for(affine_matrix(), &points);
+        assert!(AffineModel::estimate(&correspondences).is_none());
+    }
+
+    #[test]
+    fn test_homography_estimate() {
+        let points = grid_points();
+        let correspondences = correspondences_for(homography_matrix(), &points);
+        let minimal = HomographyModel::estimate(&[
+            correspondences[0], correspondences[4], correspondences[25], correspondences[29]
+        ]).unwrap();
+        assert_matrices_near(minimal.matrix(), homography_matrix(), 1e-3);
+        let model = HomographyModel::estimate(&correspondences).unwrap();
+        assert_matrices_near(model.matrix(), homography_matrix(), 1e-3);
+    }
+
+    #[test]
+    fn test_ransac_similarity_with_outliers() {
+        let mut correspondences = correspondences_for(similarity_matrix(), &grid_points());
+        let expected_inliers = add_outliers(&mut correspondences, 4);
+
+        let result = ransac::<SimilarityModel>(&correspondences, RansacOptions::new(0.5, 1)).unwrap();
+        assert_eq!(result.inliers, expected_inliers);
+        assert_eq!(result.num_inliers(), 22);
+        assert_matrices_near(result.model.matrix(), similarity_matrix(), 1e-3);
+    }
+
+    #[test]
+    fn test_ransac_affine_with_outliers() {
+        let mut correspondences = correspondences_for(affine_matrix(), &grid_points());
+        let expected_inliers = add_outliers(&mut correspondences, 3);
+
+        let result = ransac::<AffineModel>(&correspondences, RansacOptions::new(0.5, 1)).unwrap();
+        assert_eq!(result.inliers, expected_inliers);
+        assert_matrices_near(result.model.matrix(), affine_matrix(), 1e-3);
+    }
+
+    #[test]
+    fn test_ransac_homography_with_outliers() {
+        let mut correspondences = correspondences_for(homography_matrix(), &grid_points());
+        let expected_inliers = add_outliers(&mut correspondences, 5);
+
+        let result = ransac::<HomographyModel>(&correspondences, RansacOptions::new(0.5, 1)).unwrap();
+        assert_eq!(result.inliers, expected_inliers);
+        assert_matrices_near(result.model.matrix(), homography_matrix(), 1e-3);
+    }
+
+    #[test]
+    fn test_ransac_is_deterministic_for_seed() {
+        let mut correspondences = correspondences_for(affine_matrix(), &grid_points());
+        add_outliers(&mut correspondences, 2);
+
+        let mut options = RansacOptions::new(0.5, 7);
+        options.max_iterations = 5;
+        let first = ransac::<AffineModel>(&correspondences, options);
+        let second = ransac::<AffineModel>(&correspondences, options);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_ransac_too_few_correspondences() {
+        let correspondences = correspondences_for(homography_matrix(), &grid_points()[..3]);
+        assert!(ransac::<HomographyModel>(&correspondences, RansacOptions::new(1.0, 1)).is_none());
+    }
+
+    #[test]
+    fn test_ransac_translation_feeds_affine() {
+        let correspondences: Vec<_> = grid_points().iter()
+            .map(|&(x, y)| Correspondence::new((x, y), (x + 1.0, y + 2.0)))
+            .collect();
+        let result = ransac::<TranslationModel>(&correspondences, RansacOptions::new(0.5, 1)).unwrap();
+
+        let image = GrayImage::from_fn(4, 4, |x, y| Luma([(10 * y + x + 1) as u8]));
+        let moved = affine(&image, result.model.affine(), Interpolation::Nearest).unwrap();
+        assert_eq!(moved.get_pixel(1, 2)[0], 1);
+        assert_eq!(moved.get_pixel(3, 3)[0], 13);
+        assert_eq!(moved.get_pixel(0, 0)[0], 0);
+    }
+
+    #[bench]
+    fn bench_ransac_homography(b: &mut Bencher) {
+        let mut correspondences = correspondences_for(homography_matrix(), &grid_points());
+        add_outliers(&mut correspondences, 2);
+        b.iter(|| {
+            let result = ransac::<HomographyModel>(&correspondences, RansacOptions::new(0.5, 1));
+            black_box(result);
+        });
+    }
+}
@@ -1,6 +1,7 @@
 //! Basic manipulation of rectangles.
 
 use std::cmp;
+use num::Float;
 
 /// A rectangular region of non-zero width and height.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -72,8 +73,155 @@ impl Rect {
             height: (bottom - top) as u32 + 1,
         })
     }
+
+    /// Returns the smallest rect containing both self and other.
+    pub fn union(&self, other: Rect) -> Rect {
+        let left = cmp::min(self.left, other.left);
+        let top = cmp::min(self.top, other.top);
+        let right = cmp::max(self.right(), other.right());
+        let bottom = cmp::max(self.bottom(), other.bottom());
+
+        Rect {
+            left: left,
+            top: top,
+            width: (right - left) as u32 + 1,
+            height: (bottom - top) as u32 + 1,
+        }
+    }
+
+    /// Returns the smallest rect containing all of the given rects,
+    /// or none if there are no rects.
+    pub fn bounding_box<I>(rects: I) -> Option<Rect>
+        where I: IntoIterator<Item = Rect>
+    {
+        let mut rects = rects.into_iter();
+        rects.next().map(|first| rects.fold(first, |acc, r| acc.union(r)))
+    }
+
+    /// Number of pixels in rect.
+    pub fn area(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    /// Intersection over union: the number of pixels in both self and other
+    /// divided by the number in either. This is 1 for equal rects and 0 for
+    /// disjoint rects.
+    pub fn iou(&self, other: Rect) -> f32 {
+        let intersection = match self.intersect(other) {
+            Some(i) => i.area(),
+            None => return 0f32,
+        };
+        let union = self.area() + other.area() - intersection;
+        (intersection as f64 / union as f64) as f32
+    }
+
+    /// Returns a rect of the same size, moved by (dx, dy).
+    pub fn translate(&self, dx: i32, dy: i32) -> Rect {
+        Rect {
+            left: self.left + dx,
+            top: self.top + dy,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Returns the smallest rect containing the region covered by the pixels
+    /// of self when all coordinates are multiplied by `factor`, e.g. to map a
+    /// detection in a downsampled image back to the original image.
+    /// `factor` is required to be strictly positive.
+    pub fn scale(&self, factor: f32) -> Rect {
+        assert!(factor > 0f32, "factor must be strictly positive");
+        let scale = |v: i32| v as f64 * factor as f64;
+        let left = scale(self.left).floor() as i32;
+        let top = scale(self.top).floor() as i32;
+        let right = scale(self.right() + 1).ceil() as i32;
+        let bottom = scale(self.bottom() + 1).ceil() as i32;
+
+        Rect {
+            left: left,
+            top: top,
+            width: cmp::max(right - left, 1) as u32,
+            height: cmp::max(bottom - top, 1) as u32,
+        }
+    }
+
+    /// Returns a rect with the same centre, extended by dx pixels on its
+    /// left and right and by dy pixels on its top and bottom. Negative values
+    /// shrink the rect. Returns none if the result would be empty.
+    pub fn inflate(&self, dx: i32, dy: i32) -> Option<Rect> {
+        let width = self.width as i64 + 2 * dx as i64;
+        let height = self.height as i64 + 2 * dy as i64;
+
+        if width <= 0 || height <= 0 {
+            return None;
+        }
+
+        Some(Rect {
+            left: self.left - dx,
+            top: self.top - dy,
+            width: width as u32,
+            height: height as u32,
+        })
+    }
+
+    /// Returns the part of self lying within an image of the given dimensions,
+    /// or none if they are disjoint.
+    pub fn clamp_to_image(&self, width: u32, height: u32) -> Option<Rect> {
+        if width == 0 || height == 0 {
+            return None;
+        }
+        self.intersect(Rect::at(0, 0).of_size(width, height))
+    }
+
+    /// Iterates over the coordinates of the pixels in rect, in row-major order.
+    pub fn pixels(&self) -> RectPixels {
+        RectPixels {
+            rect: *self,
+            x: self.left,
+            y: self.top,
+        }
+    }
 }
 
+/// Iterator over the pixel coordinates in a rect, in row-major order.
+/// Created by `Rect::pixels`.
+#[derive(Clone, Debug)]
+pub struct RectPixels {
+    rect: Rect,
+    x: i32,
+    y: i32,
+}
+
+impl Iterator for RectPixels {
+    type Item = (i32, i32);
+
+    fn next(&mut self) -> Option<(i32, i32)> {
+        if self.y > self.rect.bottom() {
+            return None;
+        }
+        let current = (self.x, self.y);
+        if self.x == self.rect.right() {
+            self.x = self.rect.left;
+            self.y += 1;
+        } else {
+            self.x += 1;
+        }
+        Some(current)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = if self.y > self.rect.bottom() {
+            0
+        } else {
+            let rows_below = (self.rect.bottom() - self.y) as usize;
+            rows_below * self.rect.width as usize + (self.rect.right() - self.x) as usize + 1
+        };
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for RectPixels {}
+
 impl Region<i32> for Rect {
     fn contains(&self, x: i32, y: i32) -> bool {
         self.left <= x && x <= self.right() &&
@@ -110,10 +258,206 @@ impl RectPosition {
     }
 }
 
+/// An axis-aligned rectangle with floating point coordinates, e.g. for
+/// sub-pixel bounding boxes.
+///
+/// Unlike `Rect`, which is a set of pixels, a `FloatRect` is a continuous region:
+/// its right edge is at `left() + width()` and it may have zero width or height.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FloatRect<T: Float> {
+    left: T,
+    top: T,
+    width: T,
+    height: T,
+}
+
+impl<T: Float> FloatRect<T> {
+    /// Reduces possibility of confusing coordinates and dimensions
+    /// when specifying rects.
+    pub fn at(x: T, y: T) -> FloatRectPosition<T> {
+        FloatRectPosition { left: x, top: y }
+    }
+
+    /// Smallest y-coordinate reached by rect.
+    pub fn top(&self) -> T {
+        self.top
+    }
+
+    /// Smallest x-coordinate reached by rect.
+    pub fn left(&self) -> T {
+        self.left
+    }
+
+    /// Greatest y-coordinate reached by rect.
+    pub fn bottom(&self) -> T {
+        self.top + self.height
+    }
+
+    /// Greatest x-coordinate reached by rect.
+    pub fn right(&self) -> T {
+        self.left + self.width
+    }
+
+    /// Width of rect.
+    pub fn width(&self) -> T {
+        self.width
+    }
+
+    /// Height of rect.
+    pub fn height(&self) -> T {
+        self.height
+    }
+
+    /// Centre of rect.
+    pub fn center(&self) -> (T, T) {
+        let two = T::one() + T::one();
+        (self.left + self.width / two, self.top + self.height / two)
+    }
+
+    /// Area of rect.
+    pub fn area(&self) -> T {
+        self.width * self.height
+    }
+
+    /// Returns the intersection of self and other, or none if they are disjoint.
+    pub fn intersect(&self, other: FloatRect<T>) -> Option<FloatRect<T>> {
+        let left = self.left.max(other.left);
+        let top = self.top.max(other.top);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+
+        if right < left || bottom < top {
+            return None;
+        }
+
+        Some(FloatRect {
+            left: left,
+            top: top,
+            width: right - left,
+            height: bottom - top,
+        })
+    }
+
+    /// Returns the smallest rect containing both self and other.
+    pub fn union(&self, other: FloatRect<T>) -> FloatRect<T> {
+        let left = self.left.min(other.left);
+        let top = self.top.min(other.top);
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+
+        FloatRect {
+            left: left,
+            top: top,
+            width: right - left,
+            height: bottom - top,
+        }
+    }
+
+    /// Intersection over union: the area of the intersection of self and other
+    /// divided by the area of their union. Returns 0 if the union has zero area.
+    pub fn iou(&self, other: FloatRect<T>) -> T {
+        let intersection = match self.intersect(other) {
+            Some(i) => i.area(),
+            None => return T::zero(),
+        };
+        let union = self.area() + other.area() - intersection;
+        if union > T::zero() { intersection / union } else { T::zero() }
+    }
+
+    /// Returns a rect of the same size, moved by (dx, dy).
+    pub fn translate(&self, dx: T, dy: T) -> FloatRect<T> {
+        FloatRect::at(self.left + dx, self.top + dy).of_size(self.width, self.height)
+    }
+
+    /// Returns the rect obtained by multiplying all coordinates by `factor`,
+    /// which is required to be non-negative.
+    pub fn scale(&self, factor: T) -> FloatRect<T> {
+        FloatRect::at(self.left * factor, self.top * factor)
+            .of_size(self.width * factor, self.height * factor)
+    }
+
+    /// Returns a rect with the same centre, extended by dx on its left and right
+    /// and by dy on its top and bottom. Negative values shrink the rect.
+    /// Returns none if the result would have negative width or height.
+    pub fn inflate(&self, dx: T, dy: T) -> Option<FloatRect<T>> {
+        let width = self.width + dx + dx;
+        let height = self.height + dy + dy;
+
+        if width < T::zero() || height < T::zero() {
+            return None;
+        }
+
+        Some(FloatRect {
+            left: self.left - dx,
+            top: self.top - dy,
+            width: width,
+            height: height,
+        })
+    }
+
+    /// Returns the part of self lying within an image of the given dimensions,
+    /// or none if they are disjoint.
+    pub fn clamp_to_image(&self, width: u32, height: u32) -> Option<FloatRect<T>> {
+        let image = FloatRect::at(T::zero(), T::zero())
+            .of_size(T::from(width).unwrap(), T::from(height).unwrap());
+        self.intersect(image)
+    }
+
+    /// Returns the smallest `Rect` containing all pixels that overlap self, where
+    /// pixel (x, y) covers the region [x, x + 1) x [y, y + 1).
+    pub fn enclosing_rect(&self) -> Rect {
+        let left = self.left.floor();
+        let top = self.top.floor();
+        let right = self.right().ceil().max(left + T::one());
+        let bottom = self.bottom().ceil().max(top + T::one());
+
+        Rect::at(left.to_i32().unwrap(), top.to_i32().unwrap())
+            .of_size((right - left).to_u32().unwrap(), (bottom - top).to_u32().unwrap())
+    }
+}
+
+impl<T: Float> From<Rect> for FloatRect<T> {
+    /// The region covered by the pixels of a rect, where pixel (x, y)
+    /// covers the region [x, x + 1) x [y, y + 1).
+    fn from(rect: Rect) -> FloatRect<T> {
+        FloatRect::at(T::from(rect.left).unwrap(), T::from(rect.top).unwrap())
+            .of_size(T::from(rect.width).unwrap(), T::from(rect.height).unwrap())
+    }
+}
+
+impl<T: Float> Region<T> for FloatRect<T> {
+    fn contains(&self, x: T, y: T) -> bool {
+        self.left <= x && x <= self.right() &&
+        self.top <= y && y <= self.bottom()
+    }
+}
+
+/// Position of the top left of a `FloatRect`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FloatRectPosition<T: Float> {
+    left: T,
+    top: T,
+}
+
+impl<T: Float> FloatRectPosition<T> {
+    /// Construct a rectangle from a position and size. Width and height
+    /// are required to be non-negative.
+    pub fn of_size(self, width: T, height: T) -> FloatRect<T> {
+        assert!(width >= T::zero(), "width must be non-negative");
+        assert!(height >= T::zero(), "height must be non-negative");
+        FloatRect {
+            left: self.left,
+            top: self.top,
+            width: width,
+            height: height,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::{
-        Rect, Region
+        FloatRect, Rect, Region
     };
 
     #[test]
@@ -152,4 +496,107 @@ mod test {
         assert!(r.contains(5f32, 5f32));
         assert!(!r.contains(10.1f32, 10f32));
     }
+
+    #[test]
+    fn test_union() {
+        let r = Rect::at(0, 0).of_size(5, 5);
+        let s = Rect::at(3, -2).of_size(4, 3);
+        assert_eq!(r.union(s), Rect::at(0, -2).of_size(7, 7));
+        assert_eq!(r.union(r), r);
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        assert_eq!(Rect::bounding_box(vec![]), None);
+        let rects = vec![
+            Rect::at(0, 0).of_size(2, 2),
+            Rect::at(5, 1).of_size(1, 1),
+            Rect::at(-1, 3).of_size(2, 4)
+        ];
+        assert_eq!(Rect::bounding_box(rects), Some(Rect::at(-1, 0).of_size(7, 7)));
+    }
+
+    #[test]
+    fn test_iou() {
+        let r = Rect::at(0, 0).of_size(4, 4);
+        assert_eq!(r.area(), 16);
+        assert_eq!(r.iou(r), 1f32);
+        assert_eq!(r.iou(Rect::at(4, 0).of_size(4, 4)), 0f32);
+        // Intersection 8, union 24
+        assert_eq!(r.iou(Rect::at(2, 0).of_size(4, 4)), 1f32 / 3f32);
+    }
+
+    #[test]
+    fn test_translate_and_scale() {
+        let r = Rect::at(1, 2).of_size(3, 4);
+        assert_eq!(r.translate(-2, 3), Rect::at(-1, 5).of_size(3, 4));
+        assert_eq!(r.scale(2f32), Rect::at(2, 4).of_size(6, 8));
+        // Pixels 1..=3 and 2..=5 cover [1, 4) x [2, 6), which scales to [0.5, 2) x [1, 3)
+        assert_eq!(r.scale(0.5f32), Rect::at(0, 1).of_size(2, 2));
+    }
+
+    #[test]
+    fn test_inflate() {
+        let r = Rect::at(2, 2).of_size(3, 5);
+        assert_eq!(r.inflate(1, 2), Some(Rect::at(1, 0).of_size(5, 9)));
+        assert_eq!(r.inflate(-1, -2), Some(Rect::at(3, 4).of_size(1, 1)));
+        assert_eq!(r.inflate(-2, 0), None);
+    }
+
+    #[test]
+    fn test_clamp_to_image() {
+        let r = Rect::at(-2, 3).of_size(6, 10);
+        assert_eq!(r.clamp_to_image(10, 8), Some(Rect::at(0, 3).of_size(4, 5)));
+        assert_eq!(r.clamp_to_image(10, 3), None);
+        assert_eq!(r.clamp_to_image(0, 8), None);
+    }
+
+    #[test]
+    fn test_pixels() {
+        let r = Rect::at(-1, 2).of_size(2, 3);
+        let pixels = r.pixels();
+        assert_eq!(pixels.len(), 6);
+        assert_eq!(pixels.collect::<Vec<_>>(),
+            vec![(-1, 2), (0, 2), (-1, 3), (0, 3), (-1, 4), (0, 4)]);
+
+        let mut pixels = r.pixels();
+        pixels.next();
+        pixels.next();
+        pixels.next();
+        assert_eq!(pixels.len(), 3);
+    }
+
+    #[test]
+    fn test_float_rect() {
+        let r = FloatRect::at(0.5f32, 1.0).of_size(2.0, 3.0);
+        assert_eq!((r.right(), r.bottom()), (2.5, 4.0));
+        assert_eq!(r.center(), (1.5, 2.5));
+        assert_eq!(r.area(), 6.0);
+
+        let s = FloatRect::at(1.5f32, 1.0).of_size(2.0, 3.0);
+        assert_eq!(r.intersect(s), Some(FloatRect::at(1.5, 1.0).of_size(1.0, 3.0)));
+        assert_eq!(r.union(s), FloatRect::at(0.5, 1.0).of_size(3.0, 3.0));
+        assert_eq!(r.iou(s), 1.0 / 3.0);
+        assert_eq!(r.intersect(r.translate(5.0, 0.0)), None);
+
+        assert_eq!(r.scale(2.0), FloatRect::at(1.0, 2.0).of_size(4.0, 6.0));
+        assert_eq!(r.inflate(0.5, -1.0), Some(FloatRect::at(0.0, 2.0).of_size(3.0, 1.0)));
+        assert_eq!(r.inflate(-1.5, 0.0), None);
+        assert_eq!(r.translate(-1.0, 0.0).clamp_to_image(2, 2),
+            Some(FloatRect::at(0.0, 1.0).of_size(1.5, 1.0)));
+
+        assert!(r.contains(2.5, 4.0));
+        assert!(!r.contains(0.4, 2.0));
+    }
+
+    #[test]
+    fn test_float_rect_conversions() {
+        let r = Rect::at(-1, 2).of_size(3, 4);
+        let f: FloatRect<f64> = FloatRect::from(r);
+        assert_eq!(f, FloatRect::at(-1.0, 2.0).of_size(3.0, 4.0));
+        assert_eq!(f.enclosing_rect(), r);
+
+        let g = FloatRect::at(0.5f32, 0.2).of_size(1.0, 0.0);
+        assert_eq!(g.enclosing_rect(), Rect::at(0, 0).of_size(2, 1));
+    }
 }
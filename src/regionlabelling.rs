@@ -10,13 +10,19 @@ use definitions::{
     Image
 };
 
+use drawing::Point;
+use geometry::{convex_hull, polygon_area};
+use rect::Rect;
+
 use unionfind::{
     DisjointSetForest
 };
 
 use std::{
-    cmp
+    cmp,
+    f32
 };
+use std::collections::BTreeMap;
 
 /// Determines which neighbors of a pixel we consider
 /// to be connected to it.
@@ -144,11 +150,260 @@ pub fn connected_components<I>(image: &I, conn: Connectivity, background: I::Pix
     out
 }
 
+/// Spatial and central moments of a region, up to order three.
+/// Pixels are treated as points at their integer coordinates.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Moments {
+    /// Spatial moment Σ 1, i.e. the area of the region.
+    pub m00: f64,
+    /// Spatial moment Σ x.
+    pub m10: f64,
+    /// Spatial moment Σ y.
+    pub m01: f64,
+    /// Spatial moment Σ x^2.
+    pub m20: f64,
+    /// Spatial moment Σ xy.
+    pub m11: f64,
+    /// Spatial moment Σ y^2.
+    pub m02: f64,
+    /// Spatial moment Σ x^3.
+    pub m30: f64,
+    /// Spatial moment Σ x^2 y.
+    pub m21: f64,
+    /// Spatial moment Σ x y^2.
+    pub m12: f64,
+    /// Spatial moment Σ y^3.
+    pub m03: f64,
+    /// Central moment Σ (x - cx)^2, where (cx, cy) is the centroid.
+    pub mu20: f64,
+    /// Central moment Σ (x - cx)(y - cy).
+    pub mu11: f64,
+    /// Central moment Σ (y - cy)^2.
+    pub mu02: f64,
+    /// Central moment Σ (x - cx)^3.
+    pub mu30: f64,
+    /// Central moment Σ (x - cx)^2 (y - cy).
+    pub mu21: f64,
+    /// Central moment Σ (x - cx)(y - cy)^2.
+    pub mu12: f64,
+    /// Central moment Σ (y - cy)^3.
+    pub mu03: f64
+}
+
+/// Measurements of a labelled region.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct RegionProperties {
+    /// The label of the region.
+    pub label: u32,
+    /// Number of pixels in the region.
+    pub area: u32,
+    /// Smallest rectangle containing the region.
+    pub bounding_box: Rect,
+    /// Mean position of the pixels in the region.
+    pub centroid: (f32, f32),
+    /// Number of pixel edges separating the region from pixels
+    /// outside it, including those on the image border.
+    pub perimeter: f32,
+    /// Moments of the region.
+    pub moments: Moments,
+    /// Angle in radians between the x-axis and the major axis of the ellipse with
+    /// the same second central moments as the region, in the range (-π/2, π/2].
+    /// Angles are measured clockwise, as image y-coordinates increase downwards.
+    pub orientation: f32,
+    /// Eccentricity of the ellipse with the same second central moments as the
+    /// region. This is 0 for a circle and approaches 1 for elongated regions.
+    pub eccentricity: f32,
+    /// Area of the convex hull of the region, treating each pixel as a unit square.
+    pub convex_area: f32,
+    /// Ratio of the area of the region to its convex area.
+    pub solidity: f32
+}
+
+/// Per-label accumulators used by `region_properties`.
+struct RegionAccumulator {
+    area: u32,
+    min_x: u32,
+    min_y: u32,
+    max_x: u32,
+    max_y: u32,
+    perimeter: u32,
+    // Leftmost and rightmost pixels of each row containing the region, as (y, min_x, max_x)
+    rows: Vec<(u32, u32, u32)>,
+    spatial: [f64; 10],
+    central: [f64; 7]
+}
+
+impl RegionAccumulator {
+    fn new() -> RegionAccumulator {
+        RegionAccumulator {
+            area: 0,
+            min_x: u32::max_value(),
+            min_y: u32::max_value(),
+            max_x: 0,
+            max_y: 0,
+            perimeter: 0,
+            rows: vec![],
+            spatial: [0f64; 10],
+            central: [0f64; 7]
+        }
+    }
+}
+
+/// Computes properties of each region in an image of labels, such as that returned
+/// by `connected_components`. Pixels labelled 0 are treated as background. Returns
+/// one entry for each non-zero label present in the image, in increasing order of label.
+pub fn region_properties(labels: &Image<Luma<u32>>) -> Vec<RegionProperties> {
+    let (width, height) = labels.dimensions();
+    let mut accumulators: BTreeMap<u32, RegionAccumulator> = BTreeMap::new();
+
+    let label_at = |x: i64, y: i64| -> u32 {
+        if x < 0 || y < 0 || x >= width as i64 || y >= height as i64 {
+            0
+        } else {
+            unsafe { labels.unsafe_get_pixel(x as u32, y as u32)[0] }
+        }
+    };
+
+    for y in 0..height {
+        for x in 0..width {
+            let label = unsafe { labels.unsafe_get_pixel(x, y)[0] };
+            if label == 0 {
+                continue;
+            }
+            let acc = accumulators.entry(label).or_insert_with(RegionAccumulator::new);
+
+            acc.area += 1;
+            acc.min_x = cmp::min(acc.min_x, x);
+            acc.min_y = cmp::min(acc.min_y, y);
+            acc.max_x = cmp::max(acc.max_x, x);
+            acc.max_y = cmp::max(acc.max_y, y);
+
+            let (xi, yi) = (x as i64, y as i64);
+            for &(dx, dy) in &[(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                if label_at(xi + dx, yi + dy) != label {
+                    acc.perimeter += 1;
+                }
+            }
+
+            let is_new_row = acc.rows.last().map_or(true, |r| r.0 != y);
+            if is_new_row {
+                acc.rows.push((y, x, x));
+            } else {
+                let last = acc.rows.len() - 1;
+                acc.rows[last].2 = x;
+            }
+
+            let (xf, yf) = (x as f64, y as f64);
+            let s = &mut acc.spatial;
+            s[0] += 1.0;
+            s[1] += xf;
+            s[2] += yf;
+            s[3] += xf * xf;
+            s[4] += xf * yf;
+            s[5] += yf * yf;
+            s[6] += xf * xf * xf;
+            s[7] += xf * xf * yf;
+            s[8] += xf * yf * yf;
+            s[9] += yf * yf * yf;
+        }
+    }
+
+    // Compute central moments directly, to avoid the loss of precision from
+    // deriving them from the spatial moments.
+    for y in 0..height {
+        for x in 0..width {
+            let label = unsafe { labels.unsafe_get_pixel(x, y)[0] };
+            if label == 0 {
+                continue;
+            }
+            let acc = accumulators.get_mut(&label).unwrap();
+            let dx = x as f64 - acc.spatial[1] / acc.spatial[0];
+            let dy = y as f64 - acc.spatial[2] / acc.spatial[0];
+            let c = &mut acc.central;
+            c[0] += dx * dx;
+            c[1] += dx * dy;
+            c[2] += dy * dy;
+            c[3] += dx * dx * dx;
+            c[4] += dx * dx * dy;
+            c[5] += dx * dy * dy;
+            c[6] += dy * dy * dy;
+        }
+    }
+
+    accumulators.iter()
+        .map(|(&label, acc)| properties_from_accumulator(label, acc))
+        .collect()
+}
+
+fn properties_from_accumulator(label: u32, acc: &RegionAccumulator) -> RegionProperties {
+    let s = &acc.spatial;
+    let c = &acc.central;
+    let moments = Moments {
+        m00: s[0], m10: s[1], m01: s[2],
+        m20: s[3], m11: s[4], m02: s[5],
+        m30: s[6], m21: s[7], m12: s[8], m03: s[9],
+        mu20: c[0], mu11: c[1], mu02: c[2],
+        mu30: c[3], mu21: c[4], mu12: c[5], mu03: c[6]
+    };
+
+    // Eigenvalues of the covariance matrix of pixel positions
+    let (a, b, d) = (c[0] / s[0], c[1] / s[0], c[2] / s[0]);
+    let discriminant = (((a - d) * (a - d)) / 4.0 + b * b).sqrt();
+    let major = (a + d) / 2.0 + discriminant;
+    let minor = (a + d) / 2.0 - discriminant;
+
+    let orientation = if b == 0.0 && a >= d {
+        0f32
+    } else {
+        let angle = (0.5 * (2.0 * b).atan2(a - d)) as f32;
+        if angle <= -f32::consts::FRAC_PI_2 { angle + f32::consts::PI } else { angle }
+    };
+    let eccentricity = if major > 0.0 {
+        (1.0 - (minor / major).max(0.0)).sqrt() as f32
+    } else {
+        0f32
+    };
+
+    let convex_area = convex_area(&acc.rows);
+
+    RegionProperties {
+        label: label,
+        area: acc.area,
+        bounding_box: Rect::at(acc.min_x as i32, acc.min_y as i32)
+            .of_size(acc.max_x - acc.min_x + 1, acc.max_y - acc.min_y + 1),
+        centroid: ((s[1] / s[0]) as f32, (s[2] / s[0]) as f32),
+        perimeter: acc.perimeter as f32,
+        moments: moments,
+        orientation: orientation,
+        eccentricity: eccentricity,
+        convex_area: convex_area,
+        solidity: acc.area as f32 / convex_area
+    }
+}
+
+/// Area of the convex hull of the unit squares centred on the pixels of a region,
+/// given the leftmost and rightmost pixels of each of its rows.
+fn convex_area(rows: &[(u32, u32, u32)]) -> f32 {
+    // Square corners lie on half-integer coordinates, so double
+    // all coordinates to keep them integral.
+    let mut points = Vec::with_capacity(4 * rows.len());
+    for &(y, min_x, max_x) in rows {
+        let (top, bottom) = (2 * y as i32 - 1, 2 * y as i32 + 1);
+        let (left, right) = (2 * min_x as i32 - 1, 2 * max_x as i32 + 1);
+        points.push(Point::new(left, top));
+        points.push(Point::new(left, bottom));
+        points.push(Point::new(right, top));
+        points.push(Point::new(right, bottom));
+    }
+    (polygon_area(&convex_hull(&points)) / 4.0) as f32
+}
+
 #[cfg(test)]
 mod test {
 
     use super::{
-        connected_components
+        connected_components,
+        region_properties
     };
     use super::Connectivity::{
         Four,
@@ -158,11 +413,14 @@ mod test {
         HasBlack,
         HasWhite
     };
+    use definitions::Image;
     use image::{
         GrayImage,
         ImageBuffer,
         Luma
     };
+    use rect::Rect;
+    use std::f32;
     use test;
 
     #[test]
@@ -264,4 +522,146 @@ mod test {
             test::black_box(components);
             });
     }
+
+    fn labels_from(width: u32, height: u32, data: Vec<u32>) -> Image<Luma<u32>> {
+        ImageBuffer::from_raw(width, height, data).unwrap()
+    }
+
+    #[test]
+    fn test_region_properties_rectangle() {
+        let labels = labels_from(6, 4, vec![
+            0, 0, 0, 0, 0, 0,
+            0, 1, 1, 1, 1, 0,
+            0, 1, 1, 1, 1, 0,
+            0, 0, 0, 0, 0, 0]);
+
+        let props = region_properties(&labels);
+        assert_eq!(props.len(), 1);
+        let p = props[0];
+        assert_eq!(p.label, 1);
+        assert_eq!(p.area, 8);
+        assert_eq!(p.bounding_box, Rect::at(1, 1).of_size(4, 2));
+        assert_eq!(p.centroid, (2.5, 1.5));
+        assert_eq!(p.perimeter, 12.0);
+        assert_eq!(p.orientation, 0.0);
+        // Variances are 1.25 and 0.25
+        assert!((p.eccentricity - 0.8f32.sqrt()).abs() < 1e-6);
+        assert_eq!(p.convex_area, 8.0);
+        assert_eq!(p.solidity, 1.0);
+        assert_eq!(p.moments.m00, 8.0);
+        assert_eq!(p.moments.m10, 20.0);
+        assert_eq!(p.moments.mu20, 10.0);
+        assert_eq!(p.moments.mu02, 2.0);
+        assert_eq!(p.moments.mu11, 0.0);
+        assert_eq!(p.moments.mu30, 0.0);
+    }
+
+    #[test]
+    fn test_region_properties_l_shape() {
+        let labels = labels_from(3, 3, vec![
+            2, 2, 2,
+            2, 0, 0,
+            2, 0, 0]);
+
+        let props = region_properties(&labels);
+        assert_eq!(props.len(), 1);
+        let p = props[0];
+        assert_eq!(p.label, 2);
+        assert_eq!(p.area, 5);
+        assert_eq!(p.perimeter, 12.0);
+        // The hull is the 3x3 square minus a right triangle of area 2
+        assert_eq!(p.convex_area, 7.0);
+        assert!((p.solidity - 5.0 / 7.0).abs() < 1e-6);
+        // Symmetric about the diagonal from top left to bottom right
+        assert!((p.orientation + f32::consts::FRAC_PI_4).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_region_properties_diagonal_line() {
+        let labels = labels_from(4, 4, vec![
+            1, 0, 0, 0,
+            0, 1, 0, 0,
+            0, 0, 1, 0,
+            0, 0, 0, 1]);
+
+        let p = region_properties(&labels)[0];
+        assert!((p.orientation - f32::consts::FRAC_PI_4).abs() < 1e-6);
+        assert!((p.eccentricity - 1.0).abs() < 1e-6);
+        assert_eq!(p.perimeter, 16.0);
+        assert_eq!(p.convex_area, 7.0);
+    }
+
+    #[test]
+    fn test_region_properties_vertical_region() {
+        let labels = labels_from(1, 3, vec![1, 1, 1]);
+        let p = region_properties(&labels)[0];
+        assert!((p.orientation - f32::consts::FRAC_PI_2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_region_properties_single_pixel() {
+        let labels = labels_from(3, 3, vec![
+            0, 0, 0,
+            0, 5, 0,
+            0, 0, 0]);
+
+        let props = region_properties(&labels);
+        assert_eq!(props.len(), 1);
+        let p = props[0];
+        assert_eq!(p.label, 5);
+        assert_eq!(p.area, 1);
+        assert_eq!(p.centroid, (1.0, 1.0));
+        assert_eq!(p.perimeter, 4.0);
+        assert_eq!(p.eccentricity, 0.0);
+        assert_eq!(p.orientation, 0.0);
+        assert_eq!(p.convex_area, 1.0);
+        assert_eq!(p.moments.mu20, 0.0);
+    }
+
+    #[test]
+    fn test_region_properties_of_connected_components() {
+        let image: GrayImage = ImageBuffer::from_raw(5, 3, vec![
+            1, 1, 0, 0, 1,
+            1, 0, 0, 1, 1,
+            0, 0, 0, 0, 0]).unwrap();
+
+        let labelled = connected_components(&image, Four, Luma::black());
+        let props = region_properties(&labelled);
+
+        let summary: Vec<_> = props.iter()
+            .map(|p| (p.label, p.area, p.bounding_box))
+            .collect();
+        assert_eq!(summary, vec![
+            (1, 3, Rect::at(0, 0).of_size(2, 2)),
+            (2, 3, Rect::at(3, 0).of_size(2, 2))]);
+    }
+
+    #[test]
+    fn test_region_properties_sparse_large_labels() {
+        let max = ::std::u32::MAX;
+        let labels = labels_from(3, 2, vec![
+            max, 0, 7,
+            max, 0, 0]);
+
+        let summary: Vec<_> = region_properties(&labels).iter()
+            .map(|p| (p.label, p.area))
+            .collect();
+        assert_eq!(summary, vec![(7, 1), (max, 2)]);
+    }
+
+    #[test]
+    fn test_region_properties_empty() {
+        let labels = labels_from(2, 2, vec![0, 0, 0, 0]);
+        assert!(region_properties(&labels).is_empty());
+    }
+
+    #[bench]
+    fn bench_region_properties_four_chessboard(b: &mut test::Bencher) {
+        let image = chessboard(300, 300);
+        let components = connected_components(&image, Four, Luma::black());
+        b.iter(|| {
+            let props = region_properties(&components);
+            test::black_box(props);
+            });
+    }
 }
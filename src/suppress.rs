@@ -2,6 +2,8 @@
 
 use std::cmp;
 
+use rect::Rect;
+
 use definitions::{
     Position,
     Score
@@ -162,7 +164,7 @@ pub fn local_maxima<T>(ts: &[T], radius: u32) -> Vec<T>
 
         let mut is_max = true;
         let row_lower = if radius > cy {0} else {cy - radius};
-        let row_upper = if cy + radius + 1 > height {height} else {cy + radius + 1};
+        let row_upper = if cy + radius + 1 > height {height + 1} else {cy + radius + 1};
         for y in row_lower..row_upper {
             for c in &ts_by_row[y as usize] {
                 if c.x() + radius < cx {
@@ -197,12 +199,119 @@ pub fn local_maxima<T>(ts: &[T], radius: u32) -> Vec<T>
     max_ts
 }
 
+/// A scored bounding box, e.g. the output of a sliding-window detector.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Detection {
+    /// Region of the image containing the detected object.
+    pub rect: Rect,
+    /// Confidence of the detection. Higher is better.
+    pub score: f32,
+}
+
+impl Detection {
+    /// Construct a detection from a bounding box and score.
+    pub fn new(rect: Rect, score: f32) -> Detection {
+        Detection { rect: rect, score: score }
+    }
+}
+
+impl Score for Detection {
+    fn score(&self) -> f32 {
+        self.score
+    }
+}
+
+/// Greedy non-maximum suppression of overlapping detections. Repeatedly keeps
+/// the highest scoring remaining detection and discards all others whose
+/// intersection over union with it exceeds `overlap_threshold`.
+///
+/// Returns the kept detections in decreasing order of score. Ties are resolved
+/// in favour of the detection appearing earlier in `detections`.
+pub fn suppress_overlapping(detections: &[Detection], overlap_threshold: f32) -> Vec<Detection> {
+    let mut kept: Vec<Detection> = Vec::new();
+    for d in sorted_by_decreasing_score(detections) {
+        if kept.iter().all(|k| k.rect.iou(d.rect) <= overlap_threshold) {
+            kept.push(d);
+        }
+    }
+    kept
+}
+
+/// How `soft_suppress_overlapping` decays the scores of detections which
+/// overlap a higher scoring detection.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SoftSuppression {
+    /// Scores of detections whose intersection over union with the higher scoring
+    /// detection exceeds `overlap_threshold` are multiplied by (1 - IoU).
+    Linear {
+        /// Detections with at most this overlap are unaffected.
+        overlap_threshold: f32
+    },
+    /// All scores are multiplied by exp(-IoU^2 / `sigma`).
+    Gaussian {
+        /// Larger values give weaker suppression.
+        sigma: f32
+    },
+}
+
+/// Soft non-maximum suppression, as described in Bodla et al., "Soft-NMS -- Improving
+/// Object Detection With One Line of Code". Rather than discarding detections which
+/// overlap a higher scoring detection, their scores are reduced according to `decay`,
+/// and only discarded if they fall below `score_threshold`.
+///
+/// Returns the kept detections, with their decayed scores, in the order in which
+/// they were selected. This is decreasing order of decayed score.
+pub fn soft_suppress_overlapping(
+    detections: &[Detection],
+    decay: SoftSuppression,
+    score_threshold: f32
+) -> Vec<Detection> {
+    let mut remaining = sorted_by_decreasing_score(detections);
+    remaining.retain(|d| d.score >= score_threshold);
+    let mut kept: Vec<Detection> = Vec::new();
+
+    while !remaining.is_empty() {
+        // Take the first maximum, to resolve ties in favour of earlier detections
+        let mut best = 0;
+        for i in 1..remaining.len() {
+            if remaining[i].score > remaining[best].score {
+                best = i;
+            }
+        }
+        let selected = remaining.remove(best);
+
+        for d in &mut remaining {
+            let iou = selected.rect.iou(d.rect);
+            d.score *= match decay {
+                SoftSuppression::Linear { overlap_threshold } => {
+                    if iou > overlap_threshold { 1f32 - iou } else { 1f32 }
+                },
+                SoftSuppression::Gaussian { sigma } => (-iou * iou / sigma).exp(),
+            };
+        }
+        remaining.retain(|d| d.score >= score_threshold);
+        kept.push(selected);
+    }
+
+    kept
+}
+
+fn sorted_by_decreasing_score(detections: &[Detection]) -> Vec<Detection> {
+    let mut sorted = detections.to_vec();
+    sorted.sort_by(|c, d| d.score.partial_cmp(&c.score).unwrap_or(cmp::Ordering::Equal));
+    sorted
+}
+
 #[cfg(test)]
 mod test {
     use super::{
         local_maxima,
+        soft_suppress_overlapping,
         step,
-        suppress_non_maximum
+        suppress_non_maximum,
+        suppress_overlapping,
+        Detection,
+        SoftSuppression
     };
     use definitions::{
         Position,
@@ -223,8 +332,9 @@ mod test {
         quickcheck,
         TestResult
     };
+    use rect::Rect;
     use utils::{GrayTestImage,pixel_diff_summary};
-    use test::Bencher;
+    use test::{Bencher, black_box};
 
     #[derive(PartialEq, Debug, Copy, Clone)]
     struct T {
@@ -274,6 +384,17 @@ mod test {
         assert_eq!(max, expected);
     }
 
+    #[test]
+    fn test_local_maxima_considers_last_row() {
+        let ts = vec![
+            T::new(0, 0, 1f32),
+            T::new(0, 1, 5f32)
+        ];
+
+        let max = local_maxima(&ts, 1);
+        assert_eq!(max, vec![T::new(0, 1, 5f32)]);
+    }
+
     #[bench]
     fn bench_local_maxima_dense(b: &mut Bencher) {
         let mut ts = vec![];
@@ -440,4 +561,100 @@ mod test {
         assert_eq!(step(0, 4, 4).collect::<Vec<u32>>(), vec![0]);
         assert_eq!(step(4, 4, 4).collect::<Vec<u32>>(), vec![]);
     }
+
+    fn detection(x: i32, y: i32, size: u32, score: f32) -> Detection {
+        Detection::new(Rect::at(x, y).of_size(size, size), score)
+    }
+
+    #[test]
+    fn test_suppress_overlapping() {
+        let detections = vec![
+            detection(0, 0, 10, 0.5),
+            detection(1, 0, 10, 0.9),
+            detection(20, 20, 5, 0.3),
+            // IoU with the best detection is 50 / 150
+            detection(6, 0, 10, 0.7),
+            detection(1, 1, 10, 0.8)
+        ];
+
+        assert_eq!(suppress_overlapping(&detections, 0.5), vec![
+            detection(1, 0, 10, 0.9),
+            detection(6, 0, 10, 0.7),
+            detection(20, 20, 5, 0.3)
+        ]);
+
+        assert_eq!(suppress_overlapping(&detections, 0.2), vec![
+            detection(1, 0, 10, 0.9),
+            detection(20, 20, 5, 0.3)
+        ]);
+    }
+
+    #[test]
+    fn test_suppress_overlapping_threshold_is_inclusive() {
+        // IoU of exactly 1/3
+        let detections = vec![detection(0, 0, 4, 1.0), detection(2, 0, 4, 0.5)];
+        assert_eq!(suppress_overlapping(&detections, 1.0 / 3.0), detections);
+        assert_eq!(suppress_overlapping(&detections, 0.3), vec![detections[0]]);
+    }
+
+    #[test]
+    fn test_suppress_overlapping_ties() {
+        let detections = vec![detection(1, 0, 4, 1.0), detection(0, 0, 4, 1.0)];
+        assert_eq!(suppress_overlapping(&detections, 0.1), vec![detections[0]]);
+        assert!(suppress_overlapping(&[], 0.1).is_empty());
+    }
+
+    #[test]
+    fn test_soft_suppress_overlapping_linear() {
+        let detections = vec![
+            detection(2, 0, 4, 0.6),
+            detection(0, 0, 4, 0.9),
+            detection(10, 10, 4, 0.5)
+        ];
+        let decay = SoftSuppression::Linear { overlap_threshold: 0.3 };
+
+        // The overlapping detection's score is multiplied by 1 - 1/3
+        let kept = soft_suppress_overlapping(&detections, decay, 0.1);
+        assert_eq!(kept.len(), 3);
+        assert_eq!(kept[0], detections[1]);
+        assert_eq!(kept[1], detections[2]);
+        assert_eq!(kept[2].rect, detections[0].rect);
+        assert!((kept[2].score - 0.4).abs() < 1e-6);
+
+        // Below the score threshold after decay
+        let kept = soft_suppress_overlapping(&detections, decay, 0.45);
+        assert_eq!(kept, vec![detections[1], detections[2]]);
+
+        // Overlap not above the threshold
+        let decay = SoftSuppression::Linear { overlap_threshold: 0.5 };
+        let kept = soft_suppress_overlapping(&detections, decay, 0.1);
+        assert_eq!(kept, vec![detections[1], detections[0], detections[2]]);
+    }
+
+    #[test]
+    fn test_soft_suppress_overlapping_gaussian() {
+        let detections = vec![detection(0, 0, 4, 0.9), detection(2, 0, 4, 0.6)];
+        let decay = SoftSuppression::Gaussian { sigma: 0.5 };
+
+        let kept = soft_suppress_overlapping(&detections, decay, 0.0);
+        assert_eq!(kept[0], detections[0]);
+        let expected = 0.6 * (-(1.0f32 / 9.0) / 0.5).exp();
+        assert!((kept[1].score - expected).abs() < 1e-6);
+
+        // Equal rects are suppressed the most, but never completely
+        let detections = vec![detection(0, 0, 4, 0.9), detection(0, 0, 4, 0.6)];
+        let kept = soft_suppress_overlapping(&detections, decay, 0.0);
+        assert!((kept[1].score - 0.6 * (-2.0f32).exp()).abs() < 1e-6);
+    }
+
+    #[bench]
+    fn bench_suppress_overlapping(b: &mut Bencher) {
+        let detections: Vec<_> = (0..500)
+            .map(|i| detection((i * 7) % 200, (i * 13) % 150, 24, ((i * 31) % 97) as f32))
+            .collect();
+        b.iter(|| {
+            let kept = suppress_overlapping(&detections, 0.3);
+            black_box(kept);
+        });
+    }
 }